@@ -0,0 +1,231 @@
+//! Throughput and latency benchmarks for `Queue`, using only the public API.
+//!
+//! Every group is parameterized over capacity so regressions in the blocking
+//! paths (small capacities) and the uncontended paths (large capacities) show
+//! up separately. To compare a change against the current tree:
+//!
+//! ```text
+//! cargo bench --bench queue -- --save-baseline before
+//! # apply the change
+//! cargo bench --bench queue -- --baseline before
+//! ```
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use fifo_bounded_buffer::Queue;
+
+const CAPACITIES: [usize; 3] = [1, 16, 256];
+const ITEMS: usize = 8_000;
+
+/// Moves `ITEMS` items through a queue with `producers` and `consumers` threads.
+fn run_pipeline(capacity: usize, producers: usize, consumers: usize) {
+    let queue = Queue::new(capacity);
+    let per_producer = ITEMS / producers;
+
+    let consumer_threads: Vec<_> = (0..consumers)
+        .map(|_| {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || while black_box(q.dequeue()).is_some() {})
+        })
+        .collect();
+    let producer_threads: Vec<_> = (0..producers)
+        .map(|_| {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..per_producer {
+                    q.enqueue(i);
+                }
+            })
+        })
+        .collect();
+
+    for p in producer_threads {
+        p.join().unwrap();
+    }
+    queue.shutdown();
+    for c in consumer_threads {
+        c.join().unwrap();
+    }
+}
+
+/// Runs a 16p/16c pipeline on a capacity-4 queue and returns how many items
+/// each consumer received.
+fn run_contended(with_backoff: bool) -> Vec<usize> {
+    let queue = Queue::new(4);
+    let per_producer = ITEMS / 16;
+
+    let consumer_threads: Vec<_> = (0..16)
+        .map(|_| {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || std::iter::from_fn(|| q.dequeue()).count())
+        })
+        .collect();
+    let producer_threads: Vec<_> = (0..16)
+        .map(|_| {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..per_producer {
+                    if with_backoff {
+                        let _ = q.enqueue_with_backoff(i);
+                    } else {
+                        q.enqueue(i);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for p in producer_threads {
+        p.join().unwrap();
+    }
+    queue.shutdown();
+    consumer_threads
+        .into_iter()
+        .map(|c| c.join().unwrap())
+        .collect()
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contention/16p16c/4");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    for (name, with_backoff) in [("enqueue", false), ("enqueue_with_backoff", true)] {
+        group.bench_function(name, |b| b.iter(|| run_contended(with_backoff)));
+
+        // Fairness is not something criterion measures; report the spread of
+        // per-consumer delivery counts for one run alongside the timings.
+        let counts = run_contended(with_backoff);
+        let (min, max) = (counts.iter().min().unwrap(), counts.iter().max().unwrap());
+        eprintln!("contention/{name}: per-consumer items min {min}, max {max}");
+    }
+    group.finish();
+}
+
+fn throughput(c: &mut Criterion) {
+    for (producers, consumers) in [(1, 1), (4, 4), (8, 8)] {
+        let mut group = c.benchmark_group(format!("throughput/{producers}p{consumers}c"));
+        group.throughput(Throughput::Elements(ITEMS as u64));
+        for capacity in CAPACITIES {
+            group.bench_with_input(
+                BenchmarkId::from_parameter(capacity),
+                &capacity,
+                |b, &capacity| b.iter(|| run_pipeline(capacity, producers, consumers)),
+            );
+        }
+        group.finish();
+    }
+}
+
+fn enqueue_latency(c: &mut Criterion) {
+    let mut group = c.benchmark_group("enqueue_latency");
+    for capacity in CAPACITIES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(capacity),
+            &capacity,
+            |b, &capacity| {
+                // Only the enqueue is timed; the queue is drained between
+                // rounds so it never fills up.
+                b.iter_custom(|iters| {
+                    let queue = Queue::new(capacity);
+                    let mut drained = vec![0; capacity];
+                    let mut elapsed = Duration::ZERO;
+                    for i in 0..iters {
+                        let start = Instant::now();
+                        queue.enqueue(black_box(i));
+                        elapsed += start.elapsed();
+                        if (i + 1) % capacity as u64 == 0 {
+                            queue.try_dequeue_into(&mut drained);
+                        }
+                    }
+                    elapsed
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn wakeup_latency(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wakeup_latency");
+    for capacity in CAPACITIES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(capacity),
+            &capacity,
+            |b, &capacity| {
+                // Time from an enqueue to a consumer parked on an empty queue
+                // returning from `dequeue`.
+                b.iter_custom(|iters| {
+                    let queue = Queue::<Instant>::new(capacity);
+                    let latencies = Queue::new(1);
+
+                    let consumer = {
+                        let q = Arc::clone(&queue);
+                        let latencies = Arc::clone(&latencies);
+                        thread::spawn(move || {
+                            while let Some(sent) = q.dequeue() {
+                                latencies.enqueue(sent.elapsed());
+                            }
+                        })
+                    };
+
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        while queue.blocked_consumers() == 0 {
+                            std::hint::spin_loop();
+                        }
+                        queue.enqueue(Instant::now());
+                        total += latencies.dequeue().unwrap();
+                    }
+
+                    queue.shutdown();
+                    consumer.join().unwrap();
+                    total
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn ping_pong(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ping_pong");
+    for spins in [0, 100, 10_000] {
+        group.bench_with_input(BenchmarkId::new("spins", spins), &spins, |b, &spins| {
+            // One round trip through a pair of capacity-1 queues; both sides
+            // block on every message, so this is dominated by wakeup latency.
+            let ping = Queue::builder(1).spin_then_park(spins).build();
+            let pong = Queue::builder(1).spin_then_park(spins).build();
+            let echo = {
+                let ping = Arc::clone(&ping);
+                let pong = Arc::clone(&pong);
+                thread::spawn(move || {
+                    while let Some(n) = ping.dequeue() {
+                        pong.enqueue(n);
+                    }
+                })
+            };
+
+            b.iter(|| {
+                ping.enqueue(black_box(1u64));
+                pong.dequeue().unwrap()
+            });
+
+            ping.shutdown();
+            echo.join().unwrap();
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    throughput,
+    enqueue_latency,
+    wakeup_latency,
+    ping_pong,
+    contention
+);
+criterion_main!(benches);
@@ -0,0 +1,71 @@
+//! Graceful shutdown of a worker pool.
+//!
+//! Shows the recommended consumer loop, draining buffered work after
+//! `shutdown`, and recovering items nobody processed once every thread has
+//! exited.
+//!
+//! Run with `cargo run --example graceful_shutdown`.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use fifo_bounded_buffer::Queue;
+
+fn main() {
+    let jobs = Queue::<u32>::new(16);
+
+    // Recommended consumer loop: `dequeue` blocks while the queue is empty and
+    // returns `None` only once it is shut down *and* drained, so there is no
+    // need to poll `is_shutdown`.
+    let workers: Vec<_> = (0..3)
+        .map(|id| {
+            let jobs = Arc::clone(&jobs);
+            thread::spawn(move || {
+                let mut done = 0;
+                while let Some(job) = jobs.dequeue() {
+                    thread::sleep(Duration::from_millis(u64::from(job % 3)));
+                    done += 1;
+                }
+                println!("worker {id}: processed {done} jobs");
+                done
+            })
+        })
+        .collect();
+
+    // Phase 1: a graceful stop. Everything enqueued before `shutdown` is
+    // still delivered; enqueues after it are dropped.
+    for job in 0..40 {
+        jobs.enqueue(job);
+    }
+    jobs.shutdown();
+    jobs.enqueue(999);
+
+    let processed: u32 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert!(jobs.is_terminated());
+    println!("graceful: {processed} of 40 jobs processed, queue terminated");
+
+    // Phase 2: an urgent stop. The worker handles one batch and quits, and
+    // whatever is left in the queue is recovered once the last reference is
+    // released.
+    let jobs = Queue::<u32>::new(16);
+    let worker = {
+        let jobs = Arc::clone(&jobs);
+        thread::spawn(move || {
+            let mut buf = [0; 4];
+            let n = jobs.dequeue_into(&mut buf);
+            println!("urgent: handled {:?} before stopping", &buf[..n]);
+        })
+    };
+    for job in 0..10 {
+        jobs.enqueue(job);
+    }
+    jobs.shutdown();
+    worker.join().unwrap();
+
+    let leftovers = Queue::try_unwrap(jobs).expect("all workers have exited");
+    println!(
+        "urgent: {} leftover jobs to persist: {leftovers:?}",
+        leftovers.len()
+    );
+}
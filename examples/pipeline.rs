@@ -0,0 +1,69 @@
+//! Three-stage pipeline: parse → transform → write.
+//!
+//! Each stage reads from one queue and writes to the next. When a stage's
+//! input queue terminates (shut down and drained), its workers exit and the
+//! stage shuts down its output queue, so a single `shutdown` at the head
+//! cascades through the whole pipeline without losing items.
+//!
+//! Run with `cargo run --example pipeline`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use fifo_bounded_buffer::Queue;
+
+const INPUT: &str = "3 1 4 1 5 x 9 2 6 5 3 5 8 9 7 9 3 2 3 8 4 6 2 6 4 3 3";
+
+fn main() {
+    let raw = Queue::<String>::new(4);
+    let parsed = Queue::<u64>::new(4);
+    let squared = Queue::<u64>::new(4);
+
+    // Stage 1: parse text into numbers, skipping anything malformed.
+    let parse = {
+        let parsed = Arc::clone(&parsed);
+        raw.spawn_consumers(2, move |word| match word.parse() {
+            Ok(n) => parsed.enqueue(n),
+            Err(_) => eprintln!("parse: skipping {word:?}"),
+        })
+    };
+
+    // Stage 2: transform.
+    let transform = {
+        let squared = Arc::clone(&squared);
+        parsed.spawn_consumers(3, move |n| squared.enqueue(n * n))
+    };
+
+    // Stage 3: write in batches, flushing a partial batch after 10 ms.
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let write = {
+        let squared = Arc::clone(&squared);
+        let written = Arc::clone(&written);
+        std::thread::spawn(move || {
+            squared.consume_batches(4, Duration::from_millis(10), |batch| {
+                println!("write: {batch:?}");
+                written.lock().unwrap().extend(batch);
+            });
+        })
+    };
+
+    for word in INPUT.split_whitespace() {
+        raw.enqueue(word.to_string());
+    }
+
+    // Cascading shutdown: each stage closes its output once its input is done.
+    raw.shutdown();
+    parse.join();
+    parsed.shutdown();
+    transform.join();
+    squared.shutdown();
+    write.join().unwrap();
+
+    assert!(raw.is_terminated() && parsed.is_terminated() && squared.is_terminated());
+
+    let total: u64 = written.lock().unwrap().iter().sum();
+    println!(
+        "wrote {} values, sum of squares = {total}",
+        written.lock().unwrap().len()
+    );
+}
@@ -0,0 +1,246 @@
+//! Pinning producer and consumer threads to CPUs with `--pin-threads`.
+//!
+//! Pinning needs the `affinity` feature and Linux; elsewhere [`SUPPORTED`] is
+//! false, [`topology`] is empty and threads run wherever the scheduler puts
+//! them.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Whether this build can pin threads.
+pub const SUPPORTED: bool = cfg!(all(feature = "affinity", target_os = "linux"));
+
+/// How threads are laid out over physical cores.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinStrategy {
+    /// Give every thread its own physical core while there are enough, using
+    /// hyperthread siblings only after that.
+    Spread,
+    /// Put producer `i` and consumer `i` on sibling CPUs of one physical core.
+    Pack,
+}
+
+/// Assigns a CPU to every thread of a run.
+///
+/// Threads wrap around to the first CPUs again when there are more threads
+/// than CPUs.
+///
+/// # Arguments
+///
+/// * `cores` - Logical CPUs of each physical core, in core order.
+/// * `producers` - Number of producer threads.
+/// * `consumers` - Number of consumer threads.
+/// * `strategy` - Whether producers and consumers should share cores.
+///
+/// # Returns
+///
+/// The CPU of each thread, producers first and then consumers, or nothing if
+/// `cores` lists no CPUs.
+pub fn plan(
+    cores: &[Vec<usize>],
+    producers: usize,
+    consumers: usize,
+    strategy: PinStrategy,
+) -> Vec<usize> {
+    // CPUs in the order threads take them
+    let cpus: Vec<usize> = match strategy {
+        PinStrategy::Spread => {
+            let depth = cores.iter().map(Vec::len).max().unwrap_or(0);
+            (0..depth)
+                .flat_map(|sibling| cores.iter().filter_map(move |core| core.get(sibling)))
+                .copied()
+                .collect()
+        }
+        PinStrategy::Pack => cores.iter().flatten().copied().collect(),
+    };
+    if cpus.is_empty() {
+        return Vec::new();
+    }
+
+    // Thread indices in the order they take CPUs
+    let threads: Vec<usize> = match strategy {
+        PinStrategy::Spread => (0..producers + consumers).collect(),
+        PinStrategy::Pack => {
+            let pairs = producers.max(consumers);
+            (0..pairs)
+                .flat_map(|i| {
+                    let producer = (i < producers).then_some(i);
+                    let consumer = (i < consumers).then_some(producers + i);
+                    producer.into_iter().chain(consumer)
+                })
+                .collect()
+        }
+    };
+
+    let mut plan = vec![0; producers + consumers];
+    for (position, &thread) in threads.iter().enumerate() {
+        plan[thread] = cpus[position % cpus.len()];
+    }
+    plan
+}
+
+/// The CPUs this process may run on, grouped by physical core.
+///
+/// # Returns
+///
+/// One entry per physical core listing its logical CPUs, or nothing if
+/// pinning is not [`SUPPORTED`] or the CPUs cannot be determined.
+pub fn topology() -> Vec<Vec<usize>> {
+    #[cfg(all(feature = "affinity", target_os = "linux"))]
+    {
+        linux::topology()
+    }
+    #[cfg(not(all(feature = "affinity", target_os = "linux")))]
+    {
+        Vec::new()
+    }
+}
+
+/// Pins the calling thread to `cpu`, if there is one.
+///
+/// # Returns
+///
+/// The CPU the thread now runs on, or `None` if there was no CPU to pin to
+/// or pinning failed.
+pub fn try_pin(cpu: Option<usize>) -> Option<usize> {
+    cpu.filter(|&cpu| pin_current(cpu).is_ok())
+}
+
+/// Pins the calling thread to `cpu`.
+///
+/// # Errors
+///
+/// Returns a message if pinning is not [`SUPPORTED`] or the OS refused.
+pub fn pin_current(cpu: usize) -> Result<(), String> {
+    #[cfg(all(feature = "affinity", target_os = "linux"))]
+    {
+        linux::pin_current(cpu)
+    }
+    #[cfg(not(all(feature = "affinity", target_os = "linux")))]
+    {
+        Err(format!(
+            "cannot pin to CPU {cpu}: built without the affinity feature or not on Linux"
+        ))
+    }
+}
+
+#[cfg(all(feature = "affinity", target_os = "linux"))]
+mod linux {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io;
+    use std::mem;
+
+    /// The calling thread's allowed CPUs.
+    fn allowed_cpus() -> io::Result<Vec<usize>> {
+        // SAFETY: `set` is a plain bitmask that sched_getaffinity fills in,
+        // and the size passed is its exact size.
+        unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            if libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok((0..libc::CPU_SETSIZE as usize)
+                .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+                .collect())
+        }
+    }
+
+    /// A topology value of `cpu` from sysfs, if it can be read.
+    fn read_topology(cpu: usize, name: &str) -> Option<usize> {
+        let path = format!("/sys/devices/system/cpu/cpu{cpu}/topology/{name}");
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    pub fn topology() -> Vec<Vec<usize>> {
+        let Ok(cpus) = allowed_cpus() else {
+            return Vec::new();
+        };
+        // CPUs without topology information count as cores of their own
+        let mut cores: BTreeMap<(usize, usize, usize), Vec<usize>> = BTreeMap::new();
+        for cpu in cpus {
+            let key = match (
+                read_topology(cpu, "physical_package_id"),
+                read_topology(cpu, "core_id"),
+            ) {
+                (Some(package), Some(core)) => (package, core, 0),
+                _ => (usize::MAX, usize::MAX, cpu),
+            };
+            cores.entry(key).or_default().push(cpu);
+        }
+        cores.into_values().collect()
+    }
+
+    pub fn pin_current(cpu: usize) -> Result<(), String> {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(format!("CPU {cpu} is beyond the largest CPU set"));
+        }
+        // SAFETY: `set` is a plain bitmask, `cpu` is within it, and the size
+        // passed is its exact size.
+        let result = unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            libc::CPU_SET(cpu, &mut set);
+            libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set)
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "cannot pin to CPU {cpu}: {}",
+                io::Error::last_os_error()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four physical cores with two hyperthreads each, numbered like Linux
+    /// usually does: first siblings 0-3, second siblings 4-7.
+    fn smt_cores() -> Vec<Vec<usize>> {
+        (0..4).map(|core| vec![core, core + 4]).collect()
+    }
+
+    #[test]
+    fn test_plan_spread() {
+        let cpus = plan(&smt_cores(), 2, 2, PinStrategy::Spread);
+        assert_eq!(cpus, [0, 1, 2, 3]);
+
+        // Siblings are used once every core has a thread, then CPUs repeat
+        let cpus = plan(&smt_cores(), 6, 4, PinStrategy::Spread);
+        assert_eq!(cpus, [0, 1, 2, 3, 4, 5, 6, 7, 0, 1]);
+    }
+
+    #[test]
+    fn test_plan_pack() {
+        // producer i and consumer i on the two siblings of core i
+        let cpus = plan(&smt_cores(), 2, 2, PinStrategy::Pack);
+        assert_eq!(cpus, [0, 1, 4, 5]);
+
+        // the unpaired producer takes the next free CPU
+        let cpus = plan(&smt_cores(), 3, 1, PinStrategy::Pack);
+        assert_eq!(cpus, [0, 1, 5, 4]);
+    }
+
+    #[test]
+    fn test_plan_wraps_and_handles_no_cpus() {
+        assert_eq!(plan(&[vec![3]], 2, 1, PinStrategy::Spread), [3, 3, 3]);
+        assert_eq!(plan(&[vec![3]], 2, 1, PinStrategy::Pack), [3, 3, 3]);
+        assert!(plan(&[], 2, 2, PinStrategy::Spread).is_empty());
+        assert!(plan(&[Vec::new()], 2, 2, PinStrategy::Pack).is_empty());
+    }
+
+    #[test]
+    fn test_pin_current() {
+        if SUPPORTED {
+            let cpu = topology()[0][0];
+            assert_eq!(pin_current(cpu), Ok(()));
+        } else {
+            assert!(topology().is_empty());
+            assert!(pin_current(0).is_err());
+        }
+    }
+}
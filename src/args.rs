@@ -0,0 +1,1352 @@
+//! Command line arguments of the simulator.
+
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::affinity::{self, PinStrategy};
+use crate::chan::{Backend, QueueImpl};
+use crate::matrix::{self, Combination, TooMany};
+use crate::payload::PayloadKind;
+use crate::pipe::{PipeConfig, Transform};
+use crate::sim::{Burst, Config, DelayRange};
+use crate::threads::{PanicInjection, Role};
+use crate::warmup::WarmupPhase;
+
+/// Upper bound on producer and consumer threads.
+pub const MAX_THREADS: usize = 1024;
+
+/// Command line arguments using clap
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Simulates producers and consumers sharing a bounded FIFO queue",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Args {
+    /// Number of consumer threads; a comma-separated list runs each
+    #[arg(
+        short = 'c',
+        long,
+        default_value = "1",
+        value_delimiter = ',',
+        value_parser = thread_count
+    )]
+    pub consumers: Vec<usize>,
+
+    /// Number of producer threads; a comma-separated list runs each
+    #[arg(
+        short = 'p',
+        long,
+        default_value = "1",
+        value_delimiter = ',',
+        value_parser = thread_count
+    )]
+    pub producers: Vec<usize>,
+
+    /// Items to produce; see --items-mode for whether they are per producer
+    #[arg(short = 'i', long, default_value = "10", value_parser = positive)]
+    pub items: usize,
+
+    /// Whether -i counts items overall, divided between the producers, or per producer
+    #[arg(long, value_enum, default_value_t = ItemsMode::Total)]
+    pub items_mode: ItemsMode,
+
+    /// Accept --items-mode total with fewer items than producers, leaving some idle
+    #[arg(long, default_value_t = false)]
+    pub allow_idle_producers: bool,
+
+    /// Produce for this many seconds instead of a fixed number of items
+    #[arg(long, value_name = "SECS", conflicts_with = "items", value_parser = seconds)]
+    pub duration: Option<Duration>,
+
+    /// Capacity of the queue; a comma-separated list runs each
+    #[arg(
+        short = 's',
+        long,
+        default_value = "5",
+        value_delimiter = ',',
+        value_parser = positive
+    )]
+    pub queue_size: Vec<usize>,
+
+    /// Most combinations that comma-separated -p, -c and -s lists may expand to
+    #[arg(long, value_name = "N", default_value_t = 64, value_parser = positive)]
+    pub max_combinations: usize,
+
+    /// Chain K queues in series, with stage workers moving items between them
+    #[arg(long, value_name = "K", default_value = "1", value_parser = positive)]
+    pub stages: usize,
+
+    /// Worker threads of each of the K-1 stages between queues, e.g. 2,1 [default: 1 each]
+    #[arg(
+        long,
+        value_name = "COUNTS",
+        value_delimiter = ',',
+        value_parser = thread_count,
+        requires = "stages"
+    )]
+    pub stage_workers: Vec<usize>,
+
+    /// Nanoseconds of busy work stage workers do on each item
+    #[arg(long, value_name = "NS", default_value_t = 0, requires = "stages")]
+    pub work_ns: u64,
+
+    /// Channel implementation to pass items through
+    #[arg(long, value_enum, default_value_t = Backend::Fifo)]
+    pub backend: Backend,
+
+    /// Internals of the fifo backend, called through a trait object
+    #[arg(long, value_enum, value_name = "IMPL")]
+    pub queue_impl: Option<QueueImpl>,
+
+    /// Run the workload on every available backend and queue implementation and compare their throughput
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["backend", "queue_impl", "sweep_sizes"]
+    )]
+    pub compare: bool,
+
+    /// Pin each producer and consumer to its own CPU, round-robin (needs the affinity feature on Linux)
+    #[arg(long, default_value_t = false)]
+    pub pin_threads: bool,
+
+    /// Whether pinned producers and consumers avoid or share physical cores
+    #[arg(long, value_enum, default_value_t = PinStrategy::Spread, requires = "pin_threads")]
+    pub pin_strategy: PinStrategy,
+
+    /// Sleep a random 0-1 ms before every enqueue and dequeue; same as both delay ranges 0..1000000
+    #[arg(
+        short = 'd',
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["producer_delay_ns", "consumer_delay_ns"]
+    )]
+    pub delay: bool,
+
+    /// Sleep a random MIN..MAX nanoseconds before every enqueue; 0..0 for none
+    #[arg(long, value_name = "MIN..MAX", value_parser = delay_range)]
+    pub producer_delay_ns: Option<DelayRange>,
+
+    /// Sleep a random MIN..MAX nanoseconds before every dequeue; 0..0 for none
+    #[arg(long, value_name = "MIN..MAX", value_parser = delay_range)]
+    pub consumer_delay_ns: Option<DelayRange>,
+
+    /// Have each producer enqueue N items back to back, then pause
+    #[arg(long, value_name = "N", value_parser = positive)]
+    pub burst_size: Option<usize>,
+
+    /// Milliseconds producers pause after each burst
+    #[arg(long, value_name = "MS", default_value_t = 10, requires = "burst_size")]
+    pub burst_pause_ms: u64,
+
+    /// Seed for the random delays, so a run can be replayed [default: random]
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Extra checks beyond comparing counts, comma-separated
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "CHECKS")]
+    pub verify: Vec<Check>,
+
+    /// Same as --verify order
+    #[arg(long, default_value_t = false)]
+    pub verify_order: bool,
+
+    /// Type of value each item carries [default: bytes if --payload-bytes is given, else int]
+    #[arg(long, value_enum)]
+    pub payload_kind: Option<PayloadKind>,
+
+    /// Size of bytes and string payloads [default: 64]
+    #[arg(long, value_name = "N")]
+    pub payload_bytes: Option<usize>,
+
+    /// Measure how long items wait in the queue and report percentiles
+    #[arg(long, default_value_t = false)]
+    pub latency: bool,
+
+    /// Sample the queue's length and the items consumed every N milliseconds and
+    /// report occupancy and throughput over time
+    #[arg(long, value_name = "N", value_parser = positive_u64)]
+    pub sample_ms: Option<u64>,
+
+    /// Sample the process's resident memory and the queue's estimated size every
+    /// --sample-ms (10 ms by default) and report peak and average
+    #[arg(long, default_value_t = false)]
+    pub report_memory: bool,
+
+    /// Write the raw occupancy samples to this CSV file (requires --sample-ms)
+    #[arg(long, value_name = "PATH", requires = "sample_ms")]
+    pub trace: Option<PathBuf>,
+
+    /// Write the throughput of every sampling interval to this CSV file
+    /// (requires --sample-ms)
+    #[arg(long, value_name = "PATH", requires = "sample_ms")]
+    pub throughput_trace: Option<PathBuf>,
+
+    /// Write every enqueue, dequeue and wait of the last trial to this CSV file
+    #[arg(long, value_name = "PATH")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Most rows to write to --trace-file
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1000000",
+        value_parser = positive,
+        requires = "trace_file"
+    )]
+    pub trace_limit: usize,
+
+    /// Print produced and consumed counts, rate, items in flight and ETA to stderr every second
+    #[arg(long, default_value_t = false)]
+    pub progress: bool,
+
+    /// Abort with a diagnostic dump once no item has moved for N seconds; 0 turns the watchdog off
+    #[arg(long, value_name = "N", default_value_t = 60)]
+    pub watchdog_secs: u64,
+
+    /// Make one thread panic partway through, e.g. producer:3 or consumer:1
+    #[arg(long, value_name = "ROLE:ID", value_parser = panic_target, conflicts_with = "compare")]
+    pub inject_panic: Option<(Role, usize)>,
+
+    /// Items the --inject-panic thread handles before it panics
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 10,
+        requires = "inject_panic"
+    )]
+    pub panic_after: usize,
+
+    /// Run the workload once per comma-separated queue size, e.g. 1,2,4,8
+    #[arg(long, value_name = "SIZES", value_parser = size_list)]
+    pub sweep_sizes: Option<SizeList>,
+
+    /// Run every workload listed in this TOML file and report which passed
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = [
+            "sweep_sizes", "compare", "producers", "consumers",
+            "items", "duration", "queue_size", "delay",
+            "producer_delay_ns", "consumer_delay_ns",
+        ],
+    )]
+    pub scenarios: Option<PathBuf>,
+
+    /// Number of measured trials, each with a fresh queue
+    #[arg(long, default_value = "1", value_parser = positive)]
+    pub trials: usize,
+
+    /// Number of extra trials to run first and leave out of the statistics
+    #[arg(long, default_value_t = 0)]
+    pub warmup: usize,
+
+    /// Leave the first N items consumed in each trial out of its measurements
+    #[arg(long, value_name = "N", value_parser = positive, conflicts_with = "warmup_ms")]
+    pub warmup_items: Option<usize>,
+
+    /// Leave the first M milliseconds of each trial out of its measurements
+    #[arg(long, value_name = "M", value_parser = positive_u64)]
+    pub warmup_ms: Option<u64>,
+
+    /// Format of the results printed to stdout
+    #[arg(long, value_enum, default_value_t = Output::Human)]
+    pub output: Output,
+
+    /// Append a row with the results to this CSV file, writing a header if it is new
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<PathBuf>,
+
+    /// Log events to stderr; repeat for more detail (-v trials, -vv threads and waits, -vvv items)
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// With -vvv, log only every N-th item of each thread
+    #[arg(long, value_name = "N", default_value = "1000", value_parser = positive)]
+    pub log_every: usize,
+
+    /// Most producer or consumer threads to run [default: available parallelism]
+    #[arg(long, value_parser = thread_count)]
+    pub max_threads: Option<usize>,
+
+    /// Run every requested thread even above --max-threads
+    #[arg(long, default_value_t = false)]
+    pub oversubscribe: bool,
+
+    /// Use the queue for real work instead of a simulation
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Ways to use the queue other than simulating a workload.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Copy stdin to stdout through the queue, transforming every line on worker threads
+    Pipe(PipeArgs),
+}
+
+/// Arguments of the `pipe` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct PipeArgs {
+    /// Capacity of the queue between the reader and the workers
+    #[arg(short = 's', long, default_value = "64", value_parser = positive)]
+    pub queue_size: usize,
+
+    /// Number of worker threads
+    #[arg(short = 'w', long, default_value = "4", value_parser = thread_count)]
+    pub workers: usize,
+
+    /// Split the input into chunks of N bytes instead of lines
+    #[arg(long, value_name = "N", value_parser = positive)]
+    pub chunk_bytes: Option<usize>,
+
+    /// What the workers do to every line or chunk
+    #[arg(long, value_enum, default_value_t = Transform::None)]
+    pub transform: Transform,
+}
+
+impl PipeArgs {
+    /// The configuration of the pipe.
+    pub fn config(&self) -> PipeConfig {
+        PipeConfig {
+            queue_size: self.queue_size,
+            workers: self.workers,
+            chunk_bytes: self.chunk_bytes,
+            transform: self.transform,
+        }
+    }
+}
+
+/// Distinct queue sizes to sweep over, in the order given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeList(pub Vec<usize>);
+
+/// What `-i` counts.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemsMode {
+    /// Items produced overall, divided evenly between the producers.
+    Total,
+    /// Items produced by each producer.
+    PerThread,
+}
+
+/// A check selected with `--verify`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// Every item consumed exactly once, each producer's items in FIFO order.
+    Order,
+    /// Order-independent fingerprints of produced and consumed items match.
+    Checksum,
+}
+
+/// Format of the results printed to stdout.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Readable summary lines.
+    Human,
+    /// A single JSON object describing the run.
+    Json,
+}
+
+impl Args {
+    /// Checks combinations of arguments that clap cannot express.
+    ///
+    /// # Errors
+    ///
+    /// Returns a usage error if `--stage-workers` does not list one count per
+    /// stage between queues, or if `--queue-impl` is combined with a backend
+    /// other than fifo,
+    /// if `--items-mode total` leaves producers without items and
+    /// `--allow-idle-producers` was not given, if `--items-mode per-thread`
+    /// overflows the item count, if `--warmup-items` leaves no item to
+    /// measure, if `--inject-panic` names a thread that does not exist or is used
+    /// with a backend that cannot be closed under a blocked thread, or if
+    /// lists of `-p`, `-c` or `-s` expand to more than `--max-combinations`
+    /// runs or are combined with `--compare` or `--sweep-sizes`.
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        if self.is_matrix() {
+            if self.compare || self.sweep_sizes.is_some() {
+                return Err(Args::command().error(
+                    ErrorKind::ArgumentConflict,
+                    "lists of -p, -c or -s cannot be combined with --compare or --sweep-sizes",
+                ));
+            }
+            if let Err(err) = self.combinations() {
+                return Err(Args::command().error(ErrorKind::ValueValidation, err));
+            }
+        }
+        // Item checks must hold for every producer count listed
+        let most = self.producers.iter().copied().max().unwrap_or(1);
+        let fewest = self.producers.iter().copied().min().unwrap_or(1);
+        if self.queue_impl.is_some() && self.backend != Backend::Fifo {
+            return Err(Args::command().error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--queue-impl only applies to the fifo backend, not {}",
+                    self.backend.name()
+                ),
+            ));
+        }
+        if self.duration.is_none() {
+            match self.items_mode {
+                ItemsMode::Total if self.items < most && !self.allow_idle_producers => {
+                    return Err(Args::command().error(
+                        ErrorKind::ValueValidation,
+                        format!(
+                            "{} items leave {} of {} producers idle; raise --items or pass \
+                             --allow-idle-producers",
+                            self.items,
+                            most - self.items,
+                            most
+                        ),
+                    ));
+                }
+                ItemsMode::PerThread if self.items.checked_mul(most).is_none() => {
+                    return Err(Args::command().error(
+                        ErrorKind::ValueValidation,
+                        format!(
+                            "{} items for each of {} producers is too many",
+                            self.items, most
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+            let total = match self.items_mode {
+                ItemsMode::Total => self.items,
+                ItemsMode::PerThread => self.items * fewest,
+            };
+            if let Some(warmup) = self.warmup_items.filter(|&w| w >= total) {
+                return Err(Args::command().error(
+                    ErrorKind::ValueValidation,
+                    format!("--warmup-items {warmup} leaves none of the {total} items to measure"),
+                ));
+            }
+        }
+        if let Some((role, id)) = self.inject_panic {
+            if self.backend != Backend::Fifo {
+                return Err(Args::command().error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "--inject-panic only applies to the fifo backend, not {}",
+                        self.backend.name()
+                    ),
+                ));
+            }
+            let (threads, name) = match role {
+                Role::Producer => (fewest, "producers"),
+                _ => (
+                    self.consumers.iter().copied().min().unwrap_or(1),
+                    "consumers",
+                ),
+            };
+            if id >= threads {
+                return Err(Args::command().error(
+                    ErrorKind::ValueValidation,
+                    format!(
+                        "--inject-panic names thread {id}, but there are only {threads} {name}"
+                    ),
+                ));
+            }
+        }
+        let between = self.stages - 1;
+        if !self.stage_workers.is_empty() && self.stage_workers.len() != between {
+            return Err(Args::command().error(
+                ErrorKind::ValueValidation,
+                format!(
+                    "--stage-workers lists {} counts but --stages {} has {between} \
+                     stages between its queues",
+                    self.stage_workers.len(),
+                    self.stages
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The delay of one side: `given` if set, else what `-d` implies.
+    fn delay_range(&self, given: Option<DelayRange>) -> DelayRange {
+        given.unwrap_or(if self.delay {
+            DelayRange::UP_TO_1MS
+        } else {
+            DelayRange::NONE
+        })
+    }
+
+    /// Whether `-p`, `-c` or `-s` lists more than one value.
+    pub fn is_matrix(&self) -> bool {
+        [&self.producers, &self.consumers, &self.queue_size]
+            .iter()
+            .any(|values| values.len() > 1)
+    }
+
+    /// Every combination of the listed `-p`, `-c` and `-s` values.
+    ///
+    /// # Errors
+    ///
+    /// Returns how many combinations there would be if that is more than
+    /// `--max-combinations`.
+    pub fn combinations(&self) -> Result<Vec<Combination>, TooMany> {
+        matrix::expand(
+            &self.producers,
+            &self.consumers,
+            &self.queue_size,
+            self.max_combinations,
+        )
+    }
+
+    /// Resolves the arguments into the configuration to simulate, with the
+    /// first of any listed `-p`, `-c` and `-s` values.
+    ///
+    /// Producer and consumer counts above the thread limit are reduced to it,
+    /// unless `--oversubscribe` was given.
+    ///
+    /// # Returns
+    ///
+    /// The configuration and a warning for every thread count that was
+    /// reduced or that exceeds the limit.
+    pub fn config(&self) -> (Config, Vec<String>) {
+        self.config_for(Combination {
+            producers: self.producers[0],
+            consumers: self.consumers[0],
+            queue_size: self.queue_size[0],
+        })
+    }
+
+    /// Resolves the arguments into one configuration per combination of the
+    /// listed `-p`, `-c` and `-s` values, in [`matrix::expand`] order.
+    ///
+    /// # Returns
+    ///
+    /// The configurations and every distinct warning resolving them raised.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more combinations than `--max-combinations`,
+    /// which [`Args::validate`] rejects.
+    pub fn matrix(&self) -> (Vec<Config>, Vec<String>) {
+        let combinations = self
+            .combinations()
+            .expect("--max-combinations is checked by validate");
+        let mut warnings: Vec<String> = Vec::new();
+        let configs = combinations
+            .into_iter()
+            .map(|combination| {
+                let (config, raised) = self.config_for(combination);
+                for warning in raised {
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                }
+                config
+            })
+            .collect();
+        (configs, warnings)
+    }
+
+    /// Resolves the arguments into the configuration of one combination.
+    fn config_for(&self, combination: Combination) -> (Config, Vec<String>) {
+        let max = self.max_threads.unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, |n| n.get().min(MAX_THREADS))
+        });
+        let mut warnings = Vec::new();
+        let mut limit = |requested: usize, role: &str| {
+            if requested <= max {
+                requested
+            } else if self.oversubscribe {
+                warnings.push(format!(
+                    "running {requested} {role} threads, more than the limit of {max}"
+                ));
+                requested
+            } else {
+                warnings.push(format!(
+                    "reducing {role} threads from {requested} to {max}; \
+                     raise --max-threads or pass --oversubscribe to keep them"
+                ));
+                max
+            }
+        };
+        let producers = limit(combination.producers, "producer");
+        let consumers = limit(combination.consumers, "consumer");
+
+        let payload = self
+            .payload_kind
+            .unwrap_or(if self.payload_bytes.is_some() {
+                PayloadKind::Bytes
+            } else {
+                PayloadKind::Int
+            });
+        if payload == PayloadKind::Int && self.payload_bytes.is_some() {
+            warnings.push(String::from(
+                "ignoring --payload-bytes because int payloads have a fixed size",
+            ));
+        }
+
+        if self.sample_ms.is_some() && self.backend == Backend::StdMpsc {
+            warnings.push(String::from(
+                "not sampling the queue's length because std-mpsc cannot report it",
+            ));
+        }
+
+        let pin = (self.pin_threads && affinity::SUPPORTED).then_some(self.pin_strategy);
+        if self.pin_threads && pin.is_none() {
+            warnings.push(String::from(
+                "ignoring --pin-threads because this build cannot pin threads; \
+                 rebuild with --features affinity on Linux",
+            ));
+        }
+
+        let stage_workers = if self.stage_workers.is_empty() {
+            vec![1; self.stages - 1]
+        } else {
+            self.stage_workers.clone()
+        };
+        let mut verify_order = self.verify_order || self.verify.contains(&Check::Order);
+        if verify_order && stage_workers.iter().any(|&w| w > 1) {
+            warnings.push(String::from(
+                "ignoring --verify order because stages with several workers reorder items",
+            ));
+            verify_order = false;
+        }
+
+        let inject_panic = self.inject_panic.and_then(|(role, id)| {
+            let threads = match role {
+                Role::Producer => producers,
+                _ => consumers,
+            };
+            if id >= threads {
+                warnings.push(format!(
+                    "ignoring --inject-panic because only {threads} threads of its role remain"
+                ));
+                return None;
+            }
+            Some(PanicInjection {
+                role,
+                id,
+                after: self.panic_after,
+            })
+        });
+
+        let config = Config {
+            producers,
+            consumers,
+            items: match self.items_mode {
+                ItemsMode::Total => self.items,
+                ItemsMode::PerThread => self.items * producers,
+            },
+            duration_ms: self.duration.map(|d| d.as_millis() as u64),
+            queue_size: combination.queue_size,
+            backend: self.backend,
+            queue_impl: self.queue_impl,
+            trace_file: self.trace_file.clone(),
+            trace_limit: self.trace_limit,
+            watchdog_secs: (self.watchdog_secs > 0).then_some(self.watchdog_secs),
+            progress: self.progress,
+            inject_panic,
+            report_memory: self.report_memory,
+            warmup_phase: match (self.warmup_items, self.warmup_ms) {
+                (Some(items), _) => Some(WarmupPhase::Items(items)),
+                (None, Some(ms)) => Some(WarmupPhase::Millis(ms)),
+                (None, None) => None,
+            },
+            producer_delay: self.delay_range(self.producer_delay_ns),
+            consumer_delay: self.delay_range(self.consumer_delay_ns),
+            seed: self.seed.unwrap_or_else(rand::random),
+            burst: self.burst_size.map(|size| Burst {
+                size,
+                pause_ms: self.burst_pause_ms,
+            }),
+            verify_order,
+            checksum: self.verify.contains(&Check::Checksum),
+            latency: self.latency,
+            payload,
+            payload_bytes: self
+                .payload_bytes
+                .unwrap_or(Config::default().payload_bytes),
+            sample_ms: self.sample_ms,
+            pin,
+            log_every: self.log_every,
+            stage_workers,
+            work_ns: self.work_ns,
+        };
+        (config, warnings)
+    }
+}
+
+/// Parses a count that must be at least one.
+fn positive(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err(String::from("must be at least 1")),
+        Ok(n) => Ok(n),
+        Err(_) => Err(format!("`{s}` is not a non-negative whole number")),
+    }
+}
+
+/// Parses a `u64` that must be at least one.
+fn positive_u64(s: &str) -> Result<u64, String> {
+    positive(s).map(|n| n as u64)
+}
+
+/// Parses a comma-separated list of distinct, non-zero sizes.
+fn size_list(s: &str) -> Result<SizeList, String> {
+    let mut sizes = Vec::new();
+    for part in s.split(',') {
+        let size = positive(part.trim()).map_err(|e| format!("`{}`: {e}", part.trim()))?;
+        if sizes.contains(&size) {
+            return Err(format!("size {size} is listed more than once"));
+        }
+        sizes.push(size);
+    }
+    Ok(SizeList(sizes))
+}
+
+/// Parses a `MIN..MAX` range of nanoseconds.
+fn delay_range(s: &str) -> Result<DelayRange, String> {
+    let (min, max) = s
+        .split_once("..")
+        .ok_or_else(|| format!("`{s}` is not a MIN..MAX range"))?;
+    let bound = |part: &str| {
+        part.trim()
+            .parse::<u64>()
+            .map_err(|_| format!("`{}` is not a number of nanoseconds", part.trim()))
+    };
+    let (min_ns, max_ns) = (bound(min)?, bound(max)?);
+    if min_ns > max_ns {
+        return Err(format!("range {min_ns}..{max_ns} is inverted"));
+    }
+    Ok(DelayRange { min_ns, max_ns })
+}
+
+/// Parses a `ROLE:ID` thread, where `ROLE` is `producer` or `consumer`.
+fn panic_target(s: &str) -> Result<(Role, usize), String> {
+    let (role, id) = s
+        .split_once(':')
+        .ok_or_else(|| format!("`{s}` is not a ROLE:ID thread"))?;
+    let role = match role.trim() {
+        "producer" => Role::Producer,
+        "consumer" => Role::Consumer,
+        other => return Err(format!("`{other}` is not producer or consumer")),
+    };
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{}` is not a thread index", id.trim()))?;
+    Ok((role, id))
+}
+
+/// Parses a positive number of seconds, possibly fractional.
+fn seconds(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Duration::try_from_secs_f64(secs)
+            .ok()
+            .filter(|d| d.as_millis() > 0)
+            .ok_or_else(|| String::from("must be at least a millisecond")),
+        Ok(_) => Err(String::from("must be a positive number of seconds")),
+        Err(_) => Err(format!("`{s}` is not a number of seconds")),
+    }
+}
+
+/// Parses a thread count between one and [`MAX_THREADS`].
+fn thread_count(s: &str) -> Result<usize, String> {
+    let n = positive(s)?;
+    if n > MAX_THREADS {
+        return Err(format!("at most {MAX_THREADS} threads are supported"));
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(std::iter::once("fifo_bounded_buffer").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.consumers, [1]);
+        assert_eq!(args.producers, [1]);
+        assert_eq!(args.items, 10);
+        assert_eq!(args.queue_size, [5]);
+        assert!(!args.delay);
+        assert_eq!(args.output, Output::Human);
+        assert_eq!((args.trials, args.warmup), (1, 0));
+        assert_eq!(args.sample_ms, None);
+        assert_eq!(args.seed, None);
+        assert_eq!(parse(&["--seed", "7"]).unwrap().config().0.seed, 7);
+        assert!(parse(&["--sample-ms", "0"]).is_err());
+        assert!(parse(&["--trace", "samples.csv"]).is_err());
+        assert!(parse(&["--sample-ms", "5", "--trace", "samples.csv"]).is_ok());
+        assert!(parse(&["--throughput-trace", "rates.csv"]).is_err());
+        assert!(parse(&["--sample-ms", "5", "--throughput-trace", "rates.csv"]).is_ok());
+
+        let (config, _) = parse(&["--trace-file", "events.csv", "--trace-limit", "50"])
+            .unwrap()
+            .config();
+        assert_eq!(config.trace_file, Some(PathBuf::from("events.csv")));
+        assert_eq!(config.trace_limit, 50);
+        assert!(parse(&["--trace-limit", "50"]).is_err());
+        assert!(parse(&["--trace-file", "events.csv", "--trace-limit", "0"]).is_err());
+        assert!(parse(&["--trials", "0"]).is_err());
+        assert_eq!(parse(&["--output", "json"]).unwrap().output, Output::Json);
+        assert!(parse(&["--output", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_long_and_short_names() {
+        let long = parse(&[
+            "--consumers",
+            "3",
+            "--producers",
+            "4",
+            "--items",
+            "100",
+            "--queue-size",
+            "8",
+            "--delay",
+        ])
+        .unwrap();
+        let short = parse(&["-c", "3", "-p", "4", "-i", "100", "-s", "8", "-d"]).unwrap();
+
+        for args in [long, short] {
+            assert_eq!(
+                (
+                    args.consumers,
+                    args.producers,
+                    args.items,
+                    args.queue_size,
+                    args.delay
+                ),
+                (vec![3], vec![4], 100, vec![8], true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_zero() {
+        for flag in ["-c", "-p", "-i", "-s"] {
+            let err = parse(&[flag, "0"]).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                clap::error::ErrorKind::ValueValidation,
+                "{flag}"
+            );
+            assert!(err.to_string().contains("must be at least 1"), "{flag}");
+        }
+    }
+
+    #[test]
+    fn test_max_threads() {
+        let (config, warnings) = parse(&["-p", "16", "-c", "4", "--max-threads", "8"])
+            .unwrap()
+            .config();
+        assert_eq!((config.producers, config.consumers), (8, 4));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("from 16 to 8"));
+
+        let (config, warnings) = parse(&[
+            "-p",
+            "16",
+            "-c",
+            "4",
+            "--max-threads",
+            "8",
+            "--oversubscribe",
+        ])
+        .unwrap()
+        .config();
+        assert_eq!((config.producers, config.consumers), (16, 4));
+        assert_eq!(warnings.len(), 1);
+
+        let (config, warnings) = parse(&["-p", "2", "-c", "2", "--max-threads", "2"])
+            .unwrap()
+            .config();
+        assert_eq!((config.producers, config.consumers), (2, 2));
+        assert!(warnings.is_empty());
+        assert!(parse(&["--max-threads", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_payload_options() {
+        let (config, warnings) = parse(&[]).unwrap().config();
+        assert_eq!(
+            (config.payload, config.payload_bytes),
+            (PayloadKind::Int, 64)
+        );
+        assert!(warnings.is_empty());
+
+        let (config, _) = parse(&["--payload-bytes", "4096"]).unwrap().config();
+        assert_eq!(
+            (config.payload, config.payload_bytes),
+            (PayloadKind::Bytes, 4096)
+        );
+
+        let (config, _) = parse(&["--payload-kind", "string"]).unwrap().config();
+        assert_eq!(
+            (config.payload, config.payload_bytes),
+            (PayloadKind::String, 64)
+        );
+
+        let (_, warnings) = parse(&["--payload-kind", "int", "--payload-bytes", "8"])
+            .unwrap()
+            .config();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_verify() {
+        let config = |args: &[&str]| {
+            let (config, _) = parse(args).unwrap().config();
+            (config.verify_order, config.checksum)
+        };
+        assert_eq!(config(&[]), (false, false));
+        assert_eq!(config(&["--verify", "checksum"]), (false, true));
+        assert_eq!(config(&["--verify", "order,checksum"]), (true, true));
+        assert_eq!(config(&["--verify-order"]), (true, false));
+        assert!(parse(&["--verify", "vibes"]).is_err());
+    }
+
+    #[test]
+    fn test_duration() {
+        let (config, _) = parse(&["--duration", "1.5"]).unwrap().config();
+        assert_eq!(config.duration_ms, Some(1500));
+        assert_eq!(parse(&[]).unwrap().config().0.duration_ms, None);
+
+        assert!(parse(&["--duration", "0"]).is_err());
+        assert!(parse(&["--duration", "soon"]).is_err());
+        let err = parse(&["--duration", "5", "-i", "100"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_bursts() {
+        assert_eq!(parse(&[]).unwrap().config().0.burst, None);
+        let (config, _) = parse(&["--burst-size", "32", "--burst-pause-ms", "5"])
+            .unwrap()
+            .config();
+        assert_eq!(
+            config.burst,
+            Some(Burst {
+                size: 32,
+                pause_ms: 5
+            })
+        );
+        assert_eq!(
+            parse(&["--burst-size", "8"]).unwrap().config().0.burst,
+            Some(Burst {
+                size: 8,
+                pause_ms: 10
+            })
+        );
+        assert!(parse(&["--burst-pause-ms", "5"]).is_err());
+        assert!(parse(&["--burst-size", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_backend() {
+        assert_eq!(parse(&[]).unwrap().backend, Backend::Fifo);
+        let (config, warnings) = parse(&["--backend", "std-mpsc", "--sample-ms", "5"])
+            .unwrap()
+            .config();
+        assert_eq!(config.backend, Backend::StdMpsc);
+        assert_eq!(warnings.len(), 1);
+
+        assert!(parse(&["--compare"]).unwrap().compare);
+        assert!(parse(&["--compare", "--backend", "fifo"]).is_err());
+        assert!(parse(&["--compare", "--sweep-sizes", "1,2"]).is_err());
+        assert!(parse(&["--backend", "tokio"]).is_err());
+    }
+
+    #[test]
+    fn test_scenarios() {
+        let args = parse(&["--scenarios", "scenarios.toml", "--trials", "2"]).unwrap();
+        assert_eq!(args.scenarios, Some(PathBuf::from("scenarios.toml")));
+        assert!(parse(&["--scenarios", "s.toml", "-p", "2"]).is_err());
+        assert!(parse(&["--scenarios", "s.toml", "--compare"]).is_err());
+    }
+
+    #[test]
+    fn test_pin_threads() {
+        let (config, warnings) = parse(&["--pin-threads", "--pin-strategy", "pack"])
+            .unwrap()
+            .config();
+        if affinity::SUPPORTED {
+            assert_eq!((config.pin, warnings.len()), (Some(PinStrategy::Pack), 0));
+        } else {
+            assert_eq!((config.pin, warnings.len()), (None, 1));
+        }
+        assert_eq!(parse(&[]).unwrap().config().0.pin, None);
+        assert!(parse(&["--pin-strategy", "pack"]).is_err());
+    }
+
+    #[test]
+    fn test_verbosity() {
+        use crate::logging;
+        use log::LevelFilter;
+
+        let level = |args: &[&str]| logging::level(parse(args).unwrap().verbose);
+        assert_eq!(level(&[]), LevelFilter::Off);
+        assert_eq!(level(&["-v"]), LevelFilter::Info);
+        assert_eq!(level(&["-v", "--verbose"]), LevelFilter::Debug);
+        assert_eq!(level(&["-vvv"]), LevelFilter::Trace);
+        assert_eq!(level(&["-vvvvv"]), LevelFilter::Trace);
+
+        let (config, _) = parse(&["-vvv", "--log-every", "10"]).unwrap().config();
+        assert_eq!(config.log_every, 10);
+        assert!(parse(&["--log-every", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_watchdog_and_progress() {
+        assert_eq!(parse(&[]).unwrap().config().0.watchdog_secs, Some(60));
+        let (config, _) = parse(&["--watchdog-secs", "5"]).unwrap().config();
+        assert_eq!(config.watchdog_secs, Some(5));
+        let (config, _) = parse(&["--watchdog-secs", "0"]).unwrap().config();
+        assert_eq!(config.watchdog_secs, None);
+        assert!(!config.progress);
+        assert!(parse(&["--progress"]).unwrap().config().0.progress);
+    }
+
+    #[test]
+    fn test_inject_panic() {
+        assert_eq!(parse(&[]).unwrap().config().0.inject_panic, None);
+        let args = parse(&["-c", "2", "--oversubscribe", "--inject-panic", "consumer:1"]).unwrap();
+        assert!(args.validate().is_ok());
+        let expected = PanicInjection {
+            role: Role::Consumer,
+            id: 1,
+            after: 10,
+        };
+        assert_eq!(args.config().0.inject_panic, Some(expected));
+        let (config, _) = parse(&["--inject-panic", "producer:0", "--panic-after", "3"])
+            .unwrap()
+            .config();
+        assert_eq!(
+            config.inject_panic.map(|p| (p.role, p.after)),
+            Some((Role::Producer, 3))
+        );
+
+        let err = parse(&["--inject-panic", "producer:3"])
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("only 1 producers"), "{err}");
+        let args = parse(&["--inject-panic", "consumer:0", "--backend", "std-mpsc"]).unwrap();
+        assert!(args.validate().is_err());
+
+        let args = parse(&[
+            "-c",
+            "2",
+            "--max-threads",
+            "1",
+            "--inject-panic",
+            "consumer:1",
+        ]);
+        let (config, warnings) = args.unwrap().config();
+        assert_eq!(config.inject_panic, None);
+        assert!(
+            warnings.iter().any(|w| w.contains("--inject-panic")),
+            "{warnings:?}"
+        );
+
+        assert!(parse(&["--inject-panic", "worker:0"]).is_err());
+        assert!(parse(&["--inject-panic", "consumer"]).is_err());
+        assert!(parse(&["--inject-panic", "consumer:x"]).is_err());
+        assert!(parse(&["--panic-after", "3"]).is_err());
+        assert!(parse(&["--inject-panic", "consumer:0", "--compare"]).is_err());
+    }
+
+    #[test]
+    fn test_items_mode() {
+        let total = parse(&["-p", "4", "--oversubscribe", "-i", "100"]).unwrap();
+        assert_eq!(total.items_mode, ItemsMode::Total);
+        assert!(total.validate().is_ok());
+        assert_eq!(total.config().0.items, 100);
+
+        let args = [
+            "-p",
+            "4",
+            "--oversubscribe",
+            "-i",
+            "100",
+            "--items-mode",
+            "per-thread",
+        ];
+        let per_thread = parse(&args).unwrap();
+        assert!(per_thread.validate().is_ok());
+        let (config, _) = per_thread.config();
+        assert_eq!(config.items, 400);
+        assert_eq!(config.items_per_producer(), Some(vec![100; 4]));
+
+        // Per-thread counts follow the producers that are actually run
+        let args = [
+            "-p",
+            "4",
+            "--max-threads",
+            "2",
+            "-i",
+            "100",
+            "--items-mode",
+            "per-thread",
+        ];
+        assert_eq!(parse(&args).unwrap().config().0.items, 200);
+    }
+
+    #[test]
+    fn test_idle_producers_rejected() {
+        let err = parse(&["-p", "4", "-i", "3"])
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("leave 1 of 4 producers idle"),
+            "{err}"
+        );
+        let allowed = parse(&["-p", "4", "-i", "3", "--allow-idle-producers"]).unwrap();
+        assert!(allowed.validate().is_ok());
+        let args = ["-p", "4", "-i", "3", "--items-mode", "per-thread"];
+        assert!(parse(&args).unwrap().validate().is_ok());
+        assert!(
+            parse(&["-p", "4", "--duration", "1"])
+                .unwrap()
+                .validate()
+                .is_ok()
+        );
+
+        let huge = usize::MAX.to_string();
+        let args = ["-p", "2", "-i", &huge, "--items-mode", "per-thread"];
+        assert!(parse(&args).unwrap().validate().is_err());
+        assert!(parse(&["--items-mode", "each"]).is_err());
+    }
+
+    #[test]
+    fn test_report_memory() {
+        assert!(!parse(&[]).unwrap().config().0.report_memory);
+        let (config, warnings) = parse(&["--report-memory"]).unwrap().config();
+        assert!(config.report_memory && warnings.is_empty());
+        assert_eq!(config.sample_ms, None);
+    }
+
+    #[test]
+    fn test_warmup_phase() {
+        assert_eq!(parse(&[]).unwrap().config().0.warmup_phase, None);
+        let args = parse(&["-i", "100", "--warmup-items", "20"]).unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.config().0.warmup_phase, Some(WarmupPhase::Items(20)));
+        let (config, _) = parse(&["--warmup-ms", "50"]).unwrap().config();
+        assert_eq!(config.warmup_phase, Some(WarmupPhase::Millis(50)));
+
+        let err = parse(&["-i", "100", "--warmup-items", "100"])
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("none of the 100 items"), "{err}");
+        let args = ["-p", "2", "-i", "100", "--items-mode", "per-thread"];
+        let per_thread = parse(&[&args[..], &["--warmup-items", "150"]].concat()).unwrap();
+        assert!(per_thread.validate().is_ok());
+        assert!(
+            parse(&["--duration", "1", "--warmup-items", "100"])
+                .unwrap()
+                .validate()
+                .is_ok()
+        );
+
+        assert!(parse(&["--warmup-items", "5", "--warmup-ms", "5"]).is_err());
+        assert!(parse(&["--warmup-items", "0"]).is_err());
+        assert!(parse(&["--warmup-ms", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_pipe() {
+        assert!(parse(&[]).unwrap().command.is_none());
+        let Some(Command::Pipe(pipe)) = parse(&["pipe"]).unwrap().command else {
+            panic!("expected the pipe subcommand");
+        };
+        let expected = PipeConfig {
+            queue_size: 64,
+            workers: 4,
+            chunk_bytes: None,
+            transform: Transform::None,
+        };
+        assert_eq!(pipe.config(), expected);
+
+        let args = parse(&["pipe", "-s", "8", "-w", "2", "--chunk-bytes", "4096"]);
+        let Some(Command::Pipe(pipe)) = args.unwrap().command else {
+            panic!("expected the pipe subcommand");
+        };
+        assert_eq!(
+            (pipe.queue_size, pipe.workers, pipe.chunk_bytes),
+            (8, 2, Some(4096))
+        );
+        let args = parse(&["pipe", "--transform", "rot13"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Pipe(p)) if p.transform == Transform::Rot13));
+
+        assert!(parse(&["pipe", "--chunk-bytes", "0"]).is_err());
+        assert!(parse(&["pipe", "--transform", "lower"]).is_err());
+        assert!(parse(&["-p", "2", "pipe"]).is_err());
+    }
+
+    #[test]
+    fn test_queue_impl() {
+        let args = parse(&["--queue-impl", "mutex"]).unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.config().0.queue_impl, Some(QueueImpl::Mutex));
+        assert_eq!(parse(&[]).unwrap().config().0.queue_impl, None);
+
+        assert!(parse(&["--queue-impl", "lockfree"]).is_err());
+        let args = parse(&["--queue-impl", "mutex", "--backend", "std-mpsc"]).unwrap();
+        assert!(args.validate().is_err());
+
+        assert!(parse(&["--queue-impl", "mutex", "--compare"]).is_err());
+        assert!(parse(&["--queue-impl", "spinlock"]).is_err());
+    }
+
+    #[test]
+    fn test_stages() {
+        let (config, _) = parse(&[]).unwrap().config();
+        assert!(config.stage_workers.is_empty());
+
+        let args = parse(&["--stages", "3", "--work-ns", "500"]).unwrap();
+        assert!(args.validate().is_ok());
+        let (config, _) = args.config();
+        assert_eq!((config.stage_workers, config.work_ns), (vec![1, 1], 500));
+
+        let args = parse(&["--stages", "3", "--stage-workers", "2,4"]).unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.config().0.stage_workers, vec![2, 4]);
+
+        let args = parse(&["--stages", "3", "--stage-workers", "2"]).unwrap();
+        let err = args.validate().unwrap_err();
+        assert!(err.to_string().contains("lists 1 counts"), "{err}");
+
+        let (config, warnings) =
+            parse(&["--stages", "2", "--stage-workers", "2", "--verify-order"])
+                .unwrap()
+                .config();
+        assert!(!config.verify_order);
+        assert_eq!(warnings.len(), 1);
+
+        assert!(parse(&["--stages", "0"]).is_err());
+        assert!(parse(&["--stage-workers", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_delay_ranges() {
+        let (config, _) = parse(&[]).unwrap().config();
+        assert_eq!(
+            (config.producer_delay, config.consumer_delay),
+            (DelayRange::NONE, DelayRange::NONE)
+        );
+        let (config, _) = parse(&["-d"]).unwrap().config();
+        assert_eq!(
+            (config.producer_delay, config.consumer_delay),
+            (DelayRange::UP_TO_1MS, DelayRange::UP_TO_1MS)
+        );
+
+        let (config, _) = parse(&[
+            "--producer-delay-ns",
+            "0..0",
+            "--consumer-delay-ns",
+            "100 .. 5000",
+        ])
+        .unwrap()
+        .config();
+        assert_eq!(config.producer_delay, DelayRange::NONE);
+        assert_eq!(
+            config.consumer_delay,
+            DelayRange {
+                min_ns: 100,
+                max_ns: 5000
+            }
+        );
+        assert_eq!(config.consumer_delay.to_string(), "100..5000");
+
+        assert_eq!(
+            delay_range("7..7"),
+            Ok(DelayRange {
+                min_ns: 7,
+                max_ns: 7
+            })
+        );
+        assert!(delay_range("10..5").unwrap_err().contains("inverted"));
+        assert!(delay_range("5").is_err());
+        assert!(delay_range("..5").is_err());
+        assert!(delay_range("1..").is_err());
+        assert!(delay_range("-1..5").is_err());
+        assert!(delay_range("1...5").is_err());
+        assert!(delay_range("a..b").is_err());
+        assert!(parse(&["-d", "--producer-delay-ns", "0..10"]).is_err());
+    }
+
+    #[test]
+    fn test_sweep_sizes() {
+        let args = parse(&["--sweep-sizes", "1,2, 4,64"]).unwrap();
+        assert_eq!(args.sweep_sizes, Some(SizeList(vec![1, 2, 4, 64])));
+        assert_eq!(parse(&[]).unwrap().sweep_sizes, None);
+
+        let err = parse(&["--sweep-sizes", "1,0,4"]).unwrap_err();
+        assert!(err.to_string().contains("must be at least 1"));
+        let err = parse(&["--sweep-sizes", "1,2,1"]).unwrap_err();
+        assert!(err.to_string().contains("listed more than once"));
+        assert!(parse(&["--sweep-sizes", "1,,2"]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_too_many_threads_and_garbage() {
+        let too_many = (MAX_THREADS + 1).to_string();
+        assert!(parse(&["--producers", &too_many]).is_err());
+        assert!(parse(&["--consumers", &too_many]).is_err());
+        assert!(parse(&["--producers", &MAX_THREADS.to_string()]).is_ok());
+
+        let err = parse(&["--items", "ten"]).unwrap_err();
+        assert!(err.to_string().contains("not a non-negative whole number"));
+        assert!(parse(&["--queue-size", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_matrix() {
+        let args = parse(&["-p", "1,2", "-c", "1,4", "-s", "2,8,32", "--oversubscribe"]).unwrap();
+        assert!(args.is_matrix());
+        assert!(args.validate().is_ok());
+        let (configs, _) = args.matrix();
+        assert_eq!(configs.len(), 12);
+        assert_eq!(
+            (
+                configs[0].producers,
+                configs[0].consumers,
+                configs[0].queue_size
+            ),
+            (1, 1, 2)
+        );
+        assert_eq!(
+            (
+                configs[5].producers,
+                configs[5].consumers,
+                configs[5].queue_size
+            ),
+            (1, 4, 32)
+        );
+        assert_eq!(args.config().0.queue_size, 2);
+        assert!(!parse(&["-s", "8"]).unwrap().is_matrix());
+
+        let err = parse(&["-p", "1,2", "-s", "2,8,32", "--max-combinations", "5"])
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        assert!(err.to_string().contains("6 combinations"), "{err}");
+        let err = parse(&["-s", "2,8", "--compare"])
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        assert!(parse(&["-s", "2,0"]).is_err());
+    }
+}
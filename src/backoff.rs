@@ -0,0 +1,65 @@
+//! Bounded exponential backoff for threads that keep losing a race.
+
+use crate::sync::{spin_loop, yield_now};
+
+/// Exponent of the largest spin step; later steps yield instead.
+const SPIN_LIMIT: u32 = 6;
+
+/// Number of steps after which the backoff is considered exhausted.
+const YIELD_LIMIT: u32 = 10;
+
+/// Spins for exponentially longer on each step, then yields the thread.
+///
+/// Each step is short and bounded, so a caller that rechecks the queue's state
+/// between steps never misses a shutdown by more than one step.
+#[derive(Debug, Default)]
+pub(crate) struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for one step: `2^step` spin iterations while the step is small,
+    /// a thread yield afterwards.
+    pub(crate) fn snooze(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                spin_loop();
+            }
+        } else {
+            yield_now();
+        }
+
+        if self.step <= YIELD_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// Returns `true` once backing off further is unlikely to help and the
+    /// caller should block instead.
+    pub(crate) fn is_completed(&self) -> bool {
+        self.step > YIELD_LIMIT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completes_after_yield_limit() {
+        let mut backoff = Backoff::new();
+        for _ in 0..=YIELD_LIMIT {
+            assert!(!backoff.is_completed());
+            backoff.snooze();
+        }
+        assert!(backoff.is_completed());
+
+        // Further steps stay bounded.
+        backoff.snooze();
+        assert_eq!(backoff.step, YIELD_LIMIT + 1);
+    }
+}
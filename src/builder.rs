@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
+use crate::sync::{AtomicBool, AtomicUsize, Condvar, Mutex};
+use crate::{Inner, ItemSize, Queue};
+
+/// Builder for configuring a [`Queue`] before it is shared between threads.
+///
+/// Obtained from [`Queue::builder`]; [`Queue::new`] is equivalent to building
+/// with every option left at its default.
+///
+/// # Example
+///
+/// ```
+/// use fifo_bounded_buffer::Queue;
+///
+/// let queue = Queue::<String>::builder(16)
+///     .item_size(|s| s.capacity())
+///     .build();
+///
+/// queue.enqueue(String::from("hello"));
+/// assert!(queue.item_bytes() >= 5);
+/// ```
+pub struct QueueBuilder<T> {
+    capacity: usize,
+    item_size: Option<ItemSize<T>>,
+    spin_budget: u32,
+    fair_consumers: bool,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T> QueueBuilder<T> {
+    /// Creates a builder for a queue holding at most `capacity` items.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of elements the queue can hold.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            item_size: None,
+            spin_budget: 0,
+            fair_consumers: false,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the closure used to estimate the memory held by each item.
+    ///
+    /// The closure is called once when an item is enqueued and once when it is
+    /// dequeued, so it must return the same value for an unmodified item. Its
+    /// results feed [`Queue::item_bytes`] and [`Queue::memory_usage`].
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the approximate size in bytes of an item.
+    pub fn item_size<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T) -> usize + Send + Sync + 'static,
+    {
+        self.item_size = Some(Box::new(f));
+        self
+    }
+
+    /// Makes blocking calls spin before parking on the condition variable.
+    ///
+    /// A producer facing a full queue, or a consumer facing an empty one, first
+    /// polls the queue's state for up to `spins` iterations of
+    /// [`std::hint::spin_loop`] without holding the lock, and only parks if the
+    /// condition is still unmet. This trades CPU time for wakeup latency when
+    /// the other side usually responds within microseconds, and only pays off
+    /// with a spare core for the spinning thread; on a single core it delays
+    /// the very thread it is waiting for. Shutdown is checked on every
+    /// iteration. The default of `0` parks immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `spins` - Maximum number of spin iterations per blocking call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u64>::builder(64).spin_then_park(1_000).build();
+    /// queue.enqueue(1);
+    /// assert_eq!(queue.dequeue(), Some(1));
+    /// ```
+    pub fn spin_then_park(mut self, spins: u32) -> Self {
+        self.spin_budget = spins;
+        self
+    }
+
+    /// Hands items to blocked consumers in the order they started waiting.
+    ///
+    /// By default a new item goes to whichever consumer the condition variable
+    /// wakes, or to a consumer that arrives without waiting at all, which can
+    /// starve some consumers when items trickle in. With fair consumers, each
+    /// waiting consumer takes a ticket and every new item is granted to the
+    /// oldest ticket on arrival; newly arriving consumers queue behind existing
+    /// waiters and cannot take an item granted to one of them.
+    /// This costs a broadcast wakeup per item while several consumers wait, so
+    /// it is off by default. [`ConsumerStats::consumed`](crate::ConsumerStats::consumed)
+    /// shows how evenly items are spread.
+    ///
+    /// # Arguments
+    ///
+    /// * `fair` - Whether to deliver items in consumer arrival order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u32>::builder(8).fair_consumers(true).build();
+    /// queue.enqueue(1);
+    /// assert_eq!(queue.dequeue(), Some(1));
+    /// ```
+    pub fn fair_consumers(mut self, fair: bool) -> Self {
+        self.fair_consumers = fair;
+        self
+    }
+
+    /// Sets the clock used by timed operations.
+    ///
+    /// Only available with the `test-util` feature; see [`Queue::with_clock`].
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Builds the configured queue.
+    ///
+    /// # Returns
+    ///
+    /// A reference-counted pointer (`Arc`) to the new `Queue` instance.
+    pub fn build(self) -> Arc<Queue<T>> {
+        Arc::new(Queue {
+            inner: Mutex::new(Inner {
+                buffer: VecDeque::with_capacity(self.capacity),
+                shutdown: false,
+                blocked_producers: 0,
+                blocked_consumers: 0,
+                consumer_tickets: VecDeque::new(),
+                granted_consumers: 0,
+                next_ticket: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: self.capacity,
+            item_size: self.item_size,
+            item_bytes: AtomicUsize::new(0),
+            spin_budget: self.spin_budget,
+            fair_consumers: self.fair_consumers,
+            len_hint: AtomicUsize::new(0),
+            shutdown_hint: AtomicBool::new(false),
+            #[cfg(test)]
+            spin_iterations: AtomicUsize::new(0),
+            producers: Mutex::new(Vec::new()),
+            consumers: Mutex::new(Vec::new()),
+            clock: self.clock,
+            #[cfg(any(test, feature = "test-hooks"))]
+            hooks: Condvar::new(),
+        })
+    }
+}
+
+impl<T> fmt::Debug for QueueBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueBuilder")
+            .field("capacity", &self.capacity)
+            .field("item_size", &self.item_size.is_some())
+            .field("spin_budget", &self.spin_budget)
+            .field("fair_consumers", &self.fair_consumers)
+            .finish()
+    }
+}
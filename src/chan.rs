@@ -0,0 +1,306 @@
+//! The channel operations the simulation needs, implemented by this crate's
+//! queue and by the alternatives it is compared against.
+
+use clap::ValueEnum;
+use fifo_bounded_buffer::{DynQueue, Queue};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+
+/// A bounded multi-producer, multi-consumer channel.
+pub trait Chan<T>: Send + Sync {
+    /// Sends `item`, blocking while the channel is full. Items sent after
+    /// [`Chan::close`] are dropped.
+    fn send(&self, item: T);
+
+    /// Receives the oldest item, blocking while the channel is empty.
+    ///
+    /// # Returns
+    ///
+    /// The item, or `None` once the channel is closed and empty.
+    fn recv(&self) -> Option<T>;
+
+    /// Closes the channel; receivers drain what is left and then get `None`.
+    fn close(&self);
+
+    /// Number of items in the channel, if the channel can tell.
+    fn len(&self) -> Option<usize>;
+
+    /// Estimated bytes held by the channel, if the channel can tell.
+    fn memory_usage(&self) -> Option<usize> {
+        None
+    }
+
+    /// A short description of the channel's state for diagnostics.
+    fn state(&self) -> String {
+        match self.len() {
+            Some(len) => format!("len {len}"),
+            None => String::from("len unknown"),
+        }
+    }
+}
+
+/// Channel implementation a run uses.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// This crate's bounded FIFO queue.
+    Fifo,
+    /// `std::sync::mpsc::sync_channel`, with the receiver shared behind a mutex.
+    StdMpsc,
+    /// `crossbeam_channel::bounded`.
+    #[cfg(feature = "crossbeam")]
+    Crossbeam,
+}
+
+impl Backend {
+    /// Every backend compiled into this binary.
+    pub fn available() -> &'static [Backend] {
+        Backend::value_variants()
+    }
+
+    /// Name of the backend as given to `--backend`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Fifo => "fifo",
+            Backend::StdMpsc => "std-mpsc",
+            #[cfg(feature = "crossbeam")]
+            Backend::Crossbeam => "crossbeam",
+        }
+    }
+}
+
+/// Internals of the fifo backend, reached through [`DynQueue`] with
+/// `--queue-impl`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueImpl {
+    /// The crate's `Queue`, a `std::sync::Mutex` with two condition variables.
+    Mutex,
+}
+
+impl QueueImpl {
+    /// Every implementation, in the order `--compare` runs them.
+    pub fn available() -> Vec<QueueImpl> {
+        QueueImpl::value_variants().to_vec()
+    }
+
+    /// Name of the implementation as given to `--queue-impl`.
+    pub fn name(self) -> &'static str {
+        match self {
+            QueueImpl::Mutex => "mutex",
+        }
+    }
+
+    /// Creates a queue holding at most `capacity` items.
+    pub fn build<T: Send + 'static>(self, capacity: usize) -> Arc<dyn DynQueue<T>> {
+        match self {
+            QueueImpl::Mutex => Queue::new(capacity),
+        }
+    }
+}
+
+impl<T: Send> Chan<T> for Queue<T> {
+    fn send(&self, item: T) {
+        self.enqueue(item);
+    }
+
+    fn recv(&self) -> Option<T> {
+        self.dequeue()
+    }
+
+    fn close(&self) {
+        self.shutdown();
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(Queue::len(self))
+    }
+
+    fn memory_usage(&self) -> Option<usize> {
+        Some(Queue::memory_usage(self))
+    }
+
+    fn state(&self) -> String {
+        format!(
+            "len {}, blocked producers {}, blocked consumers {}, shut down {}",
+            Queue::len(self),
+            self.blocked_producers(),
+            self.blocked_consumers(),
+            self.is_shutdown()
+        )
+    }
+}
+
+/// A queue chosen with `--queue-impl`, every call going through [`DynQueue`].
+pub struct DynChan<T>(pub Arc<dyn DynQueue<T>>);
+
+impl<T: Send> Chan<T> for DynChan<T> {
+    fn send(&self, item: T) {
+        self.0.enqueue(item);
+    }
+
+    fn recv(&self) -> Option<T> {
+        self.0.dequeue()
+    }
+
+    fn close(&self) {
+        self.0.shutdown();
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// A `std::sync::mpsc` sync channel shared by several producers and consumers.
+///
+/// Producers share the sender behind a read lock so [`Chan::close`] can drop
+/// it; consumers take turns on the receiver.
+pub struct StdMpsc<T> {
+    sender: RwLock<Option<mpsc::SyncSender<T>>>,
+    receiver: Mutex<mpsc::Receiver<T>>,
+}
+
+impl<T> StdMpsc<T> {
+    /// Creates a channel holding at most `capacity` items.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        Self {
+            sender: RwLock::new(Some(sender)),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl<T: Send> Chan<T> for StdMpsc<T> {
+    fn send(&self, item: T) {
+        if let Some(sender) = &*self.sender.read().unwrap() {
+            let _ = sender.send(item);
+        }
+    }
+
+    fn recv(&self) -> Option<T> {
+        self.receiver.lock().unwrap().recv().ok()
+    }
+
+    fn close(&self) {
+        self.sender.write().unwrap().take();
+    }
+
+    fn len(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A `crossbeam_channel` bounded channel.
+#[cfg(feature = "crossbeam")]
+pub struct Crossbeam<T> {
+    sender: RwLock<Option<crossbeam_channel::Sender<T>>>,
+    receiver: crossbeam_channel::Receiver<T>,
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T> Crossbeam<T> {
+    /// Creates a channel holding at most `capacity` items.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(capacity);
+        Self {
+            sender: RwLock::new(Some(sender)),
+            receiver,
+        }
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> Chan<T> for Crossbeam<T> {
+    fn send(&self, item: T) {
+        if let Some(sender) = &*self.sender.read().unwrap() {
+            let _ = sender.send(item);
+        }
+    }
+
+    fn recv(&self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+
+    fn close(&self) {
+        self.sender.write().unwrap().take();
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.receiver.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Sends 0..100 from two producers through `chan` to two consumers.
+    fn exchange(chan: Arc<dyn Chan<usize>>) {
+        let producers: Vec<_> = (0..2)
+            .map(|p| {
+                let chan = Arc::clone(&chan);
+                thread::spawn(move || (0..50).for_each(|i| chan.send(p * 50 + i)))
+            })
+            .collect();
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let chan = Arc::clone(&chan);
+                thread::spawn(move || std::iter::from_fn(|| chan.recv()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        producers.into_iter().for_each(|p| p.join().unwrap());
+        chan.close();
+        let mut received: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        received.sort_unstable();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+        assert_eq!(chan.recv(), None);
+        assert!(chan.len().is_none_or(|n| n == 0));
+    }
+
+    #[test]
+    fn test_fifo() {
+        let queue: Arc<Queue<usize>> = Queue::new(4);
+        exchange(queue);
+    }
+
+    #[test]
+    fn test_queue_impls() {
+        for imp in QueueImpl::available() {
+            exchange(Arc::new(DynChan(imp.build(4))));
+        }
+    }
+
+    #[test]
+    fn test_std_mpsc() {
+        exchange(Arc::new(StdMpsc::new(4)));
+    }
+
+    #[cfg(feature = "crossbeam")]
+    #[test]
+    fn test_crossbeam() {
+        exchange(Arc::new(Crossbeam::new(4)));
+    }
+
+    #[test]
+    fn test_send_after_close_is_dropped() {
+        let chan = StdMpsc::new(1);
+        chan.close();
+        chan.send(1);
+        assert_eq!(chan.recv(), None);
+    }
+
+    #[test]
+    fn test_names_parse_back() {
+        for &backend in Backend::available() {
+            assert_eq!(Backend::from_str(backend.name(), false), Ok(backend));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Order-independent fingerprints of the items each side of the queue saw.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::verify::Tagged;
+
+/// Accumulates a 64-bit hash of every item with both a wrapping sum and an
+/// xor, so the result does not depend on the order items were seen in.
+///
+/// A duplicated item changes the sum even when a dropped one cancels its
+/// count, and substituting one item for another changes both. Each thread
+/// keeps its own checksum and they are combined with [`Checksum::merge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checksum {
+    /// Number of items added.
+    pub count: u64,
+    /// Wrapping sum of the item hashes.
+    pub sum: u64,
+    /// Xor of the item hashes.
+    pub xor: u64,
+}
+
+/// Spreads the producer and sequence number over all 64 bits (splitmix64).
+fn hash(tag: Tagged) -> u64 {
+    let mut z = ((tag.0 as u64) << 40 ^ tag.1 as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Checksum {
+    /// Adds one item.
+    pub fn add(&mut self, tag: Tagged) {
+        let h = hash(tag);
+        self.count += 1;
+        self.sum = self.sum.wrapping_add(h);
+        self.xor ^= h;
+    }
+
+    /// Adds everything accumulated in `other`.
+    pub fn merge(&mut self, other: &Checksum) {
+        self.count += other.count;
+        self.sum = self.sum.wrapping_add(other.sum);
+        self.xor ^= other.xor;
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "count {} sum {:016x} xor {:016x}",
+            self.count, self.sum, self.xor
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn of(tags: &[Tagged]) -> Checksum {
+        let mut c = Checksum::default();
+        for &tag in tags {
+            c.add(tag);
+        }
+        c
+    }
+
+    #[test]
+    fn test_order_independent_and_mergeable() {
+        let tags = [(0, 0), (0, 1), (1, 0), (1, 1), (2, 7)];
+        let reversed: Vec<_> = tags.iter().rev().copied().collect();
+        assert_eq!(of(&tags), of(&reversed));
+
+        let mut merged = of(&tags[..2]);
+        merged.merge(&of(&tags[2..]));
+        assert_eq!(merged, of(&tags));
+    }
+
+    #[test]
+    fn test_detects_what_counts_miss() {
+        let expected = of(&[(0, 0), (0, 1), (1, 0)]);
+        // Same count, one item swapped for another
+        assert_ne!(of(&[(0, 0), (0, 1), (1, 1)]), expected);
+        // Same count, one item duplicated and another dropped
+        assert_ne!(of(&[(0, 0), (0, 0), (1, 0)]), expected);
+        // Producer and sequence number transposed
+        assert_ne!(of(&[(0, 1)]), of(&[(1, 0)]));
+    }
+}
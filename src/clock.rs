@@ -0,0 +1,103 @@
+//! Time source used by the queue's timed operations.
+//!
+//! Production queues use [`SystemClock`]. With the `test-util` feature, a queue
+//! can be built with a [`MockClock`] so timeout behavior is tested without
+//! depending on real elapsed time.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::Mutex;
+
+/// A source of time for timeouts and blocked-time accounting.
+///
+/// Condition-variable waits still happen on the queue's own condvars;
+/// [`Clock::wait_timeout`] only decides how long such a wait may block in real
+/// time, which lets a mock clock resolve timed waits immediately.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Returns the real time a wait with the given `timeout` should block for.
+    fn wait_timeout(&self, timeout: Duration) -> Duration;
+}
+
+/// The real clock, backed by [`Instant::now`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> Duration {
+        timeout
+    }
+}
+
+/// A manually advanced clock for deterministic tests.
+///
+/// Time only moves when [`MockClock::advance`] is called or when the queue
+/// performs a timed wait: such a wait advances the clock by its full timeout and
+/// returns immediately, as if nothing happened during the wait.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use fifo_bounded_buffer::{Clock, MockClock};
+///
+/// let clock = MockClock::new();
+/// let start = clock.now();
+///
+/// clock.advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// Creates a mock clock frozen at the current instant.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Returns how far the clock has moved since it was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> Duration {
+        self.advance(timeout);
+        Duration::ZERO
+    }
+}
@@ -0,0 +1,90 @@
+//! Object-safe queue operations, so callers can pick a queue implementation
+//! at run time.
+
+use crate::Queue;
+
+/// The blocking operations shared by every bounded queue implementation.
+///
+/// Unlike [`Queue`], which is used through its inherent methods, this trait
+/// can be used as `dyn DynQueue<T>`. Each call then goes through a vtable,
+/// which costs an indirect call per operation.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use fifo_bounded_buffer::{DynQueue, Queue};
+///
+/// let queue: Arc<dyn DynQueue<u32>> = Queue::new(2);
+/// queue.enqueue(1);
+/// queue.shutdown();
+///
+/// assert_eq!(queue.len(), 1);
+/// assert_eq!(queue.dequeue(), Some(1));
+/// assert_eq!(queue.dequeue(), None);
+/// ```
+pub trait DynQueue<T>: Send + Sync {
+    /// Adds `item` at the back, blocking while the queue is full. Items
+    /// enqueued after [`DynQueue::shutdown`] are dropped.
+    fn enqueue(&self, item: T);
+
+    /// Removes the item at the front, blocking while the queue is empty.
+    ///
+    /// # Returns
+    ///
+    /// The item, or `None` once the queue is shut down and empty.
+    fn dequeue(&self) -> Option<T>;
+
+    /// Stops accepting items and wakes every blocked caller.
+    fn shutdown(&self);
+
+    /// Number of items currently buffered.
+    fn len(&self) -> usize;
+
+    /// Whether no items are currently buffered.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Send> DynQueue<T> for Queue<T> {
+    fn enqueue(&self, item: T) {
+        Queue::enqueue(self, item);
+    }
+
+    fn dequeue(&self) -> Option<T> {
+        Queue::dequeue(self)
+    }
+
+    fn shutdown(&self) {
+        Queue::shutdown(self);
+    }
+
+    fn len(&self) -> usize {
+        Queue::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_through_trait_object() {
+        let queue: Arc<dyn DynQueue<usize>> = Queue::new(2);
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || (0..100).for_each(|i| queue.enqueue(i)))
+        };
+        let received: Vec<_> = (0..100).map_while(|_| queue.dequeue()).collect();
+        producer.join().unwrap();
+
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+        queue.shutdown();
+        queue.enqueue(7);
+        assert_eq!(queue.dequeue(), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Ways a run can fail, and the exit status each one maps to.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::sim::TrialFailure;
+
+/// Exit status of a trial that lost, duplicated, corrupted or reordered items.
+pub const EXIT_VERIFICATION: u8 = 1;
+/// Exit status of a command line that could not be parsed, as clap uses.
+pub const EXIT_USAGE: u8 = 2;
+/// Exit status of a run the watchdog found making no progress.
+pub const EXIT_STALLED: u8 = 3;
+/// Exit status of a `pipe` that could not read its input or write its
+/// output, as `EX_IOERR` of sysexits.h.
+pub const EXIT_IO: u8 = 74;
+/// Exit status of a run stopped by Ctrl-C, as shells report for SIGINT.
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Why the simulator did not finish successfully.
+#[derive(Debug)]
+pub enum SimError {
+    /// The command line was rejected, or asked for help or the version.
+    Usage(clap::Error),
+    /// A trial's outcome failed verification.
+    Verification(TrialFailure),
+    /// The `--scenarios` file could not be read or parsed.
+    ScenarioFile(String),
+    /// Some scenarios of a `--scenarios` run failed verification.
+    ScenariosFailed { failed: usize, total: usize },
+    /// The run was stopped by Ctrl-C after its partial results were reported.
+    Interrupted,
+    /// The `pipe` subcommand failed to read stdin or write stdout.
+    Pipe(io::Error),
+}
+
+impl SimError {
+    /// Exit status the process should end with.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // Help and version requests are errors to clap but exit with 0
+            SimError::Usage(err) if !err.use_stderr() => 0,
+            SimError::Usage(_) | SimError::ScenarioFile(_) => EXIT_USAGE,
+            SimError::Verification(_) | SimError::ScenariosFailed { .. } => EXIT_VERIFICATION,
+            SimError::Interrupted => EXIT_INTERRUPTED,
+            SimError::Pipe(_) => EXIT_IO,
+        }
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimError::Usage(err) => write!(f, "{err}"),
+            SimError::Verification(failure) => write!(f, "{failure}"),
+            SimError::ScenarioFile(err) => write!(f, "invalid scenario file {err}"),
+            SimError::ScenariosFailed { failed, total } => {
+                write!(f, "{failed} of {total} scenarios failed")
+            }
+            SimError::Interrupted => write!(f, "interrupted by Ctrl-C"),
+            SimError::Pipe(err) => write!(f, "pipe failed: {err}"),
+        }
+    }
+}
+
+impl Error for SimError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SimError::Usage(err) => Some(err),
+            SimError::Pipe(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<clap::Error> for SimError {
+    fn from(err: clap::Error) -> Self {
+        SimError::Usage(err)
+    }
+}
+
+impl From<TrialFailure> for SimError {
+    fn from(failure: TrialFailure) -> Self {
+        SimError::Verification(failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Args;
+    use crate::sim::{self, Config};
+    use clap::Parser;
+    use std::sync::Arc;
+
+    fn usage(args: &[&str]) -> SimError {
+        let args = std::iter::once("fifo_bounded_buffer").chain(args.iter().copied());
+        Args::try_parse_from(args).unwrap_err().into()
+    }
+
+    fn verification() -> SimError {
+        let config = Config {
+            items: 20,
+            ..Config::default()
+        };
+        let mut outcome = sim::run(&config, &Arc::default());
+        outcome.consumed -= 1;
+        TrialFailure {
+            config: Box::new(config),
+            trial: 0,
+            outcome: Box::new(outcome),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(usage(&["--items", "0"]).exit_code(), EXIT_USAGE);
+        assert_eq!(usage(&["--bogus"]).exit_code(), EXIT_USAGE);
+        assert_eq!(usage(&["--help"]).exit_code(), 0);
+        assert_eq!(usage(&["--version"]).exit_code(), 0);
+        assert_eq!(verification().exit_code(), EXIT_VERIFICATION);
+        assert_eq!(SimError::Interrupted.exit_code(), EXIT_INTERRUPTED);
+        let pipe = SimError::Pipe(io::ErrorKind::InvalidData.into());
+        assert_eq!(pipe.exit_code(), EXIT_IO);
+        assert_eq!(
+            SimError::ScenarioFile(String::from("x.toml: no such file")).exit_code(),
+            EXIT_USAGE
+        );
+        let failed = SimError::ScenariosFailed {
+            failed: 1,
+            total: 3,
+        };
+        assert_eq!(failed.exit_code(), EXIT_VERIFICATION);
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            verification().to_string(),
+            "trial 1 (queue size 5, fifo): requested 20, produced 20, consumed 19"
+        );
+        assert_eq!(SimError::Interrupted.to_string(), "interrupted by Ctrl-C");
+        let failed = SimError::ScenariosFailed {
+            failed: 1,
+            total: 3,
+        };
+        assert_eq!(failed.to_string(), "1 of 3 scenarios failed");
+    }
+}
@@ -0,0 +1,327 @@
+//! Every enqueue, dequeue and wait of a run, written to `--trace-file` for
+//! plotting.
+//!
+//! Simulation threads collect events in small local batches and hand full
+//! batches to a bounded [`Queue`] read by a writer thread of its own, so
+//! they never touch the file themselves.
+
+use fifo_bounded_buffer::Queue;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Lines at the top of a trace file describing its columns.
+pub const HEADER: &str = "\
+# elapsed_us: microseconds since the trial started
+# event: enqueue, dequeue, block-start or block-end
+# queue_len: items in the queue right after the event, empty if the backend cannot tell
+elapsed_us,event,queue_len";
+
+/// Events a thread collects before handing them to the writer.
+const BATCH: usize = 256;
+/// Batches that may wait for the writer before recording threads block.
+const RING_BATCHES: usize = 64;
+
+/// What happened to the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// A producer added an item.
+    Enqueue,
+    /// A consumer removed an item.
+    Dequeue,
+    /// A thread found the queue full, or empty, and is about to wait.
+    BlockStart,
+    /// A thread that found the queue full or empty got through.
+    BlockEnd,
+}
+
+impl EventKind {
+    /// Name of the event in the `event` column.
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Enqueue => "enqueue",
+            EventKind::Dequeue => "dequeue",
+            EventKind::BlockStart => "block-start",
+            EventKind::BlockEnd => "block-end",
+        }
+    }
+}
+
+/// One row of a trace file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// Microseconds since the trial started.
+    pub elapsed_us: u64,
+    /// What happened.
+    pub kind: EventKind,
+    /// Items in the queue right after the event, if the backend can tell.
+    pub queue_len: Option<usize>,
+}
+
+impl Event {
+    /// Formats the event as a CSV row without a trailing newline.
+    pub fn row(&self) -> String {
+        let len = self
+            .queue_len
+            .map_or_else(String::new, |len| len.to_string());
+        format!("{},{},{len}", self.elapsed_us, self.kind.name())
+    }
+
+    /// Parses a row written by [`Event::row`].
+    ///
+    /// # Returns
+    ///
+    /// The event, or `None` if the row is malformed.
+    #[cfg(test)]
+    pub fn parse(row: &str) -> Option<Self> {
+        let mut fields = row.split(',');
+        let elapsed_us = fields.next()?.parse().ok()?;
+        let kind = match fields.next()? {
+            "enqueue" => EventKind::Enqueue,
+            "dequeue" => EventKind::Dequeue,
+            "block-start" => EventKind::BlockStart,
+            "block-end" => EventKind::BlockEnd,
+            _ => return None,
+        };
+        let queue_len = match fields.next()? {
+            "" => None,
+            len => Some(len.parse().ok()?),
+        };
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            elapsed_us,
+            kind,
+            queue_len,
+        })
+    }
+}
+
+/// A trace file being written by its writer thread.
+pub struct EventLog {
+    ring: Arc<Queue<Vec<Event>>>,
+    start: Instant,
+    /// Rows that may still be recorded before the limit is reached.
+    remaining: AtomicUsize,
+    writer: JoinHandle<io::Result<usize>>,
+}
+
+impl EventLog {
+    /// Creates the trace file and starts its writer thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File to create or truncate.
+    /// * `limit` - Most rows to write; later events are not recorded.
+    /// * `start` - When the trial started; events are stamped relative to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from creating the file.
+    pub fn create(path: &Path, limit: usize, start: Instant) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let ring: Arc<Queue<Vec<Event>>> = Queue::new(RING_BATCHES);
+        let writer = {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || {
+                writeln!(out, "{HEADER}")?;
+                let mut rows = 0;
+                while let Some(batch) = ring.dequeue() {
+                    for event in &batch {
+                        writeln!(out, "{}", event.row())?;
+                    }
+                    rows += batch.len();
+                }
+                out.flush()?;
+                Ok(rows)
+            })
+        };
+        Ok(Self {
+            ring,
+            start,
+            remaining: AtomicUsize::new(limit),
+            writer,
+        })
+    }
+
+    /// A recorder for one simulation thread.
+    pub fn recorder(self: &Arc<Self>) -> Recorder {
+        Recorder {
+            log: Arc::clone(self),
+            batch: Vec::with_capacity(BATCH),
+            allowed: 0,
+            full: false,
+        }
+    }
+
+    /// Waits for the writer to write every batch handed to it.
+    ///
+    /// Every [`Recorder`] must have been dropped first, so their last
+    /// batches are in.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing the file.
+    pub fn finish(self) -> io::Result<usize> {
+        self.ring.shutdown();
+        self.writer.join().expect("trace writer panicked")
+    }
+}
+
+/// Collects the events of one thread in batches for the writer.
+pub struct Recorder {
+    log: Arc<EventLog>,
+    batch: Vec<Event>,
+    /// Rows claimed from the limit but not yet recorded.
+    allowed: usize,
+    /// Whether the limit has been reached.
+    full: bool,
+}
+
+impl Recorder {
+    /// Records `kind` with the queue now holding `queue_len` items, unless
+    /// the file has reached its row limit.
+    pub fn record(&mut self, kind: EventKind, queue_len: Option<usize>) {
+        if self.full {
+            return;
+        }
+        if self.allowed == 0 {
+            let claimed = self
+                .log
+                .remaining
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                    (left > 0).then(|| left.saturating_sub(BATCH))
+                })
+                .map_or(0, |left| left.min(BATCH));
+            if claimed == 0 {
+                self.full = true;
+                self.flush();
+                return;
+            }
+            self.allowed = claimed;
+        }
+        self.allowed -= 1;
+        self.batch.push(Event {
+            elapsed_us: self.log.start.elapsed().as_micros() as u64,
+            kind,
+            queue_len,
+        });
+        if self.batch.len() == BATCH {
+            self.flush();
+        }
+    }
+
+    /// Hands the events collected so far to the writer.
+    fn flush(&mut self) {
+        if !self.batch.is_empty() {
+            let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(BATCH));
+            self.log.ring.enqueue(batch);
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A trace file path unique to `name` in the temp directory.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("fifo-events-{}-{name}.csv", std::process::id()))
+    }
+
+    /// The events of a trace file, after checking its header.
+    fn read_back(path: &Path) -> Vec<Event> {
+        let text = fs::read_to_string(path).unwrap();
+        let mut lines = text.lines();
+        for line in HEADER.lines() {
+            assert_eq!(lines.next(), Some(line));
+        }
+        lines
+            .map(|row| Event::parse(row).unwrap_or_else(|| panic!("bad row {row:?}")))
+            .collect()
+    }
+
+    #[test]
+    fn test_rows_parse_back() {
+        let event = Event {
+            elapsed_us: 1234,
+            kind: EventKind::BlockStart,
+            queue_len: Some(8),
+        };
+        assert_eq!(event.row(), "1234,block-start,8");
+        assert_eq!(Event::parse(&event.row()), Some(event));
+
+        let unknown = Event {
+            kind: EventKind::Dequeue,
+            queue_len: None,
+            ..event
+        };
+        assert_eq!(unknown.row(), "1234,dequeue,");
+        assert_eq!(Event::parse(&unknown.row()), Some(unknown));
+
+        assert_eq!(Event::parse("1,wait,2"), None);
+        assert_eq!(Event::parse("x,enqueue,2"), None);
+        assert_eq!(Event::parse("1,enqueue,2,3"), None);
+        assert_eq!(Event::parse("1,enqueue"), None);
+    }
+
+    #[test]
+    fn test_writer_drains_everything() {
+        let path = temp_path("drain");
+        let log = Arc::new(EventLog::create(&path, usize::MAX, Instant::now()).unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let mut recorder = log.recorder();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        recorder.record(EventKind::Enqueue, Some(t * 1000 + i));
+                    }
+                })
+            })
+            .collect();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+
+        let log = Arc::into_inner(log).unwrap();
+        assert_eq!(log.finish().unwrap(), 4000);
+        let mut lens: Vec<_> = read_back(&path)
+            .iter()
+            .map(|e| e.queue_len.unwrap())
+            .collect();
+        lens.sort_unstable();
+        assert_eq!(lens, (0..4000).collect::<Vec<_>>());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_limit_bounds_rows() {
+        let path = temp_path("limit");
+        let log = Arc::new(EventLog::create(&path, 300, Instant::now()).unwrap());
+        {
+            let (mut a, mut b) = (log.recorder(), log.recorder());
+            for _ in 0..500 {
+                a.record(EventKind::Dequeue, None);
+                b.record(EventKind::BlockEnd, Some(0));
+            }
+        }
+        let log = Arc::into_inner(log).unwrap();
+        assert_eq!(log.finish().unwrap(), 300);
+        assert_eq!(read_back(&path).len(), 300);
+        fs::remove_file(&path).unwrap();
+    }
+}
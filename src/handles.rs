@@ -0,0 +1,755 @@
+//! Labeled producer and consumer handles that attribute queue traffic to individual callers.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::Queue;
+
+/// Counters shared by every handle registered under the same producer label.
+#[derive(Debug)]
+pub(crate) struct ProducerCounters {
+    label: String,
+    enqueued: AtomicU64,
+    rejected: AtomicU64,
+    blocked_nanos: AtomicU64,
+}
+
+impl ProducerCounters {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            enqueued: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            blocked_nanos: AtomicU64::new(0),
+        }
+    }
+
+    fn snapshot(&self) -> ProducerStats {
+        ProducerStats {
+            label: self.label.clone(),
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            blocked: Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A snapshot of the counters accumulated by one producer label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducerStats {
+    /// Label the producer was registered under.
+    pub label: String,
+    /// Number of items successfully enqueued.
+    pub enqueued: u64,
+    /// Number of items refused because the queue was shut down.
+    pub rejected: u64,
+    /// Total time spent blocked waiting for space.
+    pub blocked: Duration,
+}
+
+/// Counters shared by every handle registered under the same consumer label.
+#[derive(Debug)]
+pub(crate) struct ConsumerCounters {
+    label: String,
+    consumed: AtomicU64,
+    blocked_nanos: AtomicU64,
+    last_activity: Mutex<Option<Instant>>,
+}
+
+impl ConsumerCounters {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            consumed: AtomicU64::new(0),
+            blocked_nanos: AtomicU64::new(0),
+            last_activity: Mutex::new(None),
+        }
+    }
+
+    fn snapshot(&self) -> ConsumerStats {
+        ConsumerStats {
+            label: self.label.clone(),
+            consumed: self.consumed.load(Ordering::Relaxed),
+            blocked: Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed)),
+            last_activity: *self.last_activity.lock().unwrap(),
+        }
+    }
+}
+
+/// A snapshot of the counters accumulated by one consumer label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerStats {
+    /// Label the consumer was registered under.
+    pub label: String,
+    /// Number of items dequeued.
+    pub consumed: u64,
+    /// Total time spent blocked waiting for items.
+    pub blocked: Duration,
+    /// When an item was last dequeued, or `None` if none has been yet.
+    pub last_activity: Option<Instant>,
+}
+
+/// A labeled producer attached to a [`Queue`].
+///
+/// Enqueue operations delegate to the queue while also updating counters for the
+/// handle's label. Handles are cheap to clone; clones, and any other handle
+/// requested under the same label, share one set of counters. The counters
+/// outlive the handles and stay available through [`Queue::producer_stats`].
+///
+/// # Example
+///
+/// ```
+/// use fifo_bounded_buffer::Queue;
+///
+/// let queue = Queue::new(4);
+/// let ingest = queue.producer("ingest");
+///
+/// ingest.enqueue(1);
+/// ingest.enqueue(2);
+/// assert_eq!(ingest.stats().enqueued, 2);
+/// ```
+pub struct ProducerHandle<T> {
+    queue: Arc<Queue<T>>,
+    counters: Arc<ProducerCounters>,
+}
+
+impl<T> ProducerHandle<T> {
+    /// Adds an item to the queue, blocking if the queue is full.
+    ///
+    /// Behaves like [`Queue::enqueue`]. Items dropped because the queue is shut
+    /// down are counted as rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item to add to the queue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(2);
+    /// let producer = queue.producer("p");
+    ///
+    /// producer.enqueue(1);
+    /// queue.shutdown();
+    /// producer.enqueue(2);
+    ///
+    /// let stats = producer.stats();
+    /// assert_eq!((stats.enqueued, stats.rejected), (1, 1));
+    /// ```
+    pub fn enqueue(&self, item: T) {
+        let (result, blocked) = self.queue.enqueue_timed(item);
+        self.record(result.is_ok() as u64, result.is_err() as u64, blocked);
+    }
+
+    /// Adds an item to the queue, spinning with backoff before blocking.
+    ///
+    /// Behaves like [`Queue::enqueue_with_backoff`]; an item handed back because
+    /// the queue is shut down counts as rejected.
+    pub fn enqueue_with_backoff(&self, item: T) -> Result<(), T> {
+        let (result, blocked) = self.queue.enqueue_with_backoff_timed(item);
+        self.record(result.is_ok() as u64, result.is_err() as u64, blocked);
+        result
+    }
+
+    /// Enqueues items from an iterator, pulling each one only once there is room for it.
+    ///
+    /// Behaves like [`Queue::enqueue_from_iter`]; the item handed back on
+    /// shutdown counts as rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// let producer = queue.producer("bulk");
+    /// assert_eq!(producer.enqueue_from_iter(0..3), Ok(3));
+    ///
+    /// queue.shutdown();
+    /// assert_eq!(producer.enqueue_from_iter(10..20), Err((0, 10)));
+    ///
+    /// let stats = producer.stats();
+    /// assert_eq!((stats.enqueued, stats.rejected), (3, 1));
+    /// ```
+    pub fn enqueue_from_iter<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> Result<usize, (usize, T)> {
+        let (result, blocked) = self.queue.enqueue_from_iter_timed(iter);
+        match &result {
+            Ok(count) => self.record(*count as u64, 0, blocked),
+            Err((count, _)) => self.record(*count as u64, 1, blocked),
+        }
+        result
+    }
+
+    /// Returns the label this handle was registered under.
+    pub fn label(&self) -> &str {
+        &self.counters.label
+    }
+
+    /// Returns a snapshot of the counters for this handle's label.
+    pub fn stats(&self) -> ProducerStats {
+        self.counters.snapshot()
+    }
+
+    /// Returns the queue this handle enqueues into.
+    pub fn queue(&self) -> &Arc<Queue<T>> {
+        &self.queue
+    }
+
+    /// Records `enqueued` and `rejected` items and the time spent blocked on them.
+    fn record(&self, enqueued: u64, rejected: u64, blocked: Duration) {
+        if enqueued > 0 {
+            self.counters
+                .enqueued
+                .fetch_add(enqueued, Ordering::Relaxed);
+        }
+        if rejected > 0 {
+            self.counters
+                .rejected
+                .fetch_add(rejected, Ordering::Relaxed);
+        }
+        if !blocked.is_zero() {
+            self.counters
+                .blocked_nanos
+                .fetch_add(blocked.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Clone for ProducerHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Arc::clone(&self.queue),
+            counters: Arc::clone(&self.counters),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ProducerHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProducerHandle")
+            .field("label", &self.counters.label)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A labeled consumer attached to a [`Queue`].
+///
+/// The consumer-side counterpart of [`ProducerHandle`]: dequeue operations
+/// delegate to the queue while recording how many items the label consumed, how
+/// long it spent blocked, and when it last received an item. Counters are shared
+/// by clones and same-label handles and remain available through
+/// [`Queue::consumer_stats`] after the handles are dropped.
+///
+/// # Example
+///
+/// ```
+/// use fifo_bounded_buffer::Queue;
+///
+/// let queue = Queue::new(4);
+/// let worker = queue.consumer("worker-1");
+///
+/// queue.enqueue(7);
+/// assert_eq!(worker.dequeue(), Some(7));
+/// assert_eq!(worker.stats().consumed, 1);
+/// ```
+pub struct ConsumerHandle<T> {
+    queue: Arc<Queue<T>>,
+    counters: Arc<ConsumerCounters>,
+}
+
+impl<T> ConsumerHandle<T> {
+    /// Removes and returns an item from the front of the queue.
+    ///
+    /// Behaves like [`Queue::dequeue`], blocking while the queue is empty and
+    /// returning `None` once it is shut down and drained.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(2);
+    /// let consumer = queue.consumer("c");
+    ///
+    /// queue.enqueue(1);
+    /// queue.shutdown();
+    ///
+    /// assert_eq!(consumer.dequeue(), Some(1));
+    /// assert_eq!(consumer.dequeue(), None);
+    /// assert_eq!(consumer.stats().consumed, 1);
+    /// ```
+    pub fn dequeue(&self) -> Option<T> {
+        let (item, blocked) = self.queue.dequeue_timed();
+        self.record(item.is_some() as u64, blocked);
+        item
+    }
+
+    /// Moves up to `buf.len()` items from the front of the queue into `buf`.
+    ///
+    /// Behaves like [`Queue::dequeue_into`]; every item written counts as consumed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// let consumer = queue.consumer("c");
+    /// queue.enqueue(1);
+    /// queue.enqueue(2);
+    ///
+    /// let mut buf = [0; 4];
+    /// assert_eq!(consumer.dequeue_into(&mut buf), 2);
+    /// assert_eq!(consumer.stats().consumed, 2);
+    /// ```
+    pub fn dequeue_into(&self, buf: &mut [T]) -> usize {
+        let (written, blocked) = self.queue.dequeue_into_timed(buf);
+        self.record(written as u64, blocked);
+        written
+    }
+
+    /// Moves up to `buf.len()` items into `buf` without blocking.
+    ///
+    /// Behaves like [`Queue::try_dequeue_into`].
+    pub fn try_dequeue_into(&self, buf: &mut [T]) -> usize {
+        let written = self.queue.try_dequeue_into(buf);
+        self.record(written as u64, Duration::ZERO);
+        written
+    }
+
+    /// Removes and returns the first item that matches `pred`.
+    ///
+    /// Behaves like [`Queue::dequeue_filter`].
+    pub fn dequeue_filter(&self, pred: impl FnMut(&T) -> bool) -> Option<T> {
+        let item = self.queue.dequeue_filter(pred);
+        self.record(item.is_some() as u64, Duration::ZERO);
+        item
+    }
+
+    /// Removes items from the front of the queue for as long as `pred` holds.
+    ///
+    /// Behaves like [`Queue::drain_while`].
+    pub fn drain_while(&self, pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let drained = self.queue.drain_while(pred);
+        self.record(drained.len() as u64, Duration::ZERO);
+        drained
+    }
+
+    /// Repeatedly removes batches of items and passes each batch to `f`.
+    ///
+    /// Behaves like [`Queue::consume_batches`]. Each batch is counted before
+    /// `f` runs; only the wait for a batch's first item counts as blocked time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_items` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(8);
+    /// let consumer = queue.consumer("batcher");
+    /// for i in 0..5 {
+    ///     queue.enqueue(i);
+    /// }
+    /// queue.shutdown();
+    ///
+    /// consumer.consume_batches(2, Duration::from_millis(50), |_| {});
+    /// assert_eq!(consumer.stats().consumed, 5);
+    /// ```
+    pub fn consume_batches(&self, max_items: usize, max_wait: Duration, mut f: impl FnMut(Vec<T>)) {
+        assert!(max_items > 0, "max_items must be greater than zero");
+        loop {
+            let (batch, blocked) = self.queue.next_batch(max_items, max_wait);
+            self.record(batch.len() as u64, blocked);
+            if batch.is_empty() {
+                return;
+            }
+            f(batch);
+        }
+    }
+
+    /// Returns the label this handle was registered under.
+    pub fn label(&self) -> &str {
+        &self.counters.label
+    }
+
+    /// Returns a snapshot of the counters for this handle's label.
+    pub fn stats(&self) -> ConsumerStats {
+        self.counters.snapshot()
+    }
+
+    /// Returns the queue this handle dequeues from.
+    pub fn queue(&self) -> &Arc<Queue<T>> {
+        &self.queue
+    }
+
+    /// Records `consumed` items and the time spent blocked obtaining them.
+    fn record(&self, consumed: u64, blocked: Duration) {
+        if consumed > 0 {
+            self.counters
+                .consumed
+                .fetch_add(consumed, Ordering::Relaxed);
+            *self.counters.last_activity.lock().unwrap() = Some(self.queue.clock.now());
+        }
+        if !blocked.is_zero() {
+            self.counters
+                .blocked_nanos
+                .fetch_add(blocked.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Clone for ConsumerHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Arc::clone(&self.queue),
+            counters: Arc::clone(&self.counters),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ConsumerHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsumerHandle")
+            .field("label", &self.counters.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Queue<T> {
+    /// Returns a labeled producer handle for this queue.
+    ///
+    /// Requesting the same label twice returns handles that share counters.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Name used to attribute the handle's traffic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.producer("a").enqueue(1);
+    /// queue.producer("a").enqueue(2);
+    ///
+    /// assert_eq!(queue.producer_stats()[0].enqueued, 2);
+    /// ```
+    pub fn producer(self: &Arc<Self>, label: impl Into<String>) -> ProducerHandle<T> {
+        let label = label.into();
+        let mut producers = self.producers.lock().unwrap();
+        let counters = match producers.iter().find(|c| c.label == label) {
+            Some(counters) => Arc::clone(counters),
+            None => {
+                let counters = Arc::new(ProducerCounters::new(label));
+                producers.push(Arc::clone(&counters));
+                counters
+            }
+        };
+
+        ProducerHandle {
+            queue: Arc::clone(self),
+            counters,
+        }
+    }
+
+    /// Returns the counters of every producer label registered on this queue.
+    ///
+    /// Entries are listed in registration order and remain available after all
+    /// handles for a label have been dropped.
+    pub fn producer_stats(&self) -> Vec<ProducerStats> {
+        let producers = self.producers.lock().unwrap();
+        producers.iter().map(|c| c.snapshot()).collect()
+    }
+
+    /// Returns a labeled consumer handle for this queue.
+    ///
+    /// Requesting the same label twice returns handles that share counters.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Name used to attribute the handle's traffic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue(1);
+    /// queue.consumer("slow").dequeue();
+    ///
+    /// let stats = queue.consumer_stats();
+    /// assert_eq!(stats[0].label, "slow");
+    /// assert!(stats[0].last_activity.is_some());
+    /// ```
+    pub fn consumer(self: &Arc<Self>, label: impl Into<String>) -> ConsumerHandle<T> {
+        let label = label.into();
+        let mut consumers = self.consumers.lock().unwrap();
+        let counters = match consumers.iter().find(|c| c.label == label) {
+            Some(counters) => Arc::clone(counters),
+            None => {
+                let counters = Arc::new(ConsumerCounters::new(label));
+                consumers.push(Arc::clone(&counters));
+                counters
+            }
+        };
+
+        ConsumerHandle {
+            queue: Arc::clone(self),
+            counters,
+        }
+    }
+
+    /// Returns the counters of every consumer label registered on this queue.
+    ///
+    /// Entries are listed in registration order and remain available after all
+    /// handles for a label have been dropped.
+    pub fn consumer_stats(&self) -> Vec<ConsumerStats> {
+        let consumers = self.consumers.lock().unwrap();
+        consumers.iter().map(|c| c.snapshot()).collect()
+    }
+
+    /// Returns the number of distinct consumer labels registered on this queue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u8>::new(4);
+    /// let _a = queue.consumer("a");
+    /// let _b = queue.consumer("b");
+    /// let _a2 = queue.consumer("a");
+    /// assert_eq!(queue.consumer_count(), 2);
+    /// ```
+    pub fn consumer_count(&self) -> usize {
+        self.consumers.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_labeled_producers_sum_to_total() {
+        let queue = Queue::new(4);
+        let labels = ["alpha", "beta", "gamma"];
+        let counts = [100u64, 250, 40];
+
+        let producers: Vec<_> = labels
+            .iter()
+            .zip(counts)
+            .map(|(&label, count)| {
+                let handle = queue.producer(label);
+                thread::spawn(move || {
+                    for i in 0..count {
+                        handle.enqueue(i);
+                    }
+                })
+            })
+            .collect();
+
+        let consumer = {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut consumed = 0u64;
+                while q.dequeue().is_some() {
+                    consumed += 1;
+                }
+                consumed
+            })
+        };
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        queue.shutdown();
+        let consumed = consumer.join().unwrap();
+
+        let stats = queue.producer_stats();
+        assert_eq!(stats.len(), 3);
+        for ((stat, label), count) in stats.iter().zip(labels).zip(counts) {
+            assert_eq!(stat.label, label);
+            assert_eq!(stat.enqueued, count);
+            assert_eq!(stat.rejected, 0);
+        }
+        assert_eq!(stats.iter().map(|s| s.enqueued).sum::<u64>(), consumed);
+    }
+
+    #[test]
+    fn test_producer_stats_survive_handle_drop() {
+        let queue = Queue::new(4);
+        {
+            let handle = queue.producer("short-lived");
+            let clone = handle.clone();
+            handle.enqueue(1);
+            clone.enqueue(2);
+        }
+
+        let stats = queue.producer_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].label, "short-lived");
+        assert_eq!(stats[0].enqueued, 2);
+    }
+
+    #[test]
+    fn test_producer_counts_rejected_after_shutdown() {
+        let queue = Queue::new(4);
+        let handle = queue.producer("p");
+        handle.enqueue(1);
+        queue.shutdown();
+        handle.enqueue(2);
+        handle.enqueue(3);
+
+        let stats = handle.stats();
+        assert_eq!(stats.enqueued, 1);
+        assert_eq!(stats.rejected, 2);
+    }
+
+    #[test]
+    fn test_producer_counts_every_enqueue_method() {
+        let queue = Queue::new(8);
+        let producer = queue.producer("all");
+        producer.enqueue(0);
+        assert_eq!(producer.enqueue_with_backoff(1), Ok(()));
+        assert_eq!(producer.enqueue_from_iter(2..5), Ok(3));
+        assert_eq!(producer.stats().enqueued, 5);
+
+        queue.shutdown();
+        assert_eq!(producer.enqueue_with_backoff(5), Err(5));
+        assert_eq!(producer.enqueue_from_iter(6..8), Err((0, 6)));
+
+        let stats = queue.producer_stats();
+        assert_eq!((stats[0].enqueued, stats[0].rejected), (5, 2));
+    }
+
+    #[test]
+    fn test_producer_iter_records_blocked_time() {
+        let queue = Queue::new(1);
+        let producer = queue.producer("bulk");
+        queue.enqueue(9);
+
+        let p = producer.clone();
+        let handle = thread::spawn(move || p.enqueue_from_iter(0..1));
+
+        assert!(queue.wait_for_blocked_producers(1, Duration::from_secs(5)));
+        assert_eq!(queue.dequeue(), Some(9));
+        // The producer only finds the iterator exhausted once a slot is free again.
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(handle.join().unwrap(), Ok(1));
+
+        let stats = producer.stats();
+        assert_eq!(stats.enqueued, 1);
+        assert!(stats.blocked > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_producer_records_blocked_time() {
+        let queue = Queue::new(1);
+        let handle = queue.producer("blocked");
+        handle.enqueue(1);
+        assert_eq!(handle.stats().blocked, Duration::ZERO);
+
+        let h = handle.clone();
+        let producer = thread::spawn(move || h.enqueue(2));
+
+        assert!(queue.wait_for_blocked_producers(1, Duration::from_secs(5)));
+        assert_eq!(queue.dequeue(), Some(1));
+        producer.join().unwrap();
+
+        assert!(handle.stats().blocked > Duration::ZERO);
+        assert_eq!(handle.stats().enqueued, 2);
+    }
+
+    #[test]
+    fn test_consumer_tracks_activity_and_blocked_time() {
+        let queue = Queue::new(2);
+        let consumer = queue.consumer("worker");
+        assert_eq!(consumer.stats().last_activity, None);
+
+        let c = consumer.clone();
+        let handle = thread::spawn(move || c.dequeue());
+
+        assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+        let before = Instant::now();
+        queue.enqueue(5);
+        assert_eq!(handle.join().unwrap(), Some(5));
+
+        let stats = consumer.stats();
+        assert_eq!(stats.consumed, 1);
+        assert!(stats.blocked > Duration::ZERO);
+        assert!(stats.last_activity.unwrap() >= before);
+    }
+
+    #[test]
+    fn test_consumer_counts_every_dequeue_method() {
+        let queue = Queue::new(8);
+        let consumer = queue.consumer("all");
+        for i in 0..8 {
+            queue.enqueue(i);
+        }
+
+        let mut buf = [0; 2];
+        assert_eq!(consumer.dequeue_into(&mut buf), 2);
+        assert_eq!(consumer.try_dequeue_into(&mut buf), 2);
+        assert_eq!(consumer.dequeue_filter(|&i| i == 5), Some(5));
+        assert_eq!(consumer.drain_while(|&i| i < 5), vec![4]);
+        assert_eq!(consumer.stats().consumed, 6);
+
+        queue.shutdown();
+        let mut batches = Vec::new();
+        consumer.consume_batches(4, Duration::from_secs(5), |batch| batches.push(batch));
+        assert_eq!(batches, vec![vec![6, 7]]);
+
+        let stats = queue.consumer_stats();
+        assert_eq!(stats[0].consumed, 8);
+        assert!(stats[0].last_activity.is_some());
+    }
+
+    #[test]
+    fn test_consumer_batch_records_blocked_time() {
+        let queue = Queue::new(4);
+        let consumer = queue.consumer("batcher");
+
+        let c = consumer.clone();
+        let handle = thread::spawn(move || {
+            let mut buf = [0; 4];
+            c.dequeue_into(&mut buf)
+        });
+
+        assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+        queue.enqueue(1);
+        assert_eq!(handle.join().unwrap(), 1);
+
+        let stats = consumer.stats();
+        assert_eq!(stats.consumed, 1);
+        assert!(stats.blocked > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_consumer_none_is_not_counted() {
+        let queue = Queue::<u8>::new(2);
+        let consumer = queue.consumer("idle");
+        queue.shutdown();
+
+        assert_eq!(consumer.dequeue(), None);
+        assert_eq!(consumer.stats().consumed, 0);
+        assert_eq!(consumer.stats().last_activity, None);
+        assert_eq!(queue.consumer_count(), 1);
+    }
+}
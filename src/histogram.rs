@@ -0,0 +1,202 @@
+//! A log-bucketed latency histogram, cheap enough to record every item.
+
+use serde::{Deserialize, Serialize};
+
+/// Bits of precision kept below the most significant bit of a value.
+const SUB_BITS: u32 = 4;
+/// Buckets per power of two.
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+/// Enough buckets for any `u64`.
+const BUCKETS: usize = (64 - SUB_BITS as usize + 1) * SUB_BUCKETS;
+
+/// Counts of nanosecond durations in buckets whose width grows with the value.
+///
+/// Values below 16 get a bucket each; above that every power of two is split
+/// into 16 equal buckets, so a reported percentile is within 1/16 of the
+/// true value. Each thread records into its own histogram, and the results
+/// are combined with [`Histogram::merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+/// Selected percentiles of a histogram, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Percentiles {
+    /// Number of recorded values.
+    pub samples: u64,
+    /// Median.
+    pub p50_ns: u64,
+    /// 90th percentile.
+    pub p90_ns: u64,
+    /// 99th percentile.
+    pub p99_ns: u64,
+    /// 99.9th percentile.
+    pub p999_ns: u64,
+    /// Largest recorded value.
+    pub max_ns: u64,
+}
+
+/// Index of the bucket holding `value`.
+fn bucket(value: u64) -> usize {
+    let msb = 63u32.saturating_sub(value.leading_zeros());
+    if msb < SUB_BITS {
+        return value as usize;
+    }
+    let shift = msb - SUB_BITS;
+    (shift as usize + 1) * SUB_BUCKETS + ((value >> shift) as usize - SUB_BUCKETS)
+}
+
+/// Largest value that falls into bucket `index`.
+fn bucket_upper(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let mantissa = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    // The top bucket ends exactly at u64::MAX; compute it without overflow.
+    ((mantissa << shift) - 1).saturating_add(1u64 << shift)
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            max: 0,
+        }
+    }
+
+    /// Records one value.
+    pub fn record(&mut self, value: u64) {
+        self.counts[bucket(value)] += 1;
+        self.total += 1;
+        self.max = self.max.max(value);
+    }
+
+    /// Adds every value recorded in `other` to this histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (mine, theirs) in self.counts.iter_mut().zip(&other.counts) {
+            *mine += theirs;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Value at or below which fraction `q` of the recorded values fall.
+    ///
+    /// # Arguments
+    ///
+    /// * `q` - Quantile between 0 and 1.
+    ///
+    /// # Returns
+    ///
+    /// The upper bound of the bucket reaching the quantile, capped at the
+    /// largest recorded value, or zero if nothing was recorded.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q * self.total as f64).ceil() as u64).clamp(1, self.total);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_upper(index).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// The percentiles reported by `--latency`.
+    pub fn percentiles(&self) -> Percentiles {
+        Percentiles {
+            samples: self.count(),
+            p50_ns: self.quantile(0.50),
+            p90_ns: self.quantile(0.90),
+            p99_ns: self.quantile(0.99),
+            p999_ns: self.quantile(0.999),
+            max_ns: self.max,
+        }
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for value in (0..5_000).chain([u64::MAX / 3, u64::MAX - 1, u64::MAX]) {
+            let index = bucket(value);
+            assert!(index < BUCKETS);
+            assert!(value <= bucket_upper(index), "{value}");
+            if index > 0 {
+                assert!(value > bucket_upper(index - 1), "{value}");
+            }
+        }
+        assert_eq!(bucket(15), 15);
+        assert_eq!(bucket(16), 16);
+        // 32..=33 share a bucket once the width reaches two
+        assert_eq!(bucket(32), bucket(33));
+        assert_eq!(bucket_upper(bucket(32)), 33);
+        assert_eq!(bucket_upper(BUCKETS - 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_relative_error() {
+        for value in [100u64, 1_000, 12_345, 1_000_000, 987_654_321] {
+            let upper = bucket_upper(bucket(value));
+            assert!((upper - value) as f64 <= value as f64 / 16.0, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_percentiles_monotonic() {
+        let mut a = Histogram::new();
+        let mut b = Histogram::new();
+        for i in 0..10_000u64 {
+            let value = (i * 7_919) % 1_000_003;
+            if i % 2 == 0 {
+                a.record(value)
+            } else {
+                b.record(value)
+            }
+        }
+        a.merge(&b);
+        assert_eq!(a.count(), 10_000);
+
+        let p = a.percentiles();
+        assert!(p.p50_ns <= p.p90_ns);
+        assert!(p.p90_ns <= p.p99_ns);
+        assert!(p.p99_ns <= p.p999_ns);
+        assert!(p.p999_ns <= p.max_ns);
+        assert!(p.max_ns < 1_000_003);
+        assert_eq!(Histogram::new().percentiles().max_ns, 0);
+    }
+
+    #[test]
+    fn test_quantile_exact_below_sixteen() {
+        let mut h = Histogram::new();
+        for v in 1..=10 {
+            h.record(v);
+        }
+        assert_eq!(h.quantile(0.5), 5);
+        assert_eq!(h.quantile(0.9), 9);
+        assert_eq!(h.quantile(1.0), 10);
+    }
+}
@@ -0,0 +1,243 @@
+//! Instrumentation for tests that need to know when threads are parked in a queue.
+//!
+//! Enabled by the `test-hooks` feature (and always in this crate's own tests).
+//! Without it, none of this code or the condition variable it waits on is compiled.
+
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{KeyedQueue, OrderedQueue, Queue};
+
+impl<T> Queue<T> {
+    /// Waits until at least `n` producers are blocked waiting for space.
+    ///
+    /// Lets a test act only once a thread is actually parked in `enqueue`,
+    /// instead of sleeping and hoping it got there.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` producers were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(1);
+    /// queue.enqueue(1);
+    ///
+    /// let q = Arc::clone(&queue);
+    /// let producer = thread::spawn(move || q.enqueue(2));
+    ///
+    /// assert!(queue.wait_for_blocked_producers(1, Duration::from_secs(5)));
+    /// queue.dequeue();
+    /// producer.join().unwrap();
+    /// ```
+    pub fn wait_for_blocked_producers(&self, n: usize, timeout: Duration) -> bool {
+        self.wait_for_waiters(timeout, |blocked_producers, _| blocked_producers >= n)
+    }
+
+    /// Waits until at least `n` consumers are blocked waiting for an item.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` consumers were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(1);
+    ///
+    /// let q = Arc::clone(&queue);
+    /// let consumer = thread::spawn(move || q.dequeue());
+    ///
+    /// assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+    /// queue.enqueue(7);
+    /// assert_eq!(consumer.join().unwrap(), Some(7));
+    /// ```
+    pub fn wait_for_blocked_consumers(&self, n: usize, timeout: Duration) -> bool {
+        self.wait_for_waiters(timeout, |_, blocked_consumers| blocked_consumers >= n)
+    }
+
+    /// Waits on the hooks condvar until `done(blocked_producers, blocked_consumers)`
+    /// holds or `timeout` elapses. Always uses real time.
+    fn wait_for_waiters(&self, timeout: Duration, done: impl Fn(usize, usize) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut inner = self.inner.lock().unwrap();
+        loop {
+            if done(inner.blocked_producers, inner.blocked_consumers) {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            inner = self.hooks.wait_timeout(inner, deadline - now).unwrap().0;
+        }
+    }
+}
+
+impl<T, R> OrderedQueue<T, R> {
+    /// Waits until at least `n` threads are blocked in `submit` waiting for
+    /// room in the reorder buffer.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` submitters were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::OrderedQueue;
+    ///
+    /// let ordered = OrderedQueue::new(4, 1);
+    /// for n in 0..3 {
+    ///     ordered.enqueue(n);
+    /// }
+    /// let (s0, _) = ordered.dequeue().unwrap();
+    /// let (s1, _) = ordered.dequeue().unwrap();
+    /// let (s2, _) = ordered.dequeue().unwrap();
+    /// ordered.submit(s1, 1);
+    ///
+    /// let q = Arc::clone(&ordered);
+    /// let submitter = thread::spawn(move || q.submit(s2, 2));
+    ///
+    /// assert!(ordered.wait_for_blocked_submitters(1, Duration::from_secs(5)));
+    /// ordered.submit(s0, 0);
+    /// assert_eq!(ordered.next_in_order(), Some(0));
+    /// assert_eq!(ordered.next_in_order(), Some(1));
+    /// submitter.join().unwrap();
+    /// ```
+    pub fn wait_for_blocked_submitters(&self, n: usize, timeout: Duration) -> bool {
+        wait_until(&self.reorder, &self.hooks, timeout, |reorder| {
+            reorder.blocked_submitters >= n
+        })
+    }
+
+    /// Waits until at least `n` threads are blocked in `next_in_order`
+    /// waiting for the next result.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` emitters were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::OrderedQueue;
+    ///
+    /// let ordered = OrderedQueue::new(4, 4);
+    /// ordered.enqueue(7);
+    ///
+    /// let q = Arc::clone(&ordered);
+    /// let emitter = thread::spawn(move || q.next_in_order());
+    ///
+    /// assert!(ordered.wait_for_blocked_emitters(1, Duration::from_secs(5)));
+    /// let (seq, n) = ordered.dequeue().unwrap();
+    /// ordered.submit(seq, n * 10);
+    /// assert_eq!(emitter.join().unwrap(), Some(70));
+    /// ```
+    pub fn wait_for_blocked_emitters(&self, n: usize, timeout: Duration) -> bool {
+        wait_until(&self.reorder, &self.hooks, timeout, |reorder| {
+            reorder.blocked_emitters >= n
+        })
+    }
+}
+
+impl<K, T> KeyedQueue<K, T> {
+    /// Waits until at least `n` producers are blocked waiting for space.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` producers were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::KeyedQueue;
+    ///
+    /// let queue = KeyedQueue::new(1);
+    /// queue.enqueue("a", 1);
+    ///
+    /// let q = Arc::clone(&queue);
+    /// let producer = thread::spawn(move || q.enqueue("b", 2));
+    ///
+    /// assert!(queue.wait_for_blocked_producers(1, Duration::from_secs(5)));
+    /// assert_eq!(queue.dequeue(), Some(("a", 1)));
+    /// producer.join().unwrap();
+    /// ```
+    pub fn wait_for_blocked_producers(&self, n: usize, timeout: Duration) -> bool {
+        wait_until(&self.inner, &self.hooks, timeout, |inner| {
+            inner.blocked_producers >= n
+        })
+    }
+
+    /// Waits until at least `n` consumers are blocked waiting for an item
+    /// from a key no other consumer holds.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `n` consumers were blocked before `timeout` elapsed; `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::KeyedQueue;
+    ///
+    /// let queue = KeyedQueue::new(4);
+    ///
+    /// let q = Arc::clone(&queue);
+    /// let consumer = thread::spawn(move || q.dequeue());
+    ///
+    /// assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+    /// queue.enqueue("a", 7);
+    /// assert_eq!(consumer.join().unwrap(), Some(("a", 7)));
+    /// ```
+    pub fn wait_for_blocked_consumers(&self, n: usize, timeout: Duration) -> bool {
+        wait_until(&self.inner, &self.hooks, timeout, |inner| {
+            inner.blocked_consumers >= n
+        })
+    }
+}
+
+/// Waits on `hooks` until `done` holds for the state behind `state` or
+/// `timeout` elapses. Always uses real time.
+fn wait_until<S>(
+    state: &Mutex<S>,
+    hooks: &Condvar,
+    timeout: Duration,
+    done: impl Fn(&S) -> bool,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut guard = state.lock().unwrap();
+    loop {
+        if done(&guard) {
+            return true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        guard = hooks.wait_timeout(guard, deadline - now).unwrap().0;
+    }
+}
@@ -0,0 +1,338 @@
+//! Per-key FIFO queue whose keys are consumed in parallel but never concurrently.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// A bounded queue of items routed by key, with partition-like semantics.
+///
+/// Items sharing a key are delivered in the order they were enqueued, and a key
+/// is held by at most one consumer at a time: after [`dequeue`](Self::dequeue)
+/// hands out an item, no other item with that key is delivered until the
+/// consumer calls [`complete`](Self::complete). Items with different keys are
+/// delivered to different consumers concurrently.
+///
+/// The capacity bounds the total number of buffered items across all keys.
+/// Shutdown follows [`Queue`](crate::Queue): enqueues are dropped, and
+/// `dequeue` returns `None` once every buffered item has been delivered.
+///
+/// # Example
+///
+/// ```
+/// use fifo_bounded_buffer::KeyedQueue;
+///
+/// let queue = KeyedQueue::new(8);
+/// queue.enqueue("a", 1);
+/// queue.enqueue("a", 2);
+/// queue.enqueue("b", 3);
+///
+/// // "a" is busy until completed, so the next consumer gets "b".
+/// assert_eq!(queue.dequeue(), Some(("a", 1)));
+/// assert_eq!(queue.dequeue(), Some(("b", 3)));
+///
+/// queue.complete(&"a");
+/// assert_eq!(queue.dequeue(), Some(("a", 2)));
+/// ```
+pub struct KeyedQueue<K, T> {
+    pub(crate) inner: Mutex<KeyedInner<K, T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    #[cfg(any(test, feature = "test-hooks"))]
+    pub(crate) hooks: Condvar,
+}
+
+/// Inner shared state of the keyed queue, protected by the mutex.
+///
+/// - `queues`: per-key item storage; keys without items are removed
+/// - `ready`: keys with items that no consumer currently holds, in the order
+///   they became available
+/// - `in_flight`: keys currently held by a consumer
+/// - `len`: total number of buffered items
+/// - `shutdown`: a flag that signals termination to all threads
+/// - `blocked_producers`: threads currently waiting on `not_full`
+/// - `blocked_consumers`: threads currently waiting on `not_empty`
+pub(crate) struct KeyedInner<K, T> {
+    queues: HashMap<K, VecDeque<T>>,
+    ready: VecDeque<K>,
+    in_flight: HashSet<K>,
+    len: usize,
+    shutdown: bool,
+    pub(crate) blocked_producers: usize,
+    pub(crate) blocked_consumers: usize,
+}
+
+impl<K: Hash + Eq + Clone, T> KeyedQueue<K, T> {
+    /// Creates a new `KeyedQueue` holding at most `capacity` items in total.
+    ///
+    /// # Returns
+    ///
+    /// A reference-counted pointer (`Arc`) to the new `KeyedQueue` instance.
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(KeyedInner {
+                queues: HashMap::new(),
+                ready: VecDeque::new(),
+                in_flight: HashSet::new(),
+                len: 0,
+                shutdown: false,
+                blocked_producers: 0,
+                blocked_consumers: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            #[cfg(any(test, feature = "test-hooks"))]
+            hooks: Condvar::new(),
+        })
+    }
+
+    /// Adds an item to the back of `key`'s FIFO, blocking if the queue is full.
+    ///
+    /// If the queue is shut down, the item is silently dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key whose ordering the item participates in.
+    /// * `item` - The item to add.
+    pub fn enqueue(&self, key: K, item: T) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.len == self.capacity && !inner.shutdown {
+            inner.blocked_producers += 1;
+            self.waiters_changed();
+            while inner.len == self.capacity && !inner.shutdown {
+                inner = self.not_full.wait(inner).unwrap();
+            }
+            inner.blocked_producers -= 1;
+        }
+
+        if inner.shutdown {
+            return;
+        }
+
+        let became_ready = !inner.in_flight.contains(&key) && !inner.queues.contains_key(&key);
+        inner.queues.entry(key.clone()).or_default().push_back(item);
+        inner.len += 1;
+        if became_ready {
+            inner.ready.push_back(key);
+            self.not_empty.notify_one();
+        }
+    }
+
+    /// Removes the next item from a key that no other consumer holds.
+    ///
+    /// The returned key is held by the caller until [`complete`](Self::complete)
+    /// is called with it.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((key, item))` - the front item of the longest-waiting available key.
+    /// * `None` - if the queue is shut down and empty.
+    ///
+    /// # Blocking
+    ///
+    /// - Blocks while every buffered item belongs to a held key, or the queue is
+    ///   empty, until an item becomes available or the queue terminates.
+    pub fn dequeue(&self) -> Option<(K, T)> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.ready.is_empty() && !(inner.shutdown && inner.len == 0) {
+            inner.blocked_consumers += 1;
+            self.waiters_changed();
+            while inner.ready.is_empty() && !(inner.shutdown && inner.len == 0) {
+                inner = self.not_empty.wait(inner).unwrap();
+            }
+            inner.blocked_consumers -= 1;
+        }
+
+        let key = inner.ready.pop_front()?;
+        let queue = inner.queues.get_mut(&key)?;
+        let item = queue.pop_front()?;
+        if queue.is_empty() {
+            inner.queues.remove(&key);
+        }
+        inner.in_flight.insert(key.clone());
+        inner.len -= 1;
+
+        self.not_full.notify_one();
+        if inner.shutdown && inner.len == 0 {
+            self.not_empty.notify_all();
+        }
+        Some((key, item))
+    }
+
+    /// Releases a key obtained from [`dequeue`](Self::dequeue) so its next item
+    /// can be delivered.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key was held; `false` otherwise.
+    pub fn complete(&self, key: &K) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.in_flight.remove(key) {
+            return false;
+        }
+
+        if inner.queues.contains_key(key) {
+            inner.ready.push_back(key.clone());
+            self.not_empty.notify_one();
+        }
+        true
+    }
+
+    /// Shuts down the queue, waking all blocked threads and preventing further enqueues.
+    pub fn shutdown(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.shutdown = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// Checks if the queue has been shut down.
+    pub fn is_shutdown(&self) -> bool {
+        self.inner.lock().unwrap().shutdown
+    }
+
+    /// Returns the total number of buffered items across all keys.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().len
+    }
+
+    /// Checks if the queue holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wakes threads parked in the `test-hooks` wait functions; a no-op otherwise.
+    fn waiters_changed(&self) {
+        #[cfg(any(test, feature = "test-hooks"))]
+        self.hooks.notify_all();
+    }
+}
+
+impl<K, T> fmt::Debug for KeyedQueue<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedQueue")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_held_key_is_skipped() {
+        let queue = KeyedQueue::new(8);
+        queue.enqueue(1, "1a");
+        queue.enqueue(1, "1b");
+        queue.enqueue(2, "2a");
+
+        assert_eq!(queue.dequeue(), Some((1, "1a")));
+        assert_eq!(queue.dequeue(), Some((2, "2a")));
+
+        assert!(queue.complete(&2));
+        assert!(!queue.complete(&2));
+        assert!(queue.complete(&1));
+        assert_eq!(queue.dequeue(), Some((1, "1b")));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_dequeue_blocks_until_key_completed() {
+        let queue = KeyedQueue::new(4);
+        queue.enqueue("k", 1);
+        queue.enqueue("k", 2);
+        assert_eq!(queue.dequeue(), Some(("k", 1)));
+
+        let q = Arc::clone(&queue);
+        let handle = thread::spawn(move || q.dequeue());
+
+        assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+        assert!(!handle.is_finished());
+        queue.complete(&"k");
+        assert_eq!(handle.join().unwrap(), Some(("k", 2)));
+    }
+
+    #[test]
+    fn test_capacity_bounds_total_items() {
+        let queue = KeyedQueue::new(2);
+        queue.enqueue("a", 1);
+        queue.enqueue("b", 2);
+
+        let q = Arc::clone(&queue);
+        let handle = thread::spawn(move || q.enqueue("c", 3));
+
+        assert!(queue.wait_for_blocked_producers(1, Duration::from_secs(5)));
+        assert!(!handle.is_finished());
+        assert_eq!(queue.dequeue(), Some(("a", 1)));
+        handle.join().unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_shutdown_drains_then_returns_none() {
+        let queue = KeyedQueue::new(4);
+        queue.enqueue("a", 1);
+        queue.enqueue("a", 2);
+        queue.shutdown();
+        queue.enqueue("b", 3);
+
+        assert_eq!(queue.dequeue(), Some(("a", 1)));
+        queue.complete(&"a");
+        assert_eq!(queue.dequeue(), Some(("a", 2)));
+        assert_eq!(queue.dequeue(), None);
+    }
+
+    #[test]
+    fn test_per_key_order_with_parallel_consumers() {
+        let queue = KeyedQueue::new(8);
+        let keys = 6u32;
+        let per_key = 100u32;
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let active = Arc::new(Mutex::new(HashSet::new()));
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let q = Arc::clone(&queue);
+                let processed = Arc::clone(&processed);
+                let active = Arc::clone(&active);
+                thread::spawn(move || {
+                    let mut rng = rand::rng();
+                    while let Some((key, seq)) = q.dequeue() {
+                        assert!(active.lock().unwrap().insert(key), "key {key} held twice");
+                        thread::sleep(Duration::from_micros(rng.random_range(0..200)));
+                        processed.lock().unwrap().push((key, seq));
+                        active.lock().unwrap().remove(&key);
+                        q.complete(&key);
+                    }
+                })
+            })
+            .collect();
+
+        for seq in 0..per_key {
+            for key in 0..keys {
+                queue.enqueue(key, seq);
+            }
+        }
+        queue.shutdown();
+        for c in consumers {
+            c.join().unwrap();
+        }
+
+        let processed = processed.lock().unwrap();
+        assert_eq!(processed.len(), (keys * per_key) as usize);
+        for key in 0..keys {
+            let seqs: Vec<u32> = processed
+                .iter()
+                .filter(|(k, _)| *k == key)
+                .map(|&(_, seq)| seq)
+                .collect();
+            assert_eq!(seqs, (0..per_key).collect::<Vec<_>>());
+        }
+    }
+}
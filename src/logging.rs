@@ -0,0 +1,63 @@
+//! Event logging to stderr selected by `-v/--verbose`.
+//!
+//! Each `-v` enables one more level:
+//!
+//! - `-v`: run and trial progress
+//! - `-vv`: thread lifecycles and waits on a full or empty queue
+//! - `-vvv`: every `--log-every`-th item enqueued and dequeued
+
+use log::LevelFilter;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// When logging was set up; log lines are stamped relative to it.
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// The most detailed level logged for `verbosity` repetitions of `-v`.
+pub fn level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Sends log records at or above the level for `verbosity` to stderr, each
+/// stamped with the seconds since this call.
+///
+/// With no `-v` nothing is logged, so the output is exactly what it was
+/// without logging. `RUST_LOG` still overrides the level of single modules.
+pub fn init(verbosity: u8) {
+    let start = *START.get_or_init(Instant::now);
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(level(verbosity))
+        .parse_default_env()
+        .format(move |buf, record| {
+            writeln!(
+                buf,
+                "[{:>11.6}s {:<5}] {}",
+                start.elapsed().as_secs_f64(),
+                record.level(),
+                record.args()
+            )
+        });
+    // A logger set up earlier, e.g. by a test, stays in place
+    let _ = builder.try_init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(0), LevelFilter::Off);
+        assert_eq!(level(1), LevelFilter::Info);
+        assert_eq!(level(2), LevelFilter::Debug);
+        assert_eq!(level(3), LevelFilter::Trace);
+        assert_eq!(level(u8::MAX), LevelFilter::Trace);
+    }
+}
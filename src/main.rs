@@ -1,132 +1,693 @@
-mod queue;
+mod affinity;
+mod args;
+mod chan;
+mod checksum;
+mod error;
+mod events;
+mod histogram;
+mod logging;
+mod matrix;
+mod memory;
+mod occupancy;
+mod payload;
+mod pipe;
+mod progress;
+mod report;
+mod scenario;
+mod sim;
+mod stats;
+mod threads;
+mod throughput;
+mod verify;
+mod warmup;
+mod watchdog;
 
+use args::{Args, Command, ItemsMode, Output};
+use chan::{Backend, QueueImpl};
 use clap::Parser;
-use queue::Queue;
-use rand::Rng;
-use std::{
-    sync::{Arc, Mutex},
-    thread,
-    time::{Duration, Instant},
+use error::{EXIT_INTERRUPTED, SimError};
+use report::{
+    CompareReport, MatrixReport, NamedReport, Report, ScenarioReport, StageReport, Status,
+    SweepReport,
 };
+use scenario::Scenario;
+use sim::{Config, DelayRange, Outcome, TrialFailure};
+use std::io;
+use std::process::{self, ExitCode};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Instant;
+use threads::{Role, ThreadStats};
 
-/// Command line arguments using clap
-#[derive(Parser, Debug)]
-#[command(author, version, about)]
-struct Args {
-    /// Number of consumer threads
-    #[arg(short = 'c', default_value = "1")]
-    consumers: usize,
-
-    /// Number of producer threads
-    #[arg(short = 'p', default_value = "1")]
-    producers: usize,
-
-    /// Total items to produce per thread
-    #[arg(short = 'i', default_value = "10")]
-    items: usize,
-
-    /// Size of the queue
-    #[arg(short = 's', default_value = "5")]
-    size: usize,
-
-    /// Introduce delay between enqueue/dequeue
-    #[arg(short = 'd', default_value_t = false)]
-    delay: bool,
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            report_error(&err);
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
 
-fn main() {
-    let args = Args::parse();
+/// Parses the arguments, runs the simulation and prints its results.
+///
+/// # Errors
+///
+/// Returns the reason the run did not succeed; any results it produced have
+/// already been printed.
+fn run() -> Result<(), SimError> {
+    let args = Args::try_parse()?;
+    if let Some(Command::Pipe(pipe)) = &args.command {
+        let input = io::BufReader::new(io::stdin());
+        pipe::run(input, io::stdout(), &pipe.config()).map_err(SimError::Pipe)?;
+        return Ok(());
+    }
+    args.validate()?;
+    logging::init(args.verbose);
+    let (config, mut warnings) = args.config();
+    if args.is_matrix() {
+        warnings = args.matrix().1;
+    }
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    let scenarios = match &args.scenarios {
+        Some(path) => Some(scenario::load(path).map_err(SimError::ScenarioFile)?),
+        None => None,
+    };
+
+    if args.output == Output::Human {
+        println!(
+            "{} SAMPLE OUTPUT FROM MAIN {}",
+            "-".repeat(10),
+            "-".repeat(10)
+        );
 
-    let nump = args.producers.min(8);
-    let numc = args.consumers.min(8);
-    let per_thread = args.items / nump;
+        let queue = match &args.sweep_sizes {
+            Some(sizes) => format!("queue sizes {:?}", sizes.0),
+            None if args.queue_size.len() > 1 => format!("queue sizes {:?}", args.queue_size),
+            None => format!("queue size {}", config.queue_size),
+        };
+        let threads = |counts: &[usize], resolved: usize| match counts {
+            [_] => resolved.to_string(),
+            _ => format!("{counts:?}"),
+        };
+        let backend = if args.compare {
+            let mut names: Vec<_> = Backend::available()
+                .iter()
+                .map(|b| b.name().to_string())
+                .collect();
+            names.extend(
+                QueueImpl::available()
+                    .iter()
+                    .map(|i| format!("fifo/{}", i.name())),
+            );
+            names.join(" vs ")
+        } else {
+            config.channel()
+        };
+        let work = match config.duration_ms {
+            Some(ms) => format!("{:.3}s of items", ms as f64 / 1000.0),
+            None if args.producers.len() > 1 => match args.items_mode {
+                ItemsMode::Total => format!("{} items", args.items),
+                ItemsMode::PerThread => format!("{} items per producer", args.items),
+            },
+            None => format!(
+                "{} items ({})",
+                config.items,
+                config.per_producer().expect("items are fixed")
+            ),
+        };
+        match &scenarios {
+            Some(scenarios) => println!(
+                "Configuration: {} scenarios ({}), default seed {}",
+                scenarios.len(),
+                backend,
+                config.seed
+            ),
+            None => println!(
+                "Configuration: {} producers, {} consumers, {}, {} ({}), seed {}",
+                threads(&args.producers, config.producers),
+                threads(&args.consumers, config.consumers),
+                work,
+                queue,
+                backend,
+                config.seed
+            ),
+        }
+        if config.is_delayed() {
+            println!(
+                "Delays: producers {} ns, consumers {} ns",
+                config.producer_delay, config.consumer_delay
+            );
+        }
+        if !config.stage_workers.is_empty() {
+            println!(
+                "Pipeline: {} stages, workers {:?}, {} ns of work per item",
+                config.stage_workers.len() + 1,
+                config.stage_workers,
+                config.work_ns
+            );
+        }
+    }
 
-    println!(
-        "{} SAMPLE OUTPUT FROM MAIN {}",
-        "-".repeat(10),
-        "-".repeat(10)
-    );
+    let stop = interrupt_flag();
+    let (trials, warmup) = (args.trials, args.warmup);
+    let interrupted = if args.compare {
+        let points = sim::compare(
+            &config,
+            Backend::available(),
+            &QueueImpl::available(),
+            trials,
+            warmup,
+            &stop,
+        )
+        .map_err(|failure| failed(&args, failure))?;
+        let interrupted = run_points(&args, &points, "backend", Config::channel, |compare| {
+            serde_json::to_string(&CompareReport::new(compare))
+        });
+        if matches!(args.output, Output::Human) {
+            let reports = points
+                .iter()
+                .map(|(config, outcomes)| Report::new(config, outcomes, warmup))
+                .collect();
+            if let Some(pct) = CompareReport::new(reports).dispatch_overhead_pct {
+                println!(
+                    "Trait object overhead: fifo/mutex {pct:.1}% below fifo with direct calls"
+                );
+            }
+        }
+        interrupted
+    } else if args.is_matrix() {
+        let (configs, _) = args.matrix();
+        let points = sim::run_matrix(configs, trials, warmup, &stop)
+            .map_err(|failure| failed(&args, failure))?;
+        let interrupted = run_points(&args, &points, "p/c/size", combination, |matrix| {
+            serde_json::to_string(&MatrixReport::new(matrix))
+        });
+        if matches!(args.output, Output::Human) {
+            let reports = points
+                .iter()
+                .map(|(config, outcomes)| Report::new(config, outcomes, warmup))
+                .collect();
+            let matrix = MatrixReport::new(reports);
+            if let Some(best) = matrix.best.map(|index| &matrix.matrix[index]) {
+                println!(
+                    "Best: {} producers, {} consumers, queue size {} at {:.0} items/sec",
+                    best.config.producers,
+                    best.config.consumers,
+                    best.config.queue_size,
+                    best.items_per_sec.mean
+                );
+            }
+        }
+        interrupted
+    } else if let Some(sizes) = &args.sweep_sizes {
+        let points = sim::sweep(&config, &sizes.0, trials, warmup, &stop)
+            .map_err(|failure| failed(&args, failure))?;
+        run_points(
+            &args,
+            &points,
+            "queue size",
+            |config| config.queue_size.to_string(),
+            |sweep| serde_json::to_string(&SweepReport { sweep }),
+        )
+    } else if let Some(scenarios) = &scenarios {
+        run_scenarios(&args, &config, scenarios, &stop)?
+    } else {
+        run_single(&args, &config, &stop)?
+    };
 
-    println!(
-        "Simulating {} producers {} consumers with {} items per thread and a queue size of {}",
-        nump, numc, per_thread, args.size
+    if interrupted {
+        Err(SimError::Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+/// Labels a combination of a run matrix, e.g. `2/4/32`.
+fn combination(config: &Config) -> String {
+    format!(
+        "{}/{}/{}",
+        config.producers, config.consumers, config.queue_size
+    )
+}
+
+/// Installs a Ctrl-C handler that sets the returned flag, or exits on a
+/// second Ctrl-C.
+fn interrupt_flag() -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop);
+    let handler = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::Relaxed) {
+            eprintln!("Interrupted again, exiting immediately");
+            process::exit(i32::from(EXIT_INTERRUPTED));
+        }
+        eprintln!("Interrupted, finishing in-flight items (Ctrl-C again to force exit)");
+    });
+    if let Err(err) = handler {
+        eprintln!("warning: Ctrl-C will not stop the run cleanly: {err}");
+    }
+    stop
+}
+
+/// Turns a failed trial into an error, first printing its report with
+/// `"status": "failed"` if JSON output was requested.
+fn failed(args: &Args, failure: TrialFailure) -> SimError {
+    if args.output == Output::Json {
+        println!(
+            "{}",
+            serde_json::to_string(&Report::failed(&failure, args.warmup))
+                .expect("report serializes to JSON")
+        );
+    }
+    SimError::Verification(failure)
+}
+
+/// Prints why the run did not succeed to stderr, with everything known about
+/// a failed trial.
+fn report_error(err: &SimError) {
+    let failure = match err {
+        SimError::Usage(err) => {
+            let _ = err.print();
+            return;
+        }
+        // The partial results already say the run was interrupted
+        SimError::Interrupted => return,
+        SimError::Verification(failure) => failure,
+        SimError::ScenarioFile(_) | SimError::ScenariosFailed { .. } | SimError::Pipe(_) => {
+            eprintln!("ERROR! {err}");
+            return;
+        }
+    };
+    print_failure(failure);
+}
+
+/// Prints everything known about a trial that failed verification to stderr.
+fn print_failure(failure: &TrialFailure) {
+    let outcome = &failure.outcome;
+    eprintln!("ERROR! {failure}");
+    if let Some((sent, received)) = outcome.checksums {
+        eprintln!("  produced checksum: {sent}");
+        eprintln!("  consumed checksum: {received}");
+    }
+    for violation in &outcome.violations {
+        eprintln!("  {violation}");
+    }
+    for dead in &outcome.panicked {
+        eprintln!(
+            "  {} panicked after {} items: {}",
+            dead.thread, dead.items, dead.message
+        );
+    }
+    eprintln!(
+        "  produced {}, consumed {}, corrupted {}, queue empty after join: {}",
+        outcome.produced, outcome.consumed, outcome.corrupted, outcome.queue_empty
     );
+    for line in threads::table(&outcome.threads) {
+        eprintln!("  {line}");
+    }
+}
 
-    let queue = Arc::new(Queue::new(args.size));
-    let start = Instant::now();
+/// Appends the report to the `--csv` file, if one was given.
+fn append_csv(args: &Args, report: &Report) {
+    if let Some(path) = &args.csv
+        && let Err(err) = report.append_csv(path)
+    {
+        eprintln!("warning: could not append to {}: {err}", path.display());
+    }
+}
 
-    let produced = Arc::new(Mutex::new(0usize));
-    let consumed = Arc::new(Mutex::new(0usize));
-
-    // Spawn producers
-    let producers: Vec<_> = (0..nump)
-        .map(|_| {
-            let q = Arc::clone(&queue);
-            let prod_count = Arc::clone(&produced);
-            thread::spawn(move || {
-                let mut rng = rand::rng();
-                for i in 0..per_thread {
-                    if args.delay {
-                        let delay = rng.random_range(0..1_000_000);
-                        thread::sleep(Duration::from_nanos(delay));
-                    }
+/// Runs the trials of a single configuration and prints the results.
+///
+/// # Returns
+///
+/// Whether the run was interrupted.
+///
+/// # Errors
+///
+/// Returns the first trial that failed verification.
+fn run_single(args: &Args, config: &Config, stop: &Arc<AtomicBool>) -> Result<bool, SimError> {
+    let outcomes = sim::run_trials(config, args.trials, args.warmup, stop)
+        .map_err(|failure| failed(args, failure))?;
 
-                    q.enqueue(Box::new(i));
-                    *prod_count.lock().unwrap() += 1;
-                }
-            })
-        })
+    let report = Report::new(config, &outcomes, args.warmup);
+    append_csv(args, &report);
+
+    if let Some(path) = &args.trace {
+        let samples: Vec<_> = outcomes.iter().map(|o| o.occupancy.as_slice()).collect();
+        if let Err(err) = occupancy::write_trace(path, &samples) {
+            eprintln!("warning: could not write {}: {err}", path.display());
+        }
+    }
+    if let Some(path) = &args.throughput_trace {
+        let samples: Vec<_> = outcomes
+            .iter()
+            .map(|o| o.consumed_samples.as_slice())
+            .collect();
+        if let Err(err) = throughput::write_trace(path, &samples) {
+            eprintln!("warning: could not write {}: {err}", path.display());
+        }
+    }
+
+    match args.output {
+        Output::Human => print_human(config, &report, &outcomes),
+        Output::Json => println!(
+            "{}",
+            serde_json::to_string(&report).expect("report serializes to JSON")
+        ),
+    }
+    Ok(report.interrupted)
+}
+
+/// Reports the points of a sweep or backend comparison as a table or JSON.
+///
+/// # Arguments
+///
+/// * `points` - Configuration and kept outcomes of each point that ran.
+/// * `column` - Heading of the column that tells the points apart.
+/// * `label` - Value of that column for a point's configuration.
+/// * `to_json` - Serializes the reports of every point for `--output json`.
+///
+/// # Returns
+///
+/// Whether the run was interrupted.
+fn run_points(
+    args: &Args,
+    points: &[(Config, Vec<Outcome>)],
+    column: &str,
+    label: impl Fn(&Config) -> String,
+    to_json: impl FnOnce(Vec<Report>) -> serde_json::Result<String>,
+) -> bool {
+    let reports: Vec<_> = points
+        .iter()
+        .map(|(config, outcomes)| Report::new(config, outcomes, args.warmup))
         .collect();
+    for report in &reports {
+        append_csv(args, report);
+    }
+    let interrupted = reports.last().is_some_and(|r| r.interrupted);
 
-    // Spawn consumers
-    let consumers: Vec<_> = (0..numc)
-        .map(|_| {
-            let q = Arc::clone(&queue);
-            let cons_count = Arc::clone(&consumed);
-            thread::spawn(move || {
-                let mut rng = rand::rng();
-                loop {
-                    if args.delay {
-                        let delay = rng.random_range(0..1_000_000);
-                        thread::sleep(Duration::from_nanos(delay));
-                    }
+    match args.output {
+        Output::Human => {
+            if interrupted {
+                println!("INTERRUPTED: partial results after Ctrl-C");
+            }
+            println!(
+                "{:>10}  {:>14}  {:>12}  {:>12}  {:>9}",
+                column, "items/sec", "stddev", "median ms", "relative"
+            );
+            let best = reports
+                .iter()
+                .map(|r| r.items_per_sec.mean)
+                .fold(0.0, f64::max);
+            for report in &reports {
+                let rate = report.items_per_sec.mean;
+                println!(
+                    "{:>10}  {:>14.0}  {:>12.0}  {:>12.3}  {:>8.1}%",
+                    label(&report.config),
+                    rate,
+                    report.items_per_sec.stddev,
+                    report.elapsed_secs.median * 1000.0,
+                    if best > 0.0 { rate * 100.0 / best } else { 0.0 }
+                );
+            }
+        }
+        Output::Json => println!("{}", to_json(reports).expect("report serializes to JSON")),
+    }
+    interrupted
+}
 
-                    if let Some(item) = q.dequeue() {
-                        drop(item); // free the boxed int
-                        *cons_count.lock().unwrap() += 1;
-                    } else if q.is_shutdown() {
-                        break;
+/// Runs every scenario in turn on top of `base` and reports which passed.
+///
+/// A scenario that fails verification is reported and the next one still
+/// runs; an interrupted scenario ends the run.
+///
+/// # Returns
+///
+/// Whether the run was interrupted.
+///
+/// # Errors
+///
+/// Returns how many scenarios failed, if any did.
+fn run_scenarios(
+    args: &Args,
+    base: &Config,
+    scenarios: &[Scenario],
+    stop: &Arc<AtomicBool>,
+) -> Result<bool, SimError> {
+    let start = Instant::now();
+    let mut results = Vec::with_capacity(scenarios.len());
+    for scenario in scenarios {
+        let delay = if scenario.delay {
+            DelayRange::UP_TO_1MS
+        } else {
+            DelayRange::NONE
+        };
+        let config = Config {
+            producers: scenario.producers,
+            consumers: scenario.consumers,
+            items: scenario.items,
+            queue_size: scenario.size,
+            producer_delay: delay,
+            consumer_delay: delay,
+            seed: scenario.seed.unwrap_or(base.seed),
+            ..base.clone()
+        };
+        let report = match sim::run_trials(&config, args.trials, args.warmup, stop) {
+            Ok(outcomes) => Report::new(&config, &outcomes, args.warmup),
+            Err(failure) => {
+                eprint!("{}: ", scenario.name);
+                print_failure(&failure);
+                Report::failed(&failure, args.warmup)
+            }
+        };
+        append_csv(args, &report);
+        let interrupted = report.interrupted;
+        results.push(NamedReport {
+            name: scenario.name.clone(),
+            report,
+        });
+        if interrupted {
+            break;
+        }
+    }
+    let total_secs = start.elapsed().as_secs_f64();
+    let failed = results
+        .iter()
+        .filter(|r| r.report.status == Status::Failed)
+        .count();
+    let interrupted = results.last().is_some_and(|r| r.report.interrupted);
+
+    match args.output {
+        Output::Human => {
+            if interrupted {
+                println!("INTERRUPTED: partial results after Ctrl-C");
+            }
+            println!(
+                "{:<20}  {:>9}  {:>8}  {:>5}  {:>5}  {:>10}  {:>12}  result",
+                "scenario", "threads", "items", "size", "delay", "median ms", "items/sec"
+            );
+            for NamedReport { name, report } in &results {
+                let config = &report.config;
+                println!(
+                    "{:<20}  {:>9}  {:>8}  {:>5}  {:>5}  {:>10.3}  {:>12.0}  {}",
+                    name,
+                    format!("{}/{}", config.producers, config.consumers),
+                    config.items,
+                    config.queue_size,
+                    config.is_delayed(),
+                    report.elapsed_secs.median * 1000.0,
+                    report.items_per_sec.mean,
+                    match report.status {
+                        Status::Ok => "PASS",
+                        Status::Interrupted => "INTERRUPTED",
+                        Status::Failed => "FAIL",
                     }
-                }
+                );
+            }
+            println!(
+                "Passed {} of {} scenarios in {:.3} ms",
+                results.len() - failed,
+                scenarios.len(),
+                total_secs * 1000.0
+            );
+        }
+        Output::Json => println!(
+            "{}",
+            serde_json::to_string(&ScenarioReport {
+                scenarios: results,
+                failed,
+                total_secs,
             })
+            .expect("report serializes to JSON")
+        ),
+    }
+
+    if failed > 0 {
+        Err(SimError::ScenariosFailed {
+            failed,
+            total: scenarios.len(),
         })
-        .collect();
+    } else {
+        Ok(interrupted)
+    }
+}
 
-    // Wait for all producers
-    for p in producers {
-        p.join().unwrap();
+/// Prints the human-readable summary of a single configuration.
+fn print_human(config: &Config, report: &Report, outcomes: &[Outcome]) {
+    let last = outcomes.last().expect("at least one trial");
+    if report.interrupted {
+        println!("INTERRUPTED: partial results after Ctrl-C");
+    }
+    if config.verify_order && !report.interrupted {
+        println!("Order verified: every item consumed once, in FIFO order");
     }
+    if let Some((sent, _)) = last.checksums {
+        println!("Checksum verified: {sent}");
+    }
+    println!("Queue is empty: {}", last.queue_empty);
+    if let Some(per_producer) = config.per_producer() {
+        println!("Total requested: {} ({per_producer})", config.items);
+    }
+    if let Some(burst) = config.burst {
+        println!(
+            "Bursts: {} items then a {} ms pause",
+            burst.size, burst.pause_ms
+        );
+    }
+    println!("Total produced: {}", last.produced);
+    println!("Total consumed: {}", last.consumed);
+    if let Some(warmup) = report.warmup_excluded {
+        println!(
+            "Warmup: {} items over {:.3} ms left out of the measurements",
+            warmup.items,
+            warmup.secs * 1000.0
+        );
+    }
+    print_threads(&report.threads);
 
-    // Shutdown the queue to unblock consumers
-    queue.shutdown();
+    if report.trials > 1 {
+        let (t, r) = (&report.elapsed_secs, &report.items_per_sec);
+        println!(
+            "Trials: {} measured, {} warmup discarded",
+            report.trials, report.warmup
+        );
+        println!(
+            "Elapsed ms: min {:.3}, median {:.3}, mean {:.3}, stddev {:.3}",
+            t.min * 1000.0,
+            t.median * 1000.0,
+            t.mean * 1000.0,
+            t.stddev * 1000.0
+        );
+        println!(
+            "Items/sec: min {:.0}, median {:.0}, mean {:.0}, stddev {:.0}",
+            r.min, r.median, r.mean, r.stddev
+        );
+    }
 
-    // Wait for all consumers
-    for c in consumers {
-        c.join().unwrap();
+    if let Some(p) = &report.latency {
+        println!(
+            "Latency us over {} items: p50 {:.1}, p90 {:.1}, p99 {:.1}, p99.9 {:.1}, max {:.1}",
+            p.samples,
+            p.p50_ns as f64 / 1000.0,
+            p.p90_ns as f64 / 1000.0,
+            p.p99_ns as f64 / 1000.0,
+            p.p999_ns as f64 / 1000.0,
+            p.max_ns as f64 / 1000.0
+        );
     }
 
-    let total_produced = *produced.lock().unwrap();
-    let total_consumed = *consumed.lock().unwrap();
+    println!(
+        "Throughput: {:.0} items/sec, {:.2} MiB/sec of {:?} payload",
+        report.items_per_sec.mean,
+        report.bytes_per_sec.mean / (1024.0 * 1024.0),
+        config.payload
+    );
+
+    if let Some(o) = &report.occupancy {
+        println!(
+            "Occupancy over {} samples: mean depth {:.2}, max {}, full {:.1}%, empty {:.1}%",
+            o.samples, o.mean_depth, o.max_depth, o.full_pct, o.empty_pct
+        );
+    }
+
+    if let Some(series) = &report.throughput {
+        let cv = series
+            .cv
+            .map_or_else(|| String::from("n/a"), |cv| format!("{cv:.2}"));
+        println!(
+            "Throughput over {} intervals: {} (CV {cv})",
+            series.points.len(),
+            series.sparkline(throughput::SPARKLINE_WIDTH)
+        );
+    }
 
-    if total_produced != total_consumed {
-        eprintln!("ERROR! produced != consumed");
-        std::process::abort();
+    if let Some(m) = &report.memory {
+        println!(
+            "Memory over {} samples: RSS peak {}, mean {}; queues peak {}, mean {}",
+            m.samples,
+            memory::human(m.peak_rss_bytes.map(|b| b as f64)),
+            memory::human(m.mean_rss_bytes),
+            memory::human(m.peak_queue_bytes.map(|b| b as f64)),
+            memory::human(m.mean_queue_bytes)
+        );
     }
 
-    println!("Queue is empty: {}", queue.is_empty());
-    println!("Total produced: {}", total_produced);
-    println!("Total consumed: {}", total_consumed);
+    if let Some(stages) = &report.stages {
+        print_stages(stages);
+    }
 
-    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
-    println!("Took {}s with {} produced.", elapsed, total_produced);
+    println!(
+        "{}",
+        report::took(
+            (last.warmup_elapsed + last.elapsed).as_secs_f64(),
+            last.produced
+        )
+    );
+}
+
+/// Prints one row per pipeline stage and names the stage that limits
+/// throughput.
+fn print_stages(stages: &[StageReport]) {
+    println!(
+        "{:<6}  {:>7}  {:>10}  {:>12}  {:>9}  {:>10}",
+        "stage", "threads", "items", "items/sec", "in queue", "input full"
+    );
+    for s in stages {
+        let full = s
+            .input
+            .map_or_else(|| String::from("-"), |o| format!("{:.1}%", o.full_pct));
+        println!(
+            "{:<6}  {:>7}  {:>10}  {:>12.0}  {:>8.1}%  {:>10}",
+            s.stage, s.threads, s.items, s.items_per_sec, s.queue_pct, full
+        );
+    }
+    if let Some(bottleneck) = StageReport::bottleneck(stages) {
+        println!(
+            "Bottleneck: stage {} ({:.1}% of its time in queue calls)",
+            bottleneck.stage, bottleneck.queue_pct
+        );
+    }
+}
+
+/// Prints one row per thread so a producer or consumer that lagged stands out.
+fn print_threads(threads: &[ThreadStats]) {
+    for line in threads::table(threads) {
+        println!("{line}");
+    }
+    for role in [Role::Producer, Role::Consumer] {
+        if threads.iter().filter(|t| t.role == role).count() > 1
+            && let Some(slowest) = threads::slowest(threads, role)
+        {
+            println!(
+                "Slowest {}: {} at {:.0} items/sec",
+                role.name(),
+                slowest.id,
+                slowest.items_per_sec()
+            );
+        }
+    }
 }
@@ -0,0 +1,126 @@
+//! Every combination of the thread counts and queue sizes listed on the
+//! command line, e.g. `-p 1,2,4 -c 1,4 -s 2,8,32`.
+
+use std::fmt;
+
+/// One point of a run matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Combination {
+    /// Number of producer threads.
+    pub producers: usize,
+    /// Number of consumer threads.
+    pub consumers: usize,
+    /// Capacity of the queue.
+    pub queue_size: usize,
+}
+
+/// A matrix with more combinations than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooMany {
+    /// Number of combinations the lists expand to, saturated at `usize::MAX`.
+    pub combinations: usize,
+    /// Most combinations allowed.
+    pub max: usize,
+}
+
+impl fmt::Display for TooMany {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the listed values expand to {} combinations, more than --max-combinations {}",
+            self.combinations, self.max
+        )
+    }
+}
+
+/// Expands the listed values into every combination of them.
+///
+/// Combinations come in the order of nested loops over producers, then
+/// consumers, then queue sizes, each in the order given, so the queue size
+/// changes fastest.
+///
+/// # Arguments
+///
+/// * `producers` - Producer counts to try; must not be empty.
+/// * `consumers` - Consumer counts to try; must not be empty.
+/// * `queue_sizes` - Queue capacities to try; must not be empty.
+/// * `max` - Most combinations allowed.
+///
+/// # Errors
+///
+/// Returns how many combinations there would be if that is more than `max`.
+pub fn expand(
+    producers: &[usize],
+    consumers: &[usize],
+    queue_sizes: &[usize],
+    max: usize,
+) -> Result<Vec<Combination>, TooMany> {
+    let combinations = producers
+        .len()
+        .saturating_mul(consumers.len())
+        .saturating_mul(queue_sizes.len());
+    if combinations > max {
+        return Err(TooMany { combinations, max });
+    }
+    Ok(producers
+        .iter()
+        .flat_map(|&producers| {
+            consumers.iter().flat_map(move |&consumers| {
+                queue_sizes.iter().map(move |&queue_size| Combination {
+                    producers,
+                    consumers,
+                    queue_size,
+                })
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(producers: usize, consumers: usize, queue_size: usize) -> Combination {
+        Combination {
+            producers,
+            consumers,
+            queue_size,
+        }
+    }
+
+    #[test]
+    fn test_expand_order() {
+        let points = expand(&[1, 2], &[4, 1], &[8, 2, 32], 100).unwrap();
+        assert_eq!(points.len(), 12);
+        assert_eq!(
+            &points[..4],
+            [
+                point(1, 4, 8),
+                point(1, 4, 2),
+                point(1, 4, 32),
+                point(1, 1, 8)
+            ]
+        );
+        assert_eq!(points[6], point(2, 4, 8));
+        assert_eq!(points[11], point(2, 1, 32));
+    }
+
+    #[test]
+    fn test_single_values() {
+        assert_eq!(expand(&[3], &[2], &[5], 1).unwrap(), [point(3, 2, 5)]);
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(expand(&[1, 2], &[1, 2], &[1, 2], 8).unwrap().len(), 8);
+        let err = expand(&[1, 2], &[1, 2], &[1, 2], 7).unwrap_err();
+        assert_eq!(
+            err,
+            TooMany {
+                combinations: 8,
+                max: 7
+            }
+        );
+        assert!(err.to_string().contains("8 combinations"), "{err}");
+    }
+}
@@ -0,0 +1,225 @@
+//! Memory used during a run, sampled with `--report-memory`.
+//!
+//! The process's resident set size is read from `/proc/self/statm` on Linux;
+//! elsewhere it is unavailable. The queues' own share is their
+//! [`Queue::memory_usage`](fifo_bounded_buffer::Queue::memory_usage)
+//! estimate, which counts the payloads of the items they hold.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Interval between samples when `--sample-ms` does not set one.
+pub const DEFAULT_SAMPLE_MS: u64 = 10;
+
+/// Page size assumed when the kernel does not say.
+const FALLBACK_PAGE_SIZE: usize = 4096;
+/// Key of the page size in the auxiliary vector.
+const AT_PAGESZ: usize = 6;
+
+/// Memory in use at one point in a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemorySample {
+    /// Time since the run started.
+    pub elapsed: Duration,
+    /// Resident set size of the process, if the platform can tell.
+    pub rss_bytes: Option<usize>,
+    /// Estimated bytes held by all queues, if the backend can tell.
+    pub queue_bytes: Option<usize>,
+}
+
+/// Peak and average memory across a set of samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// Number of samples taken.
+    pub samples: usize,
+    /// Largest resident set size seen, if it could be read.
+    pub peak_rss_bytes: Option<usize>,
+    /// Average resident set size, if it could be read.
+    pub mean_rss_bytes: Option<f64>,
+    /// Largest estimate of the bytes held by the queues, if available.
+    pub peak_queue_bytes: Option<usize>,
+    /// Average estimate of the bytes held by the queues, if available.
+    pub mean_queue_bytes: Option<f64>,
+}
+
+impl Summary {
+    /// Summarizes `samples`.
+    ///
+    /// # Returns
+    ///
+    /// The summary, or `None` if there are no samples.
+    pub fn of(samples: &[MemorySample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let rss: Vec<_> = samples.iter().filter_map(|s| s.rss_bytes).collect();
+        let queues: Vec<_> = samples.iter().filter_map(|s| s.queue_bytes).collect();
+        Some(Self {
+            samples: samples.len(),
+            peak_rss_bytes: rss.iter().max().copied(),
+            mean_rss_bytes: mean(&rss),
+            peak_queue_bytes: queues.iter().max().copied(),
+            mean_queue_bytes: mean(&queues),
+        })
+    }
+}
+
+/// Average of `values`, or `None` if there are none.
+fn mean(values: &[usize]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<usize>() as f64 / values.len() as f64)
+}
+
+/// Formats a byte count for people, e.g. `1.5 MiB`, or `unavailable`.
+pub fn human(bytes: Option<f64>) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let Some(mut value) = bytes else {
+        return String::from("unavailable");
+    };
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Reads the resident set size of this process.
+///
+/// # Returns
+///
+/// The size in bytes, or `None` where it cannot be read.
+pub fn rss_bytes() -> Option<usize> {
+    if cfg!(target_os = "linux") {
+        let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+        parse_statm(&statm, page_size())
+    } else {
+        None
+    }
+}
+
+/// Parses the resident set size out of a `/proc/<pid>/statm` line.
+///
+/// # Arguments
+///
+/// * `statm` - The line: total, resident, shared, text, lib, data and
+///   dirty sizes in pages.
+/// * `page_size` - Bytes per page.
+///
+/// # Returns
+///
+/// The resident size in bytes, or `None` if the line is malformed.
+pub fn parse_statm(statm: &str, page_size: usize) -> Option<usize> {
+    let mut fields = statm.split_whitespace();
+    let _total = fields.next()?;
+    let resident: usize = fields.next()?.parse().ok()?;
+    resident.checked_mul(page_size)
+}
+
+/// Bytes per page of memory, read once from the auxiliary vector.
+fn page_size() -> usize {
+    static PAGE_SIZE: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+    *PAGE_SIZE.get_or_init(|| {
+        std::fs::read("/proc/self/auxv")
+            .ok()
+            .and_then(|auxv| parse_auxv_page_size(&auxv))
+            .unwrap_or(FALLBACK_PAGE_SIZE)
+    })
+}
+
+/// Finds the page size in the raw contents of `/proc/self/auxv`, pairs of
+/// native-endian words ending with a zero key.
+fn parse_auxv_page_size(auxv: &[u8]) -> Option<usize> {
+    const WORD: usize = size_of::<usize>();
+    let word = |bytes: &[u8]| usize::from_ne_bytes(bytes.try_into().expect("one word"));
+    auxv.chunks_exact(2 * WORD)
+        .map(|pair| (word(&pair[..WORD]), word(&pair[WORD..])))
+        .take_while(|&(key, _)| key != 0)
+        .find(|&(key, _)| key == AT_PAGESZ)
+        .map(|(_, value)| value)
+        .filter(|&size| size > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(rss_bytes: Option<usize>, queue_bytes: Option<usize>) -> MemorySample {
+        MemorySample {
+            elapsed: Duration::ZERO,
+            rss_bytes,
+            queue_bytes,
+        }
+    }
+
+    #[test]
+    fn test_parse_statm() {
+        assert_eq!(
+            parse_statm("2516 380 331 1 0 159 0\n", 4096),
+            Some(380 * 4096)
+        );
+        assert_eq!(
+            parse_statm("2516 380 331 1 0 159 0", 65536),
+            Some(380 * 65536)
+        );
+        assert_eq!(parse_statm("  10   2  ", 4096), Some(8192));
+        assert_eq!(parse_statm("2516", 4096), None);
+        assert_eq!(parse_statm("2516 x 331", 4096), None);
+        assert_eq!(parse_statm("", 4096), None);
+        assert_eq!(parse_statm("1 18446744073709551615", 4096), None);
+    }
+
+    #[test]
+    fn test_parse_auxv_page_size() {
+        let auxv: Vec<u8> = [(33, 7), (AT_PAGESZ, 16384), (0, 0), (AT_PAGESZ, 1)]
+            .iter()
+            .flat_map(|&(k, v): &(usize, usize)| [k.to_ne_bytes(), v.to_ne_bytes()])
+            .flatten()
+            .collect();
+        assert_eq!(parse_auxv_page_size(&auxv), Some(16384));
+        assert_eq!(parse_auxv_page_size(&auxv[..2 * size_of::<usize>()]), None);
+        assert_eq!(parse_auxv_page_size(&[]), None);
+    }
+
+    #[test]
+    fn test_rss_is_read_on_linux() {
+        assert_eq!(rss_bytes().is_some(), cfg!(target_os = "linux"));
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Summary::of(&[]), None);
+        let summary = Summary::of(&[
+            sample(Some(1000), Some(10)),
+            sample(Some(3000), Some(30)),
+            sample(None, Some(20)),
+        ])
+        .unwrap();
+        assert_eq!(summary.samples, 3);
+        assert_eq!(
+            (summary.peak_rss_bytes, summary.mean_rss_bytes),
+            (Some(3000), Some(2000.0))
+        );
+        assert_eq!(
+            (summary.peak_queue_bytes, summary.mean_queue_bytes),
+            (Some(30), Some(20.0))
+        );
+
+        let unavailable = Summary::of(&[sample(None, None)]).unwrap();
+        assert_eq!(
+            (unavailable.peak_rss_bytes, unavailable.mean_rss_bytes),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_human() {
+        assert_eq!(human(None), "unavailable");
+        assert_eq!(human(Some(512.0)), "512 B");
+        assert_eq!(human(Some(1536.0)), "1.5 KiB");
+        assert_eq!(human(Some(3.0 * 1024.0 * 1024.0)), "3.0 MiB");
+    }
+}
@@ -0,0 +1,133 @@
+//! Periodic samples of the queue's depth during a run.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// A queue's length at one point in a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Zero-based position of the sampled queue in the pipeline.
+    pub queue: usize,
+    /// Time since the run started.
+    pub elapsed: Duration,
+    /// Items in the queue.
+    pub len: usize,
+}
+
+/// How full the queue was across a set of samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// Number of samples taken.
+    pub samples: usize,
+    /// Average number of items in the queue.
+    pub mean_depth: f64,
+    /// Largest number of items seen in the queue.
+    pub max_depth: usize,
+    /// Percentage of samples that found the queue at capacity.
+    pub full_pct: f64,
+    /// Percentage of samples that found the queue empty.
+    pub empty_pct: f64,
+}
+
+impl Summary {
+    /// Summarizes `samples` of a queue holding at most `capacity` items.
+    ///
+    /// # Returns
+    ///
+    /// The summary, or `None` if there are no samples.
+    pub fn of(samples: &[Sample], capacity: usize) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let pct = |pred: fn(&Sample, usize) -> bool| {
+            samples.iter().filter(|s| pred(s, capacity)).count() as f64 * 100.0 / n
+        };
+
+        Some(Self {
+            samples: samples.len(),
+            mean_depth: samples.iter().map(|s| s.len).sum::<usize>() as f64 / n,
+            max_depth: samples.iter().map(|s| s.len).max().unwrap_or(0),
+            full_pct: pct(|s, capacity| s.len >= capacity),
+            empty_pct: pct(|s, _| s.len == 0),
+        })
+    }
+}
+
+/// Writes samples as CSV with `trial,elapsed_ms,len,queue` columns, the
+/// queue counted from one.
+///
+/// # Arguments
+///
+/// * `path` - File to create or truncate.
+/// * `trials` - Samples of each trial, in order.
+///
+/// # Errors
+///
+/// Returns any error from creating or writing the file.
+pub fn write_trace(path: &Path, trials: &[&[Sample]]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "trial,elapsed_ms,len,queue")?;
+    for (trial, samples) in trials.iter().enumerate() {
+        for sample in samples.iter() {
+            writeln!(
+                out,
+                "{},{:.3},{},{}",
+                trial + 1,
+                sample.elapsed.as_secs_f64() * 1000.0,
+                sample.len,
+                sample.queue + 1
+            )?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(lens: &[usize]) -> Vec<Sample> {
+        lens.iter()
+            .enumerate()
+            .map(|(i, &len)| Sample {
+                queue: 0,
+                elapsed: Duration::from_millis(i as u64 * 10),
+                len,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::of(&samples(&[0, 4, 4, 2, 0, 1, 4, 0]), 4).unwrap();
+        assert_eq!(summary.samples, 8);
+        assert_eq!(summary.mean_depth, 15.0 / 8.0);
+        assert_eq!(summary.max_depth, 4);
+        assert_eq!(summary.full_pct, 37.5);
+        assert_eq!(summary.empty_pct, 37.5);
+
+        assert_eq!(Summary::of(&[], 4), None);
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let path = std::env::temp_dir().join(format!("fifo-trace-{}.csv", std::process::id()));
+        let first = samples(&[0, 3]);
+        let second: Vec<_> = samples(&[1])
+            .into_iter()
+            .map(|s| Sample { queue: 1, ..s })
+            .collect();
+        write_trace(&path, &[&first, &second]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            contents,
+            "trial,elapsed_ms,len,queue\n1,0.000,0,1\n1,10.000,3,1\n2,0.000,1,2\n"
+        );
+    }
+}
@@ -0,0 +1,393 @@
+//! Sequence-stamped queue that restores input order after parallel processing.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::Queue;
+
+/// A work queue paired with a bounded reorder buffer.
+///
+/// Items are stamped with a sequence number on [`enqueue`](Self::enqueue) and
+/// handed to workers through an ordinary [`Queue`]. Workers process them in any
+/// order and [`submit`](Self::submit) each result under its sequence number;
+/// [`next_in_order`](Self::next_in_order) then releases results strictly in
+/// sequence. This is the decode → process in parallel → emit in order pattern.
+///
+/// Every dequeued sequence number must eventually be submitted, otherwise
+/// `next_in_order` waits for it forever. With several producers, the output
+/// order is the order in which sequence numbers were handed out.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use fifo_bounded_buffer::OrderedQueue;
+///
+/// let ordered = OrderedQueue::new(4, 4);
+///
+/// let workers: Vec<_> = (0..2)
+///     .map(|_| {
+///         let q = Arc::clone(&ordered);
+///         thread::spawn(move || {
+///             while let Some((seq, n)) = q.dequeue() {
+///                 q.submit(seq, n * 10);
+///             }
+///         })
+///     })
+///     .collect();
+///
+/// let emitter = {
+///     let q = Arc::clone(&ordered);
+///     thread::spawn(move || {
+///         let mut out = Vec::new();
+///         while let Some(result) = q.next_in_order() {
+///             out.push(result);
+///         }
+///         out
+///     })
+/// };
+///
+/// for n in 0..5 {
+///     ordered.enqueue(n);
+/// }
+/// ordered.shutdown();
+///
+/// for w in workers {
+///     w.join().unwrap();
+/// }
+/// assert_eq!(emitter.join().unwrap(), vec![0, 10, 20, 30, 40]);
+/// ```
+pub struct OrderedQueue<T, R> {
+    input: Arc<Queue<(u64, T)>>,
+    next_seq: AtomicU64,
+    pub(crate) reorder: Mutex<Reorder<R>>,
+    ready: Condvar,
+    space: Condvar,
+    reorder_capacity: usize,
+    #[cfg(any(test, feature = "test-hooks"))]
+    pub(crate) hooks: Condvar,
+}
+
+/// Results waiting for their turn, protected by the reorder mutex.
+///
+/// - `pending`: submitted results keyed by sequence number; `None` marks a
+///   sequence number whose item was never delivered to the workers
+/// - `next_out`: the sequence number `next_in_order` releases next
+/// - `blocked_submitters`: threads currently waiting on `space`
+/// - `blocked_emitters`: threads currently waiting on `ready`
+pub(crate) struct Reorder<R> {
+    pending: BTreeMap<u64, Option<R>>,
+    next_out: u64,
+    pub(crate) blocked_submitters: usize,
+    pub(crate) blocked_emitters: usize,
+}
+
+impl<T, R> OrderedQueue<T, R> {
+    /// Creates a new `OrderedQueue`.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of items waiting for a worker.
+    /// * `reorder_capacity` - Maximum number of results held back waiting for an
+    ///   earlier sequence number.
+    ///
+    /// # Returns
+    ///
+    /// A reference-counted pointer (`Arc`) to the new `OrderedQueue` instance.
+    pub fn new(capacity: usize, reorder_capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            input: Queue::new(capacity),
+            next_seq: AtomicU64::new(0),
+            reorder: Mutex::new(Reorder {
+                pending: BTreeMap::new(),
+                next_out: 0,
+                blocked_submitters: 0,
+                blocked_emitters: 0,
+            }),
+            ready: Condvar::new(),
+            space: Condvar::new(),
+            reorder_capacity,
+            #[cfg(any(test, feature = "test-hooks"))]
+            hooks: Condvar::new(),
+        })
+    }
+
+    /// Stamps an item with the next sequence number and enqueues it for the workers.
+    ///
+    /// Blocks while the work queue is full.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(seq)` - the sequence number assigned to the item.
+    /// * `None` - if the queue is shut down; the item is dropped.
+    pub fn enqueue(&self, item: T) -> Option<u64> {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        match self.input.enqueue_timed((seq, item)).0 {
+            Ok(()) => Some(seq),
+            Err(_) => {
+                self.skip(seq);
+                None
+            }
+        }
+    }
+
+    /// Removes the next item for a worker to process.
+    ///
+    /// Behaves like [`Queue::dequeue`]: blocks while no work is available and
+    /// returns `None` once the queue is shut down and drained.
+    pub fn dequeue(&self) -> Option<(u64, T)> {
+        self.input.dequeue()
+    }
+
+    /// Hands in the result for sequence number `seq`.
+    ///
+    /// Blocks while the reorder buffer is full, unless `seq` is the next number
+    /// to be released; that result is always accepted so progress is guaranteed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a result for `seq` was already submitted.
+    pub fn submit(&self, seq: u64, result: R) {
+        self.settle(seq, result);
+    }
+
+    /// Returns the next result in sequence order, blocking until it is submitted.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(result)` - the result for the lowest unreleased sequence number.
+    /// * `None` - once the queue is shut down and every accepted item's result
+    ///   has been released.
+    pub fn next_in_order(&self) -> Option<R> {
+        let mut reorder = self.reorder.lock().unwrap();
+        loop {
+            let next_out = reorder.next_out;
+            if let Some(entry) = reorder.pending.remove(&next_out) {
+                reorder.next_out += 1;
+                self.space.notify_all();
+                match entry {
+                    Some(result) => return Some(result),
+                    None => continue,
+                }
+            }
+
+            if self.input.is_shutdown() && next_out >= self.next_seq.load(Ordering::SeqCst) {
+                return None;
+            }
+            reorder.blocked_emitters += 1;
+            self.waiters_changed();
+            reorder = self.ready.wait(reorder).unwrap();
+            reorder.blocked_emitters -= 1;
+        }
+    }
+
+    /// Shuts down the work queue and wakes a waiting `next_in_order` caller.
+    ///
+    /// Items already enqueued are still handed to workers and their results are
+    /// still released in order.
+    pub fn shutdown(&self) {
+        self.input.shutdown();
+        let _reorder = self.reorder.lock().unwrap();
+        self.ready.notify_all();
+    }
+
+    /// Checks if the work queue has been shut down.
+    pub fn is_shutdown(&self) -> bool {
+        self.input.is_shutdown()
+    }
+
+    /// Marks `seq` as never delivered to the workers.
+    ///
+    /// Does not wait for room in the reorder buffer: after shutdown there may
+    /// be no `next_in_order` caller left to free it. Markers at the front of
+    /// the buffer are released here for the same reason.
+    fn skip(&self, seq: u64) {
+        let mut reorder = self.reorder.lock().unwrap();
+        reorder.pending.insert(seq, None);
+        let mut released = false;
+        while let Some(None) = reorder.pending.get(&reorder.next_out) {
+            let next_out = reorder.next_out;
+            reorder.pending.remove(&next_out);
+            reorder.next_out += 1;
+            released = true;
+        }
+        if released {
+            self.space.notify_all();
+            self.ready.notify_all();
+        }
+    }
+
+    /// Records the result for `seq`, waiting for room in the reorder buffer.
+    fn settle(&self, seq: u64, result: R) {
+        let mut reorder = self.reorder.lock().unwrap();
+        if reorder.pending.len() >= self.reorder_capacity && seq != reorder.next_out {
+            reorder.blocked_submitters += 1;
+            self.waiters_changed();
+            while reorder.pending.len() >= self.reorder_capacity && seq != reorder.next_out {
+                reorder = self.space.wait(reorder).unwrap();
+            }
+            reorder.blocked_submitters -= 1;
+        }
+
+        assert!(
+            seq >= reorder.next_out && !reorder.pending.contains_key(&seq),
+            "result for sequence number {seq} was already submitted"
+        );
+        reorder.pending.insert(seq, Some(result));
+        if seq == reorder.next_out {
+            self.ready.notify_all();
+        }
+    }
+
+    /// Wakes threads parked in the `test-hooks` wait functions; a no-op otherwise.
+    fn waiters_changed(&self) {
+        #[cfg(any(test, feature = "test-hooks"))]
+        self.hooks.notify_all();
+    }
+}
+
+impl<T, R> fmt::Debug for OrderedQueue<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderedQueue")
+            .field("next_seq", &self.next_seq)
+            .field("reorder_capacity", &self.reorder_capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_released_in_order_with_random_delays() {
+        let ordered = OrderedQueue::new(8, 4);
+        let n = 500u64;
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let q = Arc::clone(&ordered);
+                thread::spawn(move || {
+                    let mut rng = rand::rng();
+                    while let Some((seq, item)) = q.dequeue() {
+                        thread::sleep(Duration::from_micros(rng.random_range(0..500)));
+                        q.submit(seq, item);
+                    }
+                })
+            })
+            .collect();
+
+        let emitter = {
+            let q = Arc::clone(&ordered);
+            thread::spawn(move || {
+                let mut out = Vec::new();
+                while let Some(item) = q.next_in_order() {
+                    out.push(item);
+                }
+                out
+            })
+        };
+
+        for i in 0..n {
+            assert_eq!(ordered.enqueue(i), Some(i));
+        }
+        ordered.shutdown();
+
+        for w in workers {
+            w.join().unwrap();
+        }
+        assert_eq!(emitter.join().unwrap(), (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_next_in_order_waits_for_gap() {
+        let ordered = OrderedQueue::<u32, u32>::new(4, 4);
+        for i in 0..3 {
+            ordered.enqueue(i);
+        }
+        let (s0, _) = ordered.dequeue().unwrap();
+        let (s1, _) = ordered.dequeue().unwrap();
+        let (s2, _) = ordered.dequeue().unwrap();
+
+        ordered.submit(s2, 2);
+        ordered.submit(s1, 1);
+
+        let q = Arc::clone(&ordered);
+        let emitter = thread::spawn(move || (q.next_in_order(), q.next_in_order()));
+
+        assert!(ordered.wait_for_blocked_emitters(1, Duration::from_secs(5)));
+        ordered.submit(s0, 0);
+        assert_eq!(emitter.join().unwrap(), (Some(0), Some(1)));
+        assert_eq!(ordered.next_in_order(), Some(2));
+    }
+
+    #[test]
+    fn test_enqueue_after_shutdown_does_not_stall_output() {
+        let ordered = OrderedQueue::<u32, u32>::new(4, 4);
+        ordered.enqueue(7);
+        ordered.shutdown();
+        assert_eq!(ordered.enqueue(8), None);
+
+        let (seq, item) = ordered.dequeue().unwrap();
+        ordered.submit(seq, item);
+
+        assert_eq!(ordered.next_in_order(), Some(7));
+        assert_eq!(ordered.next_in_order(), None);
+    }
+
+    #[test]
+    fn test_enqueues_after_drain_do_not_block() {
+        let ordered = OrderedQueue::<u32, u32>::new(4, 1);
+        ordered.enqueue(7);
+        ordered.shutdown();
+        let (seq, item) = ordered.dequeue().unwrap();
+        ordered.submit(seq, item);
+        assert_eq!(ordered.next_in_order(), Some(7));
+        assert_eq!(ordered.next_in_order(), None);
+
+        assert_eq!(ordered.enqueue(1), None);
+        assert_eq!(ordered.enqueue(2), None);
+        assert_eq!(ordered.next_in_order(), None);
+    }
+
+    #[test]
+    fn test_full_reorder_buffer_blocks_out_of_order_submit() {
+        let ordered = OrderedQueue::<u32, u32>::new(4, 1);
+        for i in 0..3 {
+            ordered.enqueue(i);
+        }
+        let (s0, _) = ordered.dequeue().unwrap();
+        let (s1, _) = ordered.dequeue().unwrap();
+        let (s2, _) = ordered.dequeue().unwrap();
+
+        ordered.submit(s1, 1);
+        let q = Arc::clone(&ordered);
+        let blocked = thread::spawn(move || q.submit(s2, 2));
+
+        assert!(ordered.wait_for_blocked_submitters(1, Duration::from_secs(5)));
+        assert!(!blocked.is_finished());
+
+        // The next expected result is always accepted.
+        ordered.submit(s0, 0);
+        assert_eq!(ordered.next_in_order(), Some(0));
+        assert_eq!(ordered.next_in_order(), Some(1));
+        blocked.join().unwrap();
+        assert_eq!(ordered.next_in_order(), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "already submitted")]
+    fn test_duplicate_submit_panics() {
+        let ordered = OrderedQueue::<u32, u32>::new(4, 4);
+        ordered.enqueue(1);
+        let (seq, _) = ordered.dequeue().unwrap();
+        ordered.submit(seq, 1);
+        ordered.submit(seq, 1);
+    }
+}
@@ -0,0 +1,145 @@
+//! Item payloads of configurable type and size, checked by consumers.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::verify::Tagged;
+
+/// Type of the value carried by each item.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadKind {
+    /// A single integer derived from the item's tag.
+    Int,
+    /// A heap-allocated byte vector of the configured size.
+    Bytes,
+    /// A heap-allocated ASCII string of the configured size.
+    String,
+}
+
+/// The value carried by an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Int(u64),
+    Bytes(Vec<u8>),
+    String(String),
+}
+
+/// Byte `i` of the pattern for the item tagged `tag`.
+///
+/// Mixing the tag into every byte means a payload delivered with the wrong
+/// item, or partly overwritten by another, fails validation.
+fn pattern_byte(tag: Tagged, i: usize) -> u8 {
+    (tag.0.wrapping_mul(31) ^ tag.1.wrapping_mul(7)).wrapping_add(i) as u8
+}
+
+/// The integer payload for the item tagged `tag`.
+fn int_value(tag: Tagged) -> u64 {
+    ((tag.0 as u64) << 40) ^ (tag.1 as u64).wrapping_mul(0x9E37_79B9)
+}
+
+impl Payload {
+    /// Builds the payload for the item tagged `tag`.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Type of payload to build.
+    /// * `bytes` - Size of `Bytes` and `String` payloads; ignored for `Int`.
+    /// * `tag` - Producer and sequence number of the item.
+    pub fn new(kind: PayloadKind, bytes: usize, tag: Tagged) -> Self {
+        match kind {
+            PayloadKind::Int => Payload::Int(int_value(tag)),
+            PayloadKind::Bytes => {
+                Payload::Bytes((0..bytes).map(|i| pattern_byte(tag, i)).collect())
+            }
+            PayloadKind::String => Payload::String(
+                (0..bytes)
+                    .map(|i| char::from(b'a' + pattern_byte(tag, i) % 26))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Bytes the payload holds on the heap.
+    pub fn heap_bytes(&self) -> usize {
+        match self {
+            Payload::Int(_) => 0,
+            Payload::Bytes(bytes) => bytes.len(),
+            Payload::String(string) => string.len(),
+        }
+    }
+
+    /// Whether this is exactly the payload [`Payload::new`] builds for the
+    /// same arguments.
+    pub fn is_valid(&self, kind: PayloadKind, bytes: usize, tag: Tagged) -> bool {
+        match (self, kind) {
+            (Payload::Int(v), PayloadKind::Int) => *v == int_value(tag),
+            (Payload::Bytes(b), PayloadKind::Bytes) => {
+                b.len() == bytes
+                    && b.iter()
+                        .enumerate()
+                        .all(|(i, &v)| v == pattern_byte(tag, i))
+            }
+            (Payload::String(s), PayloadKind::String) => {
+                s.len() == bytes
+                    && s.bytes()
+                        .enumerate()
+                        .all(|(i, v)| v == b'a' + pattern_byte(tag, i) % 26)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Bytes of payload data carried by each item.
+///
+/// # Arguments
+///
+/// * `kind` - Type of payload.
+/// * `bytes` - Configured size of `Bytes` and `String` payloads.
+pub fn item_bytes(kind: PayloadKind, bytes: usize) -> usize {
+    match kind {
+        PayloadKind::Int => size_of::<u64>(),
+        PayloadKind::Bytes | PayloadKind::String => bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads_validate() {
+        for kind in [PayloadKind::Int, PayloadKind::Bytes, PayloadKind::String] {
+            for tag in [(0, 0), (3, 17), (255, 1_000_000)] {
+                let payload = Payload::new(kind, 100, tag);
+                assert!(payload.is_valid(kind, 100, tag), "{kind:?} {tag:?}");
+            }
+        }
+        assert_eq!(
+            Payload::new(PayloadKind::Bytes, 0, (1, 1)),
+            Payload::Bytes(Vec::new())
+        );
+        assert_eq!(item_bytes(PayloadKind::Int, 100), 8);
+        assert_eq!(item_bytes(PayloadKind::String, 100), 100);
+    }
+
+    #[test]
+    fn test_detects_corruption_and_mixing() {
+        let tag = (2, 5);
+        let Payload::Bytes(mut bytes) = Payload::new(PayloadKind::Bytes, 64, tag) else {
+            unreachable!()
+        };
+        bytes[40] ^= 1;
+        assert!(!Payload::Bytes(bytes).is_valid(PayloadKind::Bytes, 64, tag));
+
+        // Another item's payload, a truncated one, or the wrong kind
+        let other = Payload::new(PayloadKind::String, 64, (2, 6));
+        assert!(!other.is_valid(PayloadKind::String, 64, tag));
+        let short = Payload::new(PayloadKind::String, 63, tag);
+        assert!(!short.is_valid(PayloadKind::String, 64, tag));
+        let int = Payload::new(PayloadKind::Int, 64, tag);
+        assert!(!int.is_valid(PayloadKind::Bytes, 64, tag));
+        assert!(!Payload::new(PayloadKind::Int, 0, (1, 5)).is_valid(PayloadKind::Int, 0, tag));
+    }
+}
@@ -0,0 +1,219 @@
+//! Checkpoint/restore of buffered items, enabled by the `persist` feature.
+//!
+//! The on-disk format is a `bincode`-encoded item count followed by each item
+//! encoded in FIFO order. Only the buffered items are stored; the shutdown flag
+//! is not part of a checkpoint.
+
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use bincode::error::{DecodeError, EncodeError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::Queue;
+
+/// Most bytes a single checkpoint entry may decode from, so a corrupt length
+/// prefix is rejected instead of being allocated.
+const MAX_ENTRY_BYTES: usize = 64 * 1024 * 1024;
+
+impl<T: Serialize> Queue<T> {
+    /// Writes the currently buffered items to `w`, front to back.
+    ///
+    /// The queue is locked for the duration of the write, so the checkpoint is a
+    /// consistent snapshot and concurrent `enqueue`/`dequeue` calls wait until it
+    /// completes. Items stay in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - Destination of the checkpoint.
+    ///
+    /// # Returns
+    ///
+    /// The number of items written.
+    ///
+    /// # Errors
+    ///
+    /// Returns any I/O error raised by `w`, or `InvalidData` if an item cannot be serialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue(String::from("a"));
+    /// queue.enqueue(String::from("b"));
+    ///
+    /// let mut buf = Vec::new();
+    /// assert_eq!(queue.checkpoint_to(&mut buf).unwrap(), 2);
+    /// ```
+    pub fn checkpoint_to<W: Write>(&self, mut w: W) -> io::Result<usize> {
+        let inner = self.inner.lock().unwrap();
+
+        encode(&(inner.buffer.len() as u64), &mut w)?;
+        for item in &inner.buffer {
+            encode(item, &mut w)?;
+        }
+        w.flush()?;
+
+        Ok(inner.buffer.len())
+    }
+}
+
+impl<T: DeserializeOwned> Queue<T> {
+    /// Creates a new `Queue` pre-loaded with the items of a checkpoint.
+    ///
+    /// Items are restored in the order they were written, so dequeuing from the
+    /// restored queue yields the same sequence as the original would have.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of elements the queue can hold.
+    /// * `r` - Source of a checkpoint produced by [`Queue::checkpoint_to`].
+    ///
+    /// # Returns
+    ///
+    /// A reference-counted pointer (`Arc`) to the restored `Queue` instance.
+    ///
+    /// # Errors
+    ///
+    /// - `UnexpectedEof` if the checkpoint is truncated.
+    /// - `InvalidData` if the checkpoint is corrupt, holds more items than
+    ///   `capacity`, or claims an item larger than 64 MiB.
+    /// - Any other I/O error raised by `r`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue(1u32);
+    /// queue.enqueue(2u32);
+    ///
+    /// let mut buf = Vec::new();
+    /// queue.checkpoint_to(&mut buf).unwrap();
+    ///
+    /// let restored = Queue::<u32>::restore_from(4, buf.as_slice()).unwrap();
+    /// assert_eq!(restored.dequeue(), Some(1));
+    /// assert_eq!(restored.dequeue(), Some(2));
+    /// ```
+    pub fn restore_from<R: Read>(capacity: usize, mut r: R) -> io::Result<Arc<Self>> {
+        let len: u64 = decode(&mut r)?;
+        if len > capacity as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint holds {len} items but capacity is {capacity}"),
+            ));
+        }
+
+        let queue = Self::new(capacity);
+        {
+            let mut inner = queue.inner.lock().unwrap();
+            for _ in 0..len {
+                queue.push(&mut inner, decode(&mut r)?);
+            }
+        }
+
+        Ok(queue)
+    }
+}
+
+fn encode<V: Serialize, W: Write>(value: &V, w: &mut W) -> io::Result<()> {
+    bincode::serde::encode_into_std_write(value, w, bincode::config::standard())
+        .map(|_| ())
+        .map_err(|e| match e {
+            EncodeError::Io { inner, .. } => inner,
+            other => io::Error::new(io::ErrorKind::InvalidData, other),
+        })
+}
+
+fn decode<V: DeserializeOwned, R: Read>(r: &mut R) -> io::Result<V> {
+    let config = bincode::config::standard().with_limit::<MAX_ENTRY_BYTES>();
+    bincode::serde::decode_from_std_read(r, config).map_err(|e| match e {
+        DecodeError::Io { inner, .. } => inner,
+        DecodeError::UnexpectedEnd { .. } => io::Error::from(io::ErrorKind::UnexpectedEof),
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_restore_preserves_order() {
+        let queue = Queue::new(8);
+        for word in ["alpha", "beta", "gamma", "delta"] {
+            queue.enqueue(String::from(word));
+        }
+
+        let mut buf = Vec::new();
+        assert_eq!(queue.checkpoint_to(&mut buf).unwrap(), 4);
+
+        // Checkpointing does not consume the items.
+        assert_eq!(queue.dequeue().as_deref(), Some("alpha"));
+
+        let restored = Queue::<String>::restore_from(8, buf.as_slice()).unwrap();
+        assert!(!restored.is_shutdown());
+        for word in ["alpha", "beta", "gamma", "delta"] {
+            assert_eq!(restored.dequeue().as_deref(), Some(word));
+        }
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_empty_queue() {
+        let queue = Queue::<u64>::new(2);
+        let mut buf = Vec::new();
+        assert_eq!(queue.checkpoint_to(&mut buf).unwrap(), 0);
+
+        let restored = Queue::<u64>::restore_from(2, buf.as_slice()).unwrap();
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn test_restore_truncated_input_is_error() {
+        let queue = Queue::new(4);
+        queue.enqueue(vec![1u8, 2, 3]);
+        queue.enqueue(vec![4u8, 5, 6]);
+
+        let mut buf = Vec::new();
+        queue.checkpoint_to(&mut buf).unwrap();
+
+        for cut in 0..buf.len() {
+            let err = Queue::<Vec<u8>>::restore_from(4, &buf[..cut]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "cut at {cut}");
+        }
+    }
+
+    #[test]
+    fn test_restore_corrupt_input_is_error() {
+        let err = Queue::<String>::restore_from(4, [1u8, 2, 0xff, 0xfe].as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_restore_oversized_length_prefix_is_error() {
+        // One item: a string whose u64 length prefix claims about 2^60 bytes.
+        let mut buf = vec![1u8, 0xfd];
+        buf.extend_from_slice(&(u64::MAX >> 4).to_le_bytes());
+        let err = Queue::<String>::restore_from(4, buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_restore_over_capacity_is_error() {
+        let queue = Queue::new(3);
+        queue.enqueue(1u32);
+        queue.enqueue(2u32);
+        queue.enqueue(3u32);
+
+        let mut buf = Vec::new();
+        queue.checkpoint_to(&mut buf).unwrap();
+
+        let err = Queue::<u32>::restore_from(2, buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,262 @@
+//! The `pipe` subcommand: stdin copied to stdout through the bounded queue.
+//!
+//! One reader thread splits the input into lines, or chunks of a fixed size,
+//! and enqueues them numbered in order. Worker threads dequeue and transform
+//! them and hand the results to a single writer, which holds back results
+//! that overtook an earlier one until it arrives, so the output keeps the
+//! input's order. A full queue stops the reader from reading further ahead.
+
+use clap::ValueEnum;
+use fifo_bounded_buffer::Queue;
+use log::debug;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::thread;
+
+/// What workers do to every line or chunk.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// Pass the bytes through unchanged.
+    None,
+    /// Upper-case ASCII letters.
+    Upper,
+    /// Rotate ASCII letters by 13 places.
+    Rot13,
+}
+
+impl Transform {
+    /// Applies the transform to `bytes` in place.
+    pub fn apply(self, bytes: &mut [u8]) {
+        match self {
+            Transform::None => {}
+            Transform::Upper => bytes.make_ascii_uppercase(),
+            Transform::Rot13 => bytes.iter_mut().for_each(|b| {
+                *b = match *b {
+                    b'a'..=b'z' => (*b - b'a' + 13) % 26 + b'a',
+                    b'A'..=b'Z' => (*b - b'A' + 13) % 26 + b'A',
+                    other => other,
+                }
+            }),
+        }
+    }
+}
+
+/// How a pipe splits, transforms and buffers its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipeConfig {
+    /// Capacity of the queue to the workers, and of the one to the writer.
+    pub queue_size: usize,
+    /// Worker threads transforming items.
+    pub workers: usize,
+    /// Size of the chunks the input is split into, or `None` for lines.
+    pub chunk_bytes: Option<usize>,
+    /// What workers do to every item.
+    pub transform: Transform,
+}
+
+/// A line or chunk and its position in the input.
+type Numbered = (u64, Vec<u8>);
+
+/// Copies `input` to `output` through the queue.
+///
+/// # Arguments
+///
+/// * `input` - Where items are read from until end of file.
+/// * `output` - Where transformed items are written, in input order.
+/// * `config` - How the input is split, transformed and buffered.
+///
+/// # Returns
+///
+/// The number of lines or chunks read.
+///
+/// # Errors
+///
+/// Returns the first error reading `input` or writing `output`, after every
+/// thread has stopped. The output being closed early is not an error.
+pub fn run<R, W>(input: R, output: W, config: &PipeConfig) -> io::Result<u64>
+where
+    R: BufRead + Send,
+    W: Write + Send,
+{
+    let to_workers: Arc<Queue<Numbered>> = Queue::new(config.queue_size);
+    let to_writer: Arc<Queue<Numbered>> = Queue::new(config.queue_size);
+    let transform = config.transform;
+
+    thread::scope(|s| {
+        let reader = s.spawn(|| {
+            let read = read_items(input, config.chunk_bytes, &to_workers);
+            // End of input, or an error: either way the workers drain and stop
+            to_workers.shutdown();
+            read
+        });
+        let workers: Vec<_> = (0..config.workers)
+            .map(|id| {
+                let (input, output) = (&to_workers, &to_writer);
+                s.spawn(move || {
+                    let mut items = 0;
+                    while let Some((seq, mut bytes)) = input.dequeue() {
+                        transform.apply(&mut bytes);
+                        output.enqueue((seq, bytes));
+                        items += 1;
+                    }
+                    debug!("pipe worker {id} finished after {items} items");
+                })
+            })
+            .collect();
+        let writer = s.spawn(|| {
+            let written = write_in_order(output, &to_writer);
+            if written.is_err() {
+                // Nobody will take what is queued; wake everyone up instead
+                to_workers.shutdown();
+                to_writer.shutdown();
+            }
+            written
+        });
+
+        for worker in workers {
+            worker.join().expect("pipe worker panicked");
+        }
+        to_writer.shutdown();
+        let read = reader.join().expect("pipe reader panicked");
+        match writer.join().expect("pipe writer panicked") {
+            Err(err) if err.kind() == ErrorKind::BrokenPipe => read,
+            Err(err) => Err(err),
+            Ok(()) => read,
+        }
+    })
+}
+
+/// Reads `input` into numbered items on `queue` until end of file or until
+/// the queue is shut down.
+///
+/// # Returns
+///
+/// The number of items read.
+///
+/// # Errors
+///
+/// Returns any error from reading `input`.
+fn read_items<R: BufRead>(
+    mut input: R,
+    chunk_bytes: Option<usize>,
+    queue: &Queue<Numbered>,
+) -> io::Result<u64> {
+    let mut seq = 0;
+    while !queue.is_shutdown() {
+        let mut bytes = Vec::new();
+        let read = match chunk_bytes {
+            Some(size) => (&mut input).take(size as u64).read_to_end(&mut bytes)?,
+            None => input.read_until(b'\n', &mut bytes)?,
+        };
+        if read == 0 {
+            break;
+        }
+        queue.enqueue((seq, bytes));
+        seq += 1;
+    }
+    debug!("pipe reader finished after {seq} items");
+    Ok(seq)
+}
+
+/// Writes the items on `queue` to `output` in sequence order until the queue
+/// is shut down and empty.
+///
+/// # Errors
+///
+/// Returns any error from writing `output`.
+fn write_in_order<W: Write>(output: W, queue: &Queue<Numbered>) -> io::Result<()> {
+    let mut output = io::BufWriter::new(output);
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    while let Some((seq, bytes)) = queue.dequeue() {
+        pending.insert(seq, bytes);
+        while let Some(bytes) = pending.remove(&next) {
+            output.write_all(&bytes)?;
+            next += 1;
+        }
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(workers: usize, chunk_bytes: Option<usize>, transform: Transform) -> PipeConfig {
+        PipeConfig {
+            queue_size: 2,
+            workers,
+            chunk_bytes,
+            transform,
+        }
+    }
+
+    fn pipe(input: &[u8], config: &PipeConfig) -> (Vec<u8>, u64) {
+        let mut output = Vec::new();
+        let items = run(input, &mut output, config).unwrap();
+        (output, items)
+    }
+
+    #[test]
+    fn test_transforms() {
+        let mut bytes = *b"Hello, World! 123";
+        Transform::Upper.apply(&mut bytes);
+        assert_eq!(&bytes, b"HELLO, WORLD! 123");
+        let mut bytes = *b"Hello, World! xyz";
+        Transform::Rot13.apply(&mut bytes);
+        assert_eq!(&bytes, b"Uryyb, Jbeyq! klm");
+        Transform::Rot13.apply(&mut bytes);
+        assert_eq!(&bytes, b"Hello, World! xyz");
+    }
+
+    #[test]
+    fn test_lines_keep_their_order() {
+        let input: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        for workers in [1, 4] {
+            let (output, items) = pipe(input.as_bytes(), &config(workers, None, Transform::None));
+            assert_eq!(items, 1000);
+            assert_eq!(String::from_utf8(output).unwrap(), input);
+        }
+
+        let (output, items) = pipe(b"ab\ncd", &config(3, None, Transform::Upper));
+        assert_eq!((output.as_slice(), items), (&b"AB\nCD"[..], 2));
+        assert_eq!(
+            pipe(b"", &config(2, None, Transform::None)),
+            (Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn test_chunks() {
+        let input: Vec<u8> = (0..10_000u32).map(|i| b'a' + (i % 26) as u8).collect();
+        let (output, items) = pipe(&input, &config(4, Some(64), Transform::Rot13));
+        assert_eq!(items, 10_000_u64.div_ceil(64));
+        let mut expected = input.clone();
+        Transform::Rot13.apply(&mut expected);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_closed_output_stops_the_pipe() {
+        /// Accepts a few bytes and then reports the reader gone.
+        struct Closed(usize);
+        impl Write for Closed {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(ErrorKind::BrokenPipe.into());
+                }
+                let n = buf.len().min(self.0);
+                self.0 -= n;
+                Ok(n)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let input = vec![b'x'; 1 << 20];
+        let config = config(2, Some(16), Transform::None);
+        assert!(run(input.as_slice(), Closed(100), &config).is_ok());
+    }
+}
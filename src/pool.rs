@@ -0,0 +1,156 @@
+//! Managed consumer threads that drain a queue until it terminates.
+
+use std::panic;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::Queue;
+
+/// A group of consumer threads spawned by [`Queue::spawn_consumers`].
+///
+/// Each thread loops on `dequeue` and exits once the queue is shut down and
+/// drained, so the pool finishes only after someone calls [`Queue::shutdown`].
+#[derive(Debug)]
+#[must_use = "dropping a ConsumerPool detaches its threads; call `join` to wait for them"]
+pub struct ConsumerPool {
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl ConsumerPool {
+    /// Returns the number of threads in the pool.
+    pub fn len(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Checks if the pool has no threads.
+    pub fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    /// Blocks until the queue terminates and every consumer thread has exited.
+    ///
+    /// # Panics
+    ///
+    /// If any consumer panicked, the first panic is resumed on the calling thread
+    /// after all threads have been joined.
+    pub fn join(self) {
+        let mut first_panic = None;
+        for thread in self.threads {
+            if let Err(payload) = thread.join() {
+                first_panic.get_or_insert(payload);
+            }
+        }
+
+        if let Some(payload) = first_panic {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl<T: Send + 'static> Queue<T> {
+    /// Spawns `n` consumer threads that dequeue items and pass each one to `f`.
+    ///
+    /// The threads run until the queue is shut down and drained. A panic in `f`
+    /// stops only the thread it happened on; it is re-raised by
+    /// [`ConsumerPool::join`].
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of consumer threads to spawn.
+    /// * `f` - Called once per dequeued item, concurrently from all threads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(8);
+    /// let total = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let sum = Arc::clone(&total);
+    /// let pool = queue.spawn_consumers(2, move |item: usize| {
+    ///     sum.fetch_add(item, Ordering::Relaxed);
+    /// });
+    ///
+    /// for i in 1..=10 {
+    ///     queue.enqueue(i);
+    /// }
+    /// queue.shutdown();
+    /// pool.join();
+    ///
+    /// assert_eq!(total.load(Ordering::Relaxed), 55);
+    /// ```
+    pub fn spawn_consumers<F>(self: &Arc<Self>, n: usize, f: F) -> ConsumerPool
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let threads = (0..n)
+            .map(|_| {
+                let q = Arc::clone(self);
+                let f = Arc::clone(&f);
+                thread::spawn(move || {
+                    while let Some(item) = q.dequeue() {
+                        f(item);
+                    }
+                })
+            })
+            .collect();
+
+        ConsumerPool { threads }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_pool_consumes_every_item() {
+        let queue = Queue::new(16);
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let c = Arc::clone(&count);
+        let pool = queue.spawn_consumers(4, move |_item: usize| {
+            c.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(pool.len(), 4);
+
+        for i in 0..10_000 {
+            queue.enqueue(i);
+        }
+        queue.shutdown();
+        pool.join();
+
+        assert_eq!(count.load(Ordering::Relaxed), 10_000);
+        assert!(queue.is_terminated());
+    }
+
+    #[test]
+    fn test_pool_with_zero_threads() {
+        let queue = Queue::<usize>::new(1);
+        let pool = queue.spawn_consumers(0, |_| {});
+        assert!(pool.is_empty());
+        pool.join();
+    }
+
+    #[test]
+    #[should_panic(expected = "bad item")]
+    fn test_pool_join_propagates_panic() {
+        let queue = Queue::new(4);
+        let pool = queue.spawn_consumers(2, |item: usize| {
+            if item == 3 {
+                panic!("bad item");
+            }
+        });
+
+        for i in 0..6 {
+            queue.enqueue(i);
+        }
+        queue.shutdown();
+        pool.join();
+    }
+}
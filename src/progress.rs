@@ -0,0 +1,255 @@
+//! Live counters of a run, read by the watchdog and by `--progress`.
+//!
+//! Simulation threads bump a relaxed atomic counter of their own for every
+//! item they move, so watching a run costs it no locks; the queue depth is
+//! estimated as produced minus consumed rather than asked of the queue.
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Counters of one thread.
+struct Slot {
+    label: String,
+    items: AtomicUsize,
+    finished: AtomicBool,
+}
+
+/// Items moved so far by every thread of a run.
+pub struct Progress {
+    slots: Vec<Slot>,
+}
+
+impl Progress {
+    /// Creates zeroed counters for threads named by `labels`, in the order
+    /// they are referred to by index.
+    pub fn new(labels: Vec<String>) -> Arc<Self> {
+        let slots = labels
+            .into_iter()
+            .map(|label| Slot {
+                label,
+                items: AtomicUsize::new(0),
+                finished: AtomicBool::new(false),
+            })
+            .collect();
+        Arc::new(Self { slots })
+    }
+
+    /// Counts one more item moved by thread `index`.
+    pub fn tick(&self, index: usize) {
+        self.slots[index].items.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marks thread `index` as done, so its idleness is not a stall.
+    pub fn finish(&self, index: usize) {
+        self.slots[index].finished.store(true, Ordering::Relaxed);
+    }
+
+    /// Current item counts, one per thread.
+    pub fn counts(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .map(|s| s.items.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Items moved so far by thread `index`.
+    pub fn items(&self, index: usize) -> usize {
+        self.slots[index].items.load(Ordering::Relaxed)
+    }
+
+    /// Items moved so far by the threads in `threads`.
+    pub fn sum(&self, threads: Range<usize>) -> usize {
+        self.slots[threads]
+            .iter()
+            .map(|s| s.items.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Name of thread `index`, e.g. `consumer 0`.
+    pub fn label(&self, index: usize) -> &str {
+        &self.slots[index].label
+    }
+
+    /// Whether thread `index` has left its loop.
+    pub fn is_finished(&self, index: usize) -> bool {
+        self.slots[index].finished.load(Ordering::Relaxed)
+    }
+
+    /// Whether every thread has left its loop.
+    pub fn all_finished(&self) -> bool {
+        (0..self.slots.len()).all(|index| self.is_finished(index))
+    }
+}
+
+/// Totals of a run at one moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    /// Time since the run started.
+    pub elapsed: Duration,
+    /// Items produced so far.
+    pub produced: usize,
+    /// Items consumed so far.
+    pub consumed: usize,
+}
+
+impl Snapshot {
+    /// Items consumed per second between `earlier` and this snapshot, zero
+    /// if no time passed.
+    pub fn rate_since(&self, earlier: &Snapshot) -> f64 {
+        let secs = self.elapsed.saturating_sub(earlier.elapsed).as_secs_f64();
+        if secs > 0.0 {
+            self.consumed.saturating_sub(earlier.consumed) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated time until `total` items are consumed at `rate` items per
+    /// second.
+    ///
+    /// # Returns
+    ///
+    /// The estimate, or `None` if the rate is zero.
+    pub fn eta(&self, total: usize, rate: f64) -> Option<Duration> {
+        let left = total.saturating_sub(self.consumed);
+        if left == 0 {
+            Some(Duration::ZERO)
+        } else if rate > 0.0 {
+            Some(Duration::from_secs_f64(left as f64 / rate))
+        } else {
+            None
+        }
+    }
+
+    /// The progress line printed after `earlier`.
+    ///
+    /// # Arguments
+    ///
+    /// * `earlier` - The previous snapshot, for the current rate.
+    /// * `total` - Items the run will produce, if fixed.
+    pub fn line(&self, earlier: &Snapshot, total: Option<usize>) -> String {
+        let rate = self.rate_since(earlier);
+        let mut line = format!(
+            "[{:>7.1}s] produced {}, consumed {}, {:.0} items/sec, in flight {}",
+            self.elapsed.as_secs_f64(),
+            self.produced,
+            self.consumed,
+            rate,
+            self.produced.saturating_sub(self.consumed)
+        );
+        if let Some(total) = total {
+            match self.eta(total, rate) {
+                Some(eta) => line.push_str(&format!(", ETA {:.1}s", eta.as_secs_f64())),
+                None => line.push_str(", ETA unknown"),
+            }
+        }
+        line
+    }
+}
+
+/// A thread printing a progress line to stderr at a fixed interval.
+pub struct Reporter {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Reporter {
+    /// Starts printing the progress of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - Counters the run's threads bump.
+    /// * `producers` - Indices of the producer threads in `progress`.
+    /// * `consumers` - Indices of the consumer threads in `progress`.
+    /// * `total` - Items the run will produce, if fixed.
+    /// * `interval` - Time between two lines.
+    pub fn start(
+        progress: Arc<Progress>,
+        producers: Range<usize>,
+        consumers: Range<usize>,
+        total: Option<usize>,
+        interval: Duration,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let start = Instant::now();
+        let thread = thread::spawn(move || {
+            let snapshot = || Snapshot {
+                elapsed: start.elapsed(),
+                produced: progress.sum(producers.clone()),
+                consumed: progress.sum(consumers.clone()),
+            };
+            let mut last = snapshot();
+            // Stopping drops the sender, which ends the wait at once
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let now = snapshot();
+                eprintln!("{}", now.line(&last, total));
+                last = now;
+            }
+        });
+        Self { stop, thread }
+    }
+
+    /// Stops printing and waits for the reporter thread to end.
+    pub fn stop(self) {
+        drop(self.stop);
+        self.thread.join().expect("progress reporter panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(ms: u64, produced: usize, consumed: usize) -> Snapshot {
+        Snapshot {
+            elapsed: Duration::from_millis(ms),
+            produced,
+            consumed,
+        }
+    }
+
+    #[test]
+    fn test_counters() {
+        let progress = Progress::new(vec![String::from("producer 0"), String::from("consumer 0")]);
+        (0..3).for_each(|_| progress.tick(0));
+        progress.tick(1);
+        assert_eq!(progress.counts(), [3, 1]);
+        assert_eq!(progress.items(0), 3);
+        assert_eq!((progress.sum(0..1), progress.sum(0..2)), (3, 4));
+        assert_eq!(progress.label(1), "consumer 0");
+
+        progress.finish(0);
+        assert!(progress.is_finished(0) && !progress.all_finished());
+        progress.finish(1);
+        assert!(progress.all_finished());
+    }
+
+    #[test]
+    fn test_rate_and_eta() {
+        let (earlier, now) = (snapshot(1000, 600, 500), snapshot(1500, 900, 700));
+        assert_eq!(now.rate_since(&earlier), 400.0);
+        assert_eq!(now.rate_since(&now), 0.0);
+
+        assert_eq!(now.eta(1500, 400.0), Some(Duration::from_secs(2)));
+        assert_eq!(now.eta(700, 0.0), Some(Duration::ZERO));
+        assert_eq!(now.eta(1500, 0.0), None);
+    }
+
+    #[test]
+    fn test_line() {
+        let (earlier, now) = (snapshot(1000, 600, 500), snapshot(1500, 900, 700));
+        assert_eq!(
+            now.line(&earlier, Some(1500)),
+            "[    1.5s] produced 900, consumed 700, 400 items/sec, in flight 200, ETA 2.0s"
+        );
+        assert_eq!(
+            now.line(&now, None),
+            "[    1.5s] produced 900, consumed 700, 0 items/sec, in flight 200"
+        );
+        assert!(now.line(&now, Some(1500)).ends_with("ETA unknown"));
+    }
+}
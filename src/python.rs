@@ -0,0 +1,281 @@
+//! Python bindings, enabled by the `python` feature.
+//!
+//! Exposes [`Queue`] to Python as `fifo_bounded_buffer.Queue`, holding
+//! arbitrary Python objects. The GIL is released while a call blocks, so other
+//! Python threads keep running. Timeouts raise the standard `queue.Full` and
+//! `queue.Empty` exceptions; a shut-down queue raises [`ShutDown`].
+//!
+//! The importable extension is built with `maturin`, which reads
+//! `pyproject.toml` and turns on the `extension-module` feature:
+//!
+//! ```text
+//! maturin develop --release
+//! python -c "import fifo_bounded_buffer; print(fifo_bounded_buffer.Queue(4))"
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::import_exception;
+use pyo3::prelude::*;
+
+use crate::Queue;
+
+create_exception!(
+    fifo_bounded_buffer,
+    ShutDown,
+    PyException,
+    "Raised by `put` on a shut-down queue, and by `get` once it is also drained."
+);
+
+import_exception!(queue, Empty);
+import_exception!(queue, Full);
+
+/// A bounded, blocking FIFO queue of Python objects.
+///
+/// Mirrors `queue.Queue`'s `put`/`get` interface, with the shutdown semantics
+/// of the Rust [`Queue`]: after `shutdown()`, `put` raises `ShutDown` and `get`
+/// keeps returning buffered objects until the queue is drained, then raises
+/// `ShutDown` where the Rust API would return `None`.
+#[pyclass(name = "Queue", module = "fifo_bounded_buffer", frozen)]
+pub struct PyQueue {
+    queue: Arc<Queue<PyObject>>,
+}
+
+/// Why a timed operation on the wrapped queue did not complete.
+enum Interrupted {
+    TimedOut,
+    ShutDown,
+}
+
+#[pymethods]
+impl PyQueue {
+    /// Creates a queue holding at most `maxsize` objects.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if `maxsize` is zero.
+    #[new]
+    fn new(maxsize: usize) -> PyResult<Self> {
+        if maxsize == 0 {
+            return Err(PyValueError::new_err("maxsize must be greater than zero"));
+        }
+        Ok(Self {
+            queue: Queue::new(maxsize),
+        })
+    }
+
+    /// Adds `obj` to the queue, blocking while it is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Seconds to wait for space; `None` waits indefinitely.
+    ///
+    /// # Errors
+    ///
+    /// - `queue.Full` if no space became available within `timeout`.
+    /// - `ShutDown` if the queue is shut down; `obj` is not added.
+    /// - `ValueError` if `timeout` is negative.
+    #[pyo3(signature = (obj, timeout=None))]
+    fn put(&self, py: Python<'_>, obj: PyObject, timeout: Option<f64>) -> PyResult<()> {
+        let deadline = deadline(timeout)?;
+        py.allow_threads(|| self.put_until(obj, deadline))
+            .map_err(|e| match e {
+                Interrupted::TimedOut => Full::new_err(()),
+                Interrupted::ShutDown => ShutDown::new_err("put on a shut-down queue"),
+            })
+    }
+
+    /// Removes and returns the object at the front of the queue, blocking while it is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Seconds to wait for an object; `None` waits indefinitely.
+    ///
+    /// # Errors
+    ///
+    /// - `queue.Empty` if no object arrived within `timeout`.
+    /// - `ShutDown` if the queue is shut down and drained.
+    /// - `ValueError` if `timeout` is negative.
+    #[pyo3(signature = (timeout=None))]
+    fn get(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<PyObject> {
+        let deadline = deadline(timeout)?;
+        py.allow_threads(|| self.get_until(deadline))
+            .map_err(|e| match e {
+                Interrupted::TimedOut => Empty::new_err(()),
+                Interrupted::ShutDown => ShutDown::new_err("get on a shut-down, drained queue"),
+            })
+    }
+
+    /// Shuts down the queue, waking every blocked `put` and `get`.
+    fn shutdown(&self) {
+        self.queue.shutdown();
+    }
+
+    /// Returns the number of buffered objects.
+    fn qsize(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns `True` if no objects are buffered.
+    fn empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl PyQueue {
+    fn put_until(&self, obj: PyObject, deadline: Option<Instant>) -> Result<(), Interrupted> {
+        let queue = &self.queue;
+        let inner = queue.inner.lock().unwrap();
+        let (mut inner, _) = queue.wait_for_space_until(inner, deadline);
+        if inner.shutdown {
+            return Err(Interrupted::ShutDown);
+        }
+        if inner.buffer.len() == queue.capacity {
+            return Err(Interrupted::TimedOut);
+        }
+
+        queue.push(&mut inner, obj);
+        queue.notify_consumer(&mut inner);
+        Ok(())
+    }
+
+    fn get_until(&self, deadline: Option<Instant>) -> Result<PyObject, Interrupted> {
+        let queue = &self.queue;
+        let inner = queue.inner.lock().unwrap();
+        let (mut inner, _) = queue.wait_for_item_until(inner, deadline);
+        match queue.pop(&mut inner) {
+            Some(obj) => {
+                queue.not_full.notify_one();
+                Ok(obj)
+            }
+            None if inner.shutdown => Err(Interrupted::ShutDown),
+            None => Err(Interrupted::TimedOut),
+        }
+    }
+}
+
+/// Converts a Python timeout in seconds into a deadline; `None` means no deadline.
+fn deadline(timeout: Option<f64>) -> PyResult<Option<Instant>> {
+    let Some(secs) = timeout else {
+        return Ok(None);
+    };
+    let timeout = Duration::try_from_secs_f64(secs)
+        .map_err(|_| PyValueError::new_err("timeout must be a non-negative number"))?;
+    Ok(Instant::now().checked_add(timeout))
+}
+
+/// The `fifo_bounded_buffer` Python module.
+#[pymodule]
+fn fifo_bounded_buffer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyQueue>()?;
+    m.add("ShutDown", m.py().get_type::<ShutDown>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::IntoPyObjectExt;
+    use std::thread;
+
+    fn with_queue(maxsize: usize, f: impl FnOnce(Python<'_>, &PyQueue)) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| f(py, &PyQueue::new(maxsize).unwrap()));
+    }
+
+    #[test]
+    fn test_put_get_roundtrip() {
+        with_queue(2, |py, queue| {
+            assert!(queue.empty());
+            queue.put(py, 1i64.into_py_any(py).unwrap(), None).unwrap();
+            queue.put(py, "two".into_py_any(py).unwrap(), None).unwrap();
+            assert_eq!(queue.qsize(), 2);
+
+            assert_eq!(queue.get(py, None).unwrap().extract::<i64>(py).unwrap(), 1);
+            assert_eq!(
+                queue.get(py, None).unwrap().extract::<String>(py).unwrap(),
+                "two"
+            );
+            assert!(queue.empty());
+        });
+    }
+
+    #[test]
+    fn test_timeouts_raise_queue_exceptions() {
+        with_queue(1, |py, queue| {
+            assert!(
+                queue
+                    .get(py, Some(0.05))
+                    .unwrap_err()
+                    .is_instance_of::<Empty>(py)
+            );
+
+            queue.put(py, py.None(), Some(0.0)).unwrap();
+            assert!(
+                queue
+                    .put(py, py.None(), Some(0.05))
+                    .unwrap_err()
+                    .is_instance_of::<Full>(py)
+            );
+            assert!(
+                queue
+                    .get(py, Some(-1.0))
+                    .unwrap_err()
+                    .is_instance_of::<PyValueError>(py)
+            );
+        });
+    }
+
+    #[test]
+    fn test_blocking_get_releases_gil() {
+        with_queue(1, |py, queue| {
+            let q = Arc::clone(&queue.queue);
+            // Needs the GIL to build the object, so it only gets through if
+            // `get` released it.
+            let producer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                Python::with_gil(|py| q.enqueue(42i64.into_py_any(py).unwrap()));
+            });
+
+            let item = queue.get(py, Some(5.0)).unwrap();
+            assert_eq!(item.extract::<i64>(py).unwrap(), 42);
+            py.allow_threads(|| producer.join().unwrap());
+        });
+    }
+
+    #[test]
+    fn test_shutdown_drains_then_raises() {
+        with_queue(2, |py, queue| {
+            queue.put(py, py.None(), None).unwrap();
+            queue.shutdown();
+
+            let err = queue.put(py, py.None(), None).unwrap_err();
+            assert!(err.is_instance_of::<ShutDown>(py));
+            assert!(queue.get(py, None).unwrap().is_none(py));
+            assert!(
+                queue
+                    .get(py, None)
+                    .unwrap_err()
+                    .is_instance_of::<ShutDown>(py)
+            );
+        });
+    }
+
+    #[test]
+    fn test_shutdown_wakes_blocked_get() {
+        with_queue(1, |py, queue| {
+            let q = Arc::clone(&queue.queue);
+            let closer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                q.shutdown();
+            });
+
+            let err = queue.get(py, None).unwrap_err();
+            assert!(err.is_instance_of::<ShutDown>(py));
+            py.allow_threads(|| closer.join().unwrap());
+        });
+    }
+}
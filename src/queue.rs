@@ -1,5 +1,35 @@
 use std::collections::VecDeque;
-use std::sync::{Arc, Condvar, Mutex};
+use std::fmt;
+use std::sync::{Arc, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::backoff::Backoff;
+use crate::sync::{AtomicBool, AtomicUsize, Condvar, Mutex, MutexGuard, Ordering, spin_loop};
+
+mod backoff;
+mod builder;
+mod clock;
+mod dyn_queue;
+mod handles;
+#[cfg(any(test, feature = "test-hooks"))]
+mod hooks;
+mod keyed;
+mod ordered;
+#[cfg(feature = "persist")]
+mod persist;
+mod pool;
+#[cfg(feature = "python")]
+mod python;
+mod sync;
+
+pub use builder::QueueBuilder;
+#[cfg(feature = "test-util")]
+pub use clock::{Clock, MockClock, SystemClock};
+pub use dyn_queue::DynQueue;
+pub use handles::{ConsumerHandle, ConsumerStats, ProducerHandle, ProducerStats};
+pub use keyed::KeyedQueue;
+pub use ordered::OrderedQueue;
+pub use pool::ConsumerPool;
 
 /// A thread-safe, bounded, blocking FIFO queue implemented with a monitor pattern.
 ///
@@ -44,22 +74,53 @@ use std::sync::{Arc, Condvar, Mutex};
 /// producer.join().unwrap();
 /// consumer.join().unwrap();
 /// ```
-#[derive(Debug)]
 pub struct Queue<T> {
     inner: Mutex<Inner<T>>,
     not_empty: Condvar,
     not_full: Condvar,
     capacity: usize,
+    item_size: Option<ItemSize<T>>,
+    item_bytes: AtomicUsize,
+    spin_budget: u32,
+    fair_consumers: bool,
+    len_hint: AtomicUsize,
+    shutdown_hint: AtomicBool,
+    #[cfg(test)]
+    spin_iterations: AtomicUsize,
+    producers: Mutex<Vec<Arc<handles::ProducerCounters>>>,
+    consumers: Mutex<Vec<Arc<handles::ConsumerCounters>>>,
+    clock: Arc<dyn clock::Clock>,
+    #[cfg(any(test, feature = "test-hooks"))]
+    hooks: Condvar,
 }
 
+/// Consecutive wakeups that find the wait condition still unmet before a waiter
+/// starts backing off.
+const FUTILE_WAKEUPS_BEFORE_BACKOFF: u32 = 2;
+
+/// User-supplied closure estimating the memory held by a single item.
+type ItemSize<T> = Box<dyn Fn(&T) -> usize + Send + Sync>;
+
 /// Inner shared state of the queue, protected by the mutex.
 ///
 /// - `buffer`: the actual queue storage
 /// - `shutdown`: a flag that signals termination to all threads
+/// - `blocked_producers`: threads currently waiting on `not_full`
+/// - `blocked_consumers`: threads currently waiting on `not_empty`
+/// - `consumer_tickets`: with fair consumers, the tickets of waiting consumers
+///   in arrival order; each new item is granted to the front ticket
+/// - `granted_consumers`: with fair consumers, waiters whose ticket was granted
+///   an item they have not taken yet; that many queued items are theirs
+/// - `next_ticket`: the ticket handed to the next waiting consumer
 #[derive(Debug)]
 struct Inner<T> {
     buffer: VecDeque<T>,
     shutdown: bool,
+    blocked_producers: usize,
+    blocked_consumers: usize,
+    consumer_tickets: VecDeque<u64>,
+    granted_consumers: usize,
+    next_ticket: u64,
 }
 
 impl<T> Queue<T> {
@@ -87,15 +148,51 @@ impl<T> Queue<T> {
     /// let queue: std::sync::Arc<Queue<i32>> = Queue::new(5);
     /// ```
     pub fn new(capacity: usize) -> Arc<Self> {
-        Arc::new(Self {
-            inner: Mutex::new(Inner {
-                buffer: VecDeque::with_capacity(capacity),
-                shutdown: false,
-            }),
-            not_empty: Condvar::new(),
-            not_full: Condvar::new(),
-            capacity,
-        })
+        QueueBuilder::new(capacity).build()
+    }
+
+    /// Returns a [`QueueBuilder`] for configuring a queue with a fixed capacity.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of elements the queue can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<Vec<u8>>::builder(8)
+    ///     .item_size(|item| item.len())
+    ///     .build();
+    /// ```
+    pub fn builder(capacity: usize) -> QueueBuilder<T> {
+        QueueBuilder::new(capacity)
+    }
+
+    /// Creates a new `Queue` whose timed operations read time from `clock`.
+    ///
+    /// Only available with the `test-util` feature; intended for making timeout
+    /// behavior deterministic in tests, typically with a [`MockClock`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::{MockClock, Queue};
+    ///
+    /// let clock = Arc::new(MockClock::new());
+    /// let queue = Queue::with_clock(4, clock.clone());
+    ///
+    /// queue.enqueue(1);
+    /// queue.shutdown();
+    /// queue.consume_batches(8, Duration::from_secs(1), |_| {});
+    /// assert_eq!(clock.elapsed(), Duration::ZERO);
+    /// ```
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn with_clock(capacity: usize, clock: Arc<dyn clock::Clock>) -> Arc<Self> {
+        QueueBuilder::new(capacity).clock(clock).build()
     }
 
     /// Adds an item to the queue, blocking if the queue is full.
@@ -125,17 +222,149 @@ impl<T> Queue<T> {
     /// queue.enqueue(10);
     /// ```
     pub fn enqueue(&self, item: T) {
-        let mut inner = self.inner.lock().unwrap();
-        while inner.buffer.len() == self.capacity && !inner.shutdown {
-            inner = self.not_full.wait(inner).unwrap();
-        }
+        let _ = self.enqueue_timed(item);
+    }
 
-        if inner.shutdown {
-            return;
+    /// Adds an item to the queue, retrying with exponential backoff before blocking.
+    ///
+    /// Each attempt only tries to take the lock and fails fast if it is held or
+    /// the queue is full; between attempts the thread spins, then yields, for
+    /// exponentially longer. Once the backoff is exhausted this falls back to a
+    /// blocking [`enqueue`](Self::enqueue). Under heavy producer contention on a
+    /// small queue this keeps losing producers from piling onto the lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item to add to the queue.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - the item was enqueued.
+    /// * `Err(item)` - the queue is shut down; the item is handed back.
+    ///
+    /// # Blocking
+    ///
+    /// - Blocks once the backoff is exhausted, until space becomes available or
+    ///   shutdown occurs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(1);
+    /// assert_eq!(queue.enqueue_with_backoff('a'), Ok(()));
+    ///
+    /// queue.shutdown();
+    /// assert_eq!(queue.enqueue_with_backoff('b'), Err('b'));
+    /// ```
+    pub fn enqueue_with_backoff(&self, item: T) -> Result<(), T> {
+        self.enqueue_with_backoff_timed(item).0
+    }
+
+    /// Backoff enqueue shared by `enqueue_with_backoff` and the producer handles.
+    ///
+    /// Also returns the time spent blocked once the backoff was exhausted;
+    /// spinning is not counted.
+    fn enqueue_with_backoff_timed(&self, item: T) -> (Result<(), T>, Duration) {
+        let mut backoff = Backoff::new();
+        while !backoff.is_completed() {
+            let mut inner = match self.inner.try_lock() {
+                Ok(inner) => inner,
+                Err(TryLockError::WouldBlock) => {
+                    backoff.snooze();
+                    continue;
+                }
+                Err(TryLockError::Poisoned(e)) => panic!("{e}"),
+            };
+
+            if inner.shutdown {
+                return (Err(item), Duration::ZERO);
+            }
+            if inner.buffer.len() < self.capacity {
+                self.push(&mut inner, item);
+                self.notify_consumer(&mut inner);
+                return (Ok(()), Duration::ZERO);
+            }
+            drop(inner);
+            backoff.snooze();
         }
 
-        inner.buffer.push_back(item);
-        self.not_empty.notify_one();
+        self.enqueue_timed(item)
+    }
+
+    /// Enqueues items from an iterator, pulling each one only once there is room for it.
+    ///
+    /// The queue's fullness paces the iterator: the next item is not requested
+    /// until a slot is free, so at most one item is materialized ahead of the
+    /// queue's capacity. Suitable for lazy or unbounded sources.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter` - Source of items, enqueued in iteration order.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` - the iterator was exhausted after enqueuing `count` items.
+    /// * `Err((count, item))` - the queue was shut down after `count` items were
+    ///   enqueued; `item` is the next item, which could not be delivered.
+    ///
+    /// # Blocking
+    ///
+    /// - Blocks while the queue is full until space becomes available or shutdown occurs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// assert_eq!(queue.enqueue_from_iter(0..3), Ok(3));
+    ///
+    /// queue.shutdown();
+    /// assert_eq!(queue.enqueue_from_iter(10..20), Err((0, 10)));
+    /// ```
+    pub fn enqueue_from_iter<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> Result<usize, (usize, T)> {
+        self.enqueue_from_iter_timed(iter).0
+    }
+
+    /// Iterator enqueue shared by `enqueue_from_iter` and the producer handles.
+    ///
+    /// Also returns the total time spent waiting for space.
+    fn enqueue_from_iter_timed<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> (Result<usize, (usize, T)>, Duration) {
+        let mut iter = iter.into_iter();
+        let mut count = 0;
+        let mut blocked = Duration::ZERO;
+        loop {
+            let shutdown = {
+                let inner = self.inner.lock().unwrap();
+                let (inner, waited) = self.wait_for_space(inner);
+                blocked += waited;
+                inner.shutdown
+            };
+
+            let Some(item) = iter.next() else {
+                // The slot waited for above goes unused; pass the wakeup on so
+                // another blocked producer does not keep sleeping on free space.
+                self.not_full.notify_one();
+                return (Ok(count), blocked);
+            };
+            if shutdown {
+                return (Err((count, item)), blocked);
+            }
+            let (result, waited) = self.enqueue_timed(item);
+            blocked += waited;
+            if let Err(item) = result {
+                return (Err((count, item)), blocked);
+            }
+            count += 1;
+        }
     }
 
     /// Removes and returns an item from the front of the queue.
@@ -163,16 +392,254 @@ impl<T> Queue<T> {
     /// assert_eq!(queue.dequeue(), Some(42));
     /// ```
     pub fn dequeue(&self) -> Option<T> {
+        self.dequeue_timed().0
+    }
+
+    /// Moves up to `buf.len()` items from the front of the queue into `buf`.
+    ///
+    /// Items are written to the start of `buf` in FIFO order; the previous values
+    /// of the overwritten slots are dropped. This lets a consumer reuse one scratch
+    /// buffer instead of allocating per batch.
+    ///
+    /// # Returns
+    ///
+    /// The number of items written. `0` means the queue is shut down and empty,
+    /// or that `buf` is empty (in which case the call does not block).
+    ///
+    /// # Blocking
+    ///
+    /// - Blocks while the queue is empty until an item is added or shutdown occurs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// for i in 1..=3 {
+    ///     queue.enqueue(i);
+    /// }
+    ///
+    /// let mut buf = [0; 2];
+    /// assert_eq!(queue.dequeue_into(&mut buf), 2);
+    /// assert_eq!(buf, [1, 2]);
+    /// assert_eq!(queue.dequeue_into(&mut buf), 1);
+    /// assert_eq!(buf[0], 3);
+    /// ```
+    pub fn dequeue_into(&self, buf: &mut [T]) -> usize {
+        self.dequeue_into_timed(buf).0
+    }
+
+    /// Moves up to `buf.len()` items into `buf` without blocking.
+    ///
+    /// The non-blocking sibling of [`Queue::dequeue_into`].
+    ///
+    /// # Returns
+    ///
+    /// The number of items written, which is `0` if the queue is currently empty.
+    /// Use [`Queue::is_terminated`] to tell an empty queue from a finished one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// let mut buf = [0; 4];
+    /// assert_eq!(queue.try_dequeue_into(&mut buf), 0);
+    ///
+    /// queue.enqueue(9);
+    /// assert_eq!(queue.try_dequeue_into(&mut buf), 1);
+    /// assert_eq!(buf[0], 9);
+    /// ```
+    pub fn try_dequeue_into(&self, buf: &mut [T]) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        self.pop_into(&mut inner, buf)
+    }
+
+    /// Removes and returns the first item, scanning front to back, that matches `pred`.
+    ///
+    /// The remaining items keep their order. Never blocks: if nothing matches,
+    /// including when the queue is empty, this returns `None` immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `pred` - Called on each item in FIFO order until it returns `true`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(item)` - the first matching item, removed from the queue.
+    /// * `None` - if no buffered item matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(8);
+    /// for job in ["build", "test", "deploy"] {
+    ///     queue.enqueue(job);
+    /// }
+    ///
+    /// // The user cancelled "test".
+    /// assert_eq!(queue.dequeue_filter(|&job| job == "test"), Some("test"));
+    /// assert_eq!(queue.dequeue(), Some("build"));
+    /// assert_eq!(queue.dequeue(), Some("deploy"));
+    /// ```
+    pub fn dequeue_filter(&self, pred: impl FnMut(&T) -> bool) -> Option<T> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = inner.buffer.iter().position(pred)?;
+        let item = self.remove(&mut inner, index);
+        self.not_full.notify_one();
+        item
+    }
+
+    /// Removes items from the front of the queue for as long as `pred` holds.
+    ///
+    /// Stops at the first item for which `pred` returns `false`; that item and
+    /// everything behind it stay in the queue. Never blocks: on an empty queue
+    /// this returns an empty `Vec` immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `pred` - Called on each front item in turn while it returns `true`.
+    ///
+    /// # Returns
+    ///
+    /// The removed prefix, in FIFO order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(8);
+    /// for timestamp in [10, 20, 30, 40] {
+    ///     queue.enqueue(timestamp);
+    /// }
+    ///
+    /// assert_eq!(queue.drain_while(|&t| t < 25), vec![10, 20]);
+    /// assert_eq!(queue.dequeue(), Some(30));
+    /// ```
+    pub fn drain_while(&self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
         let mut inner = self.inner.lock().unwrap();
-        while inner.buffer.is_empty() && !inner.shutdown {
-            inner = self.not_empty.wait(inner).unwrap();
+        let mut drained = Vec::new();
+        while inner.buffer.front().is_some_and(&mut pred) {
+            if let Some(item) = self.pop(&mut inner) {
+                drained.push(item);
+                self.not_full.notify_one();
+            }
         }
+        drained
+    }
 
-        let item = inner.buffer.pop_front();
-        if item.is_some() {
+    /// Repeatedly removes batches of items and passes each batch to `f`.
+    ///
+    /// Each batch starts with the next available item, then collects up to
+    /// `max_items` items, waiting no longer than `max_wait` after the first one
+    /// for the batch to fill. Batches are never empty. When the queue is shut
+    /// down, any partially filled batch is flushed immediately, and the method
+    /// returns once the queue has been drained.
+    ///
+    /// `f` runs without the queue's lock held, so a panic in `f` leaves the queue
+    /// consistent and usable by other threads; the panic propagates to the caller
+    /// and the items of that batch are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_items` - Maximum number of items per batch.
+    /// * `max_wait` - How long to wait after the first item for a batch to fill.
+    /// * `f` - Called with each batch, in FIFO order.
+    ///
+    /// # Blocking
+    ///
+    /// - Blocks while the queue is empty until an item is added or shutdown occurs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_items` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(8);
+    /// for i in 0..5 {
+    ///     queue.enqueue(i);
+    /// }
+    /// queue.shutdown();
+    ///
+    /// let mut batches = Vec::new();
+    /// queue.consume_batches(2, Duration::from_millis(50), |batch| batches.push(batch));
+    /// assert_eq!(batches, vec![vec![0, 1], vec![2, 3], vec![4]]);
+    /// ```
+    pub fn consume_batches(&self, max_items: usize, max_wait: Duration, mut f: impl FnMut(Vec<T>)) {
+        assert!(max_items > 0, "max_items must be greater than zero");
+        loop {
+            let (batch, _) = self.next_batch(max_items, max_wait);
+            if batch.is_empty() {
+                return;
+            }
+            f(batch);
+        }
+    }
+
+    /// Moves up to `n` items from the front of this queue to the back of `dst`.
+    ///
+    /// Both queues are locked for the whole transfer (in a fixed order, so two
+    /// concurrent transfers in opposite directions cannot deadlock), so no other
+    /// thread can observe an item in both queues or in neither. The transfer never
+    /// blocks: it moves only as many items as are available in `self` and fit in
+    /// `dst`, preserving their order.
+    ///
+    /// # Arguments
+    ///
+    /// * `dst` - Queue receiving the items.
+    /// * `n` - Maximum number of items to move.
+    ///
+    /// # Returns
+    ///
+    /// The number of items moved. Returns `0` if either queue is shut down or if
+    /// `dst` is this queue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let busy = Queue::new(4);
+    /// let idle = Queue::new(2);
+    /// for i in 0..4 {
+    ///     busy.enqueue(i);
+    /// }
+    ///
+    /// assert_eq!(busy.transfer_to(&idle, 3), 2);
+    /// assert_eq!(idle.dequeue(), Some(0));
+    /// assert_eq!(busy.dequeue(), Some(2));
+    /// ```
+    pub fn transfer_to(&self, dst: &Queue<T>, n: usize) -> usize {
+        if std::ptr::eq(self, dst) {
+            return 0;
+        }
+
+        let (mut src_inner, mut dst_inner) = self.lock_pair(dst);
+
+        if src_inner.shutdown || dst_inner.shutdown {
+            return 0;
+        }
+
+        let room = dst.capacity.saturating_sub(dst_inner.buffer.len());
+        let moved = n.min(room).min(src_inner.buffer.len());
+        for _ in 0..moved {
+            if let Some(item) = self.pop(&mut src_inner) {
+                dst.push(&mut dst_inner, item);
+            }
             self.not_full.notify_one();
+            dst.notify_consumer(&mut dst_inner);
         }
-        item
+        moved
     }
 
     /// Shuts down the queue, waking all blocked threads and preventing further enqueues.
@@ -198,10 +665,27 @@ impl<T> Queue<T> {
     pub fn shutdown(&self) {
         let mut inner = self.inner.lock().unwrap();
         inner.shutdown = true;
+        self.shutdown_hint.store(true, Ordering::Release);
         self.not_empty.notify_all();
         self.not_full.notify_all();
     }
 
+    /// Returns the number of items currently buffered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue('a');
+    /// queue.enqueue('b');
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().buffer.len()
+    }
+
     /// Checks if the queue is currently empty.
     ///
     /// # Returns
@@ -247,32 +731,656 @@ impl<T> Queue<T> {
         let inner = self.inner.lock().unwrap();
         inner.shutdown
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::Arc;
 
-    #[test]
-    fn test_create_destroy() {
-        let _queue: Arc<Queue<usize>> = Queue::new(10);
-        // Simply creating and letting it go out of scope to "destroy" it.
-        // In Rust, destruction is handled automatically via Drop.
+    /// Checks if the queue has been shut down and fully drained.
+    ///
+    /// Once this returns `true`, every `dequeue` call returns `None` immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(2);
+    /// queue.enqueue(1);
+    /// queue.shutdown();
+    /// assert!(!queue.is_terminated());
+    ///
+    /// queue.dequeue();
+    /// assert!(queue.is_terminated());
+    /// ```
+    pub fn is_terminated(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.shutdown && inner.buffer.is_empty()
     }
 
-    #[test]
-    fn test_queue_dequeue() {
-        let queue = Arc::new(Queue::new(10));
-        let data = Box::new(1usize);
-        let ptr = data.as_ref() as *const usize;
+    /// Returns the number of threads currently blocked waiting for space.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u8>::new(1);
+    /// assert_eq!(queue.blocked_producers(), 0);
+    /// ```
+    pub fn blocked_producers(&self) -> usize {
+        self.inner.lock().unwrap().blocked_producers
+    }
 
-        queue.enqueue(data);
-        if let Some(result) = queue.dequeue() {
-            let result_ptr = result.as_ref() as *const usize;
-            assert_eq!(ptr, result_ptr);
-        } else {
-            panic!("Expected to dequeue an item, but got None");
+    /// Returns the number of threads currently blocked waiting for an item.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u8>::new(1);
+    /// assert_eq!(queue.blocked_consumers(), 0);
+    /// ```
+    pub fn blocked_consumers(&self) -> usize {
+        self.inner.lock().unwrap().blocked_consumers
+    }
+
+    /// Consumes the queue, returning the items still buffered in FIFO order.
+    ///
+    /// Because this takes the queue by value, no other thread can be using it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mutex was poisoned by a thread panicking while holding it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue(1);
+    /// queue.enqueue(2);
+    ///
+    /// let queue = Arc::into_inner(queue).unwrap();
+    /// assert_eq!(queue.into_inner(), [1, 2]);
+    /// ```
+    pub fn into_inner(self) -> VecDeque<T> {
+        self.inner.into_inner().unwrap().buffer
+    }
+
+    /// Returns the buffered items if `arc` is the only reference to the queue.
+    ///
+    /// Mirrors [`Arc::try_unwrap`]: if other `Arc`s to the queue still exist,
+    /// `arc` is handed back unchanged.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(items)` - the buffered items in FIFO order.
+    /// * `Err(arc)` - if the queue is still shared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::new(4);
+    /// queue.enqueue('a');
+    ///
+    /// let clone = Arc::clone(&queue);
+    /// let queue = Queue::try_unwrap(queue).unwrap_err();
+    ///
+    /// drop(clone);
+    /// assert_eq!(Queue::try_unwrap(queue).unwrap(), ['a']);
+    /// ```
+    pub fn try_unwrap(arc: Arc<Self>) -> Result<VecDeque<T>, Arc<Self>> {
+        Arc::try_unwrap(arc).map(Self::into_inner)
+    }
+
+    /// Returns the approximate number of bytes held by the queue.
+    ///
+    /// The estimate is the size of the `Queue` struct itself plus [`Queue::item_bytes`].
+    /// It does not account for unused slots reserved in the internal buffer or for
+    /// allocator overhead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<Vec<u8>>::builder(4)
+    ///     .item_size(|item| item.len())
+    ///     .build();
+    /// let base = queue.memory_usage();
+    ///
+    /// queue.enqueue(vec![0; 1024]);
+    /// assert_eq!(queue.memory_usage(), base + 1024);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.item_bytes()
+    }
+
+    /// Returns the running total of the sizes of all buffered items.
+    ///
+    /// Sizes come from the closure passed to [`QueueBuilder::item_size`], or
+    /// `size_of::<T>()` per item when none was supplied. The total is maintained
+    /// incrementally on enqueue/dequeue and read without taking the lock, so it may
+    /// briefly lag behind concurrent operations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fifo_bounded_buffer::Queue;
+    ///
+    /// let queue = Queue::<u64>::new(4);
+    /// queue.enqueue(1);
+    /// queue.enqueue(2);
+    /// assert_eq!(queue.item_bytes(), 2 * std::mem::size_of::<u64>());
+    /// ```
+    pub fn item_bytes(&self) -> usize {
+        self.item_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Waits on `not_full` while the queue is full and not shut down, counting the
+    /// caller as a blocked producer. Returns the guard and the time spent waiting.
+    fn wait_for_space<'a>(
+        &'a self,
+        inner: MutexGuard<'a, Inner<T>>,
+    ) -> (MutexGuard<'a, Inner<T>>, Duration) {
+        self.wait_for_space_until(inner, None)
+    }
+
+    /// Like [`wait_for_space`](Self::wait_for_space), but gives up at `deadline`,
+    /// in which case the queue may still be full when this returns.
+    fn wait_for_space_until<'a>(
+        &'a self,
+        mut inner: MutexGuard<'a, Inner<T>>,
+        deadline: Option<Instant>,
+    ) -> (MutexGuard<'a, Inner<T>>, Duration) {
+        if inner.buffer.len() < self.capacity || inner.shutdown {
+            return (inner, Duration::ZERO);
+        }
+
+        let start = self.clock.now();
+        if self.spin_budget > 0 {
+            drop(inner);
+            self.spin_until(|len| len < self.capacity);
+            inner = self.inner.lock().unwrap();
+            if inner.buffer.len() < self.capacity || inner.shutdown {
+                return (inner, self.clock.now() - start);
+            }
+        }
+
+        inner.blocked_producers += 1;
+        self.waiters_changed();
+        let mut futile_wakeups = 0;
+        let mut backoff = Backoff::new();
+        while inner.buffer.len() == self.capacity && !inner.shutdown {
+            match self.wait_on(&self.not_full, inner, deadline) {
+                Ok(guard) => inner = guard,
+                Err(guard) => {
+                    inner = guard;
+                    break;
+                }
+            }
+            if inner.buffer.len() == self.capacity && !inner.shutdown {
+                futile_wakeups += 1;
+                inner = self.back_off_after_futile_wakeups(inner, futile_wakeups, &mut backoff);
+            }
+        }
+        inner.blocked_producers -= 1;
+        (inner, self.clock.now() - start)
+    }
+
+    /// Waits on `not_empty` while the queue is empty and not shut down, counting
+    /// the caller as a blocked consumer. Returns the guard and the time spent waiting.
+    fn wait_for_item<'a>(
+        &'a self,
+        inner: MutexGuard<'a, Inner<T>>,
+    ) -> (MutexGuard<'a, Inner<T>>, Duration) {
+        self.wait_for_item_until(inner, None)
+    }
+
+    /// Like [`wait_for_item`](Self::wait_for_item), but gives up at `deadline`,
+    /// in which case the queue may still be empty when this returns.
+    fn wait_for_item_until<'a>(
+        &'a self,
+        mut inner: MutexGuard<'a, Inner<T>>,
+        deadline: Option<Instant>,
+    ) -> (MutexGuard<'a, Inner<T>>, Duration) {
+        if self.item_ready(&inner, None) {
+            return (inner, Duration::ZERO);
+        }
+
+        let start = self.clock.now();
+        if self.spin_budget > 0 {
+            drop(inner);
+            self.spin_until(|len| len > 0);
+            inner = self.inner.lock().unwrap();
+            if self.item_ready(&inner, None) {
+                return (inner, self.clock.now() - start);
+            }
+        }
+
+        let ticket = self.fair_consumers.then(|| {
+            let ticket = inner.next_ticket;
+            inner.next_ticket += 1;
+            inner.consumer_tickets.push_back(ticket);
+            ticket
+        });
+
+        inner.blocked_consumers += 1;
+        self.waiters_changed();
+        // A queued item can be granted to this ticket straight away
+        self.grant_items(&mut inner);
+        let mut futile_wakeups = 0;
+        let mut backoff = Backoff::new();
+        while !self.item_ready(&inner, ticket) {
+            match self.wait_on(&self.not_empty, inner, deadline) {
+                Ok(guard) => inner = guard,
+                Err(guard) => {
+                    inner = guard;
+                    break;
+                }
+            }
+            if let Some(ticket) = ticket {
+                self.requeue_if_taken(&mut inner, ticket);
+            }
+            if !self.item_ready(&inner, ticket) {
+                futile_wakeups += 1;
+                inner = self.back_off_after_futile_wakeups(inner, futile_wakeups, &mut backoff);
+            }
+        }
+
+        match ticket {
+            // Still waiting: timed out or shut down before being granted an item
+            Some(ticket) if inner.consumer_tickets.contains(&ticket) => {
+                inner.consumer_tickets.retain(|&t| t != ticket);
+                inner.blocked_consumers -= 1;
+                self.waiters_changed();
+            }
+            // Granted: it stopped counting as blocked when the item was granted
+            Some(_) => {
+                inner.granted_consumers -= 1;
+                // Waiters left after shutdown wait for the granted items to go
+                if inner.shutdown {
+                    self.not_empty.notify_all();
+                }
+            }
+            None => {
+                inner.blocked_consumers -= 1;
+                self.waiters_changed();
+            }
+        }
+        (inner, self.clock.now() - start)
+    }
+
+    /// Checks if a consumer holding `ticket` may stop waiting: the queue is shut
+    /// down, or it has an item it is free to take.
+    ///
+    /// With fair consumers, a ticket holder may take an item once one was
+    /// granted to it, and a consumer without a ticket only if no waiter is
+    /// owed the queued items. After shutdown, those not granted an item wait
+    /// for the granted ones to take theirs, so nobody takes an item owed to
+    /// someone else.
+    fn item_ready(&self, inner: &Inner<T>, ticket: Option<u64>) -> bool {
+        if !self.fair_consumers {
+            return inner.shutdown || !inner.buffer.is_empty();
+        }
+        let drained = inner.shutdown && inner.buffer.is_empty();
+        match ticket {
+            Some(ticket) if inner.consumer_tickets.contains(&ticket) => drained,
+            Some(_) => inner.shutdown || !inner.buffer.is_empty(),
+            None => {
+                drained
+                    || (inner.consumer_tickets.is_empty()
+                        && inner.buffer.len() > inner.granted_consumers)
+            }
+        }
+    }
+
+    /// With fair consumers, grants each queued item nobody is owed yet to the
+    /// longest-waiting ticket, in order.
+    ///
+    /// A granted consumer stops counting as blocked at once, not when it next
+    /// takes the lock, so [`blocked_consumers`](Self::blocked_consumers) only
+    /// counts consumers that are still owed nothing.
+    fn grant_items(&self, inner: &mut Inner<T>) {
+        if !self.fair_consumers {
+            return;
+        }
+        let mut granted = false;
+        while inner.buffer.len() > inner.granted_consumers {
+            if inner.consumer_tickets.pop_front().is_none() {
+                break;
+            }
+            inner.granted_consumers += 1;
+            inner.blocked_consumers -= 1;
+            granted = true;
+        }
+        if granted {
+            // The holders cannot be woken one by one, so all are woken and
+            // those not granted an item go back to sleep
+            self.not_empty.notify_all();
+            self.waiters_changed();
+        }
+    }
+
+    /// Puts `ticket` back at the front of the line if it was granted an item
+    /// that another caller, such as `try_dequeue_into` or `dequeue_filter`, took first.
+    fn requeue_if_taken(&self, inner: &mut Inner<T>, ticket: u64) {
+        if inner.shutdown
+            || inner.consumer_tickets.contains(&ticket)
+            || inner.buffer.len() >= inner.granted_consumers
+        {
+            return;
+        }
+        inner.granted_consumers -= 1;
+        inner.blocked_consumers += 1;
+        inner.consumer_tickets.push_front(ticket);
+        self.waiters_changed();
+    }
+
+    /// Wakes a consumer for a newly added item.
+    ///
+    /// With fair consumers the item is granted to the longest-waiting ticket
+    /// holder instead; see [`grant_items`](Self::grant_items).
+    fn notify_consumer(&self, inner: &mut Inner<T>) {
+        if self.fair_consumers {
+            self.grant_items(inner);
+            if inner.consumer_tickets.is_empty() {
+                self.not_empty.notify_one();
+            }
+        } else {
+            self.not_empty.notify_one();
+        }
+    }
+
+    /// Steps away from the lock once a waiter has repeatedly woken up only to
+    /// find that another thread got there first.
+    ///
+    /// Retaking the mutex immediately after every lost race makes all the
+    /// losers contend on its cache line; backing off spreads them out. The
+    /// caller rechecks its condition, including shutdown, after every step.
+    fn back_off_after_futile_wakeups<'a>(
+        &'a self,
+        inner: MutexGuard<'a, Inner<T>>,
+        futile_wakeups: u32,
+        backoff: &mut Backoff,
+    ) -> MutexGuard<'a, Inner<T>> {
+        if futile_wakeups < FUTILE_WAKEUPS_BEFORE_BACKOFF {
+            return inner;
+        }
+        drop(inner);
+        backoff.snooze();
+        self.inner.lock().unwrap()
+    }
+
+    /// Spins for up to `spin_budget` iterations, without holding the lock, until
+    /// `ready` accepts the published buffer length or the queue is shut down.
+    ///
+    /// Returns `true` if the condition was observed; the caller must still
+    /// recheck it under the lock, since another thread may act on it first.
+    fn spin_until(&self, ready: impl Fn(usize) -> bool) -> bool {
+        for _ in 0..self.spin_budget {
+            if self.shutdown_hint.load(Ordering::Acquire)
+                || ready(self.len_hint.load(Ordering::Acquire))
+            {
+                return true;
+            }
+            #[cfg(test)]
+            self.spin_iterations.fetch_add(1, Ordering::Relaxed);
+            spin_loop();
+        }
+        false
+    }
+
+    /// Waits once on `condvar`, bounded by `deadline` if there is one.
+    ///
+    /// Returns `Err` with the guard if the deadline has already passed.
+    fn wait_on<'a>(
+        &self,
+        condvar: &Condvar,
+        inner: MutexGuard<'a, Inner<T>>,
+        deadline: Option<Instant>,
+    ) -> Result<MutexGuard<'a, Inner<T>>, MutexGuard<'a, Inner<T>>> {
+        let Some(deadline) = deadline else {
+            return Ok(condvar.wait(inner).unwrap());
+        };
+        match deadline.checked_duration_since(self.clock.now()) {
+            Some(remaining) if !remaining.is_zero() => {
+                let timeout = self.clock.wait_timeout(remaining);
+                Ok(condvar.wait_timeout(inner, timeout).unwrap().0)
+            }
+            _ => Err(inner),
+        }
+    }
+
+    /// Wakes threads parked in the `test-hooks` wait functions; a no-op otherwise.
+    fn waiters_changed(&self) {
+        #[cfg(any(test, feature = "test-hooks"))]
+        self.hooks.notify_all();
+    }
+
+    /// Blocking enqueue shared by `enqueue` and the producer handles.
+    ///
+    /// Returns the item back if the queue was shut down, along with the time
+    /// spent waiting for space.
+    fn enqueue_timed(&self, item: T) -> (Result<(), T>, Duration) {
+        let inner = self.inner.lock().unwrap();
+        let (mut inner, blocked) = self.wait_for_space(inner);
+        if inner.shutdown {
+            return (Err(item), blocked);
+        }
+
+        self.push(&mut inner, item);
+        self.notify_consumer(&mut inner);
+        (Ok(()), blocked)
+    }
+
+    /// Blocking dequeue shared by `dequeue` and the consumer handles.
+    ///
+    /// Returns the item (or `None` once shut down and empty), along with the time
+    /// spent waiting for an item.
+    fn dequeue_timed(&self) -> (Option<T>, Duration) {
+        let inner = self.inner.lock().unwrap();
+        let (mut inner, blocked) = self.wait_for_item(inner);
+        let item = self.pop(&mut inner);
+        if item.is_some() {
+            self.not_full.notify_one();
+        }
+        (item, blocked)
+    }
+
+    /// Blocking batch dequeue shared by `dequeue_into` and the consumer handles.
+    ///
+    /// Returns the number of items written, along with the time spent waiting
+    /// for the first one.
+    fn dequeue_into_timed(&self, buf: &mut [T]) -> (usize, Duration) {
+        if buf.is_empty() {
+            return (0, Duration::ZERO);
+        }
+
+        let inner = self.inner.lock().unwrap();
+        let (mut inner, blocked) = self.wait_for_item(inner);
+        (self.pop_into(&mut inner, buf), blocked)
+    }
+
+    /// Locks this queue and `other` in address order, so that two threads locking
+    /// the same pair in opposite roles cannot deadlock.
+    ///
+    /// Must not be called with `other` being `self`.
+    fn lock_pair<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> (MutexGuard<'a, Inner<T>>, MutexGuard<'a, Inner<T>>) {
+        if (self as *const Self) < (other as *const Self) {
+            let ours = self.inner.lock().unwrap();
+            (ours, other.inner.lock().unwrap())
+        } else {
+            let theirs = other.inner.lock().unwrap();
+            (self.inner.lock().unwrap(), theirs)
+        }
+    }
+
+    /// Pops items into the front of `buf` until either runs out, waking one
+    /// producer per freed slot.
+    fn pop_into(&self, inner: &mut Inner<T>, buf: &mut [T]) -> usize {
+        let mut written = 0;
+        for slot in buf.iter_mut() {
+            match self.pop(inner) {
+                Some(item) => *slot = item,
+                None => break,
+            }
+            written += 1;
+            self.not_full.notify_one();
+        }
+        written
+    }
+
+    /// Collects the next batch for `consume_batches`; empty once terminated.
+    ///
+    /// Also returns the time spent waiting for the batch's first item; waiting
+    /// for the batch to fill is not counted.
+    fn next_batch(&self, max_items: usize, max_wait: Duration) -> (Vec<T>, Duration) {
+        let inner = self.inner.lock().unwrap();
+        let (mut inner, blocked) = self.wait_for_item(inner);
+
+        let deadline = self.clock.now().checked_add(max_wait);
+        let mut batch = Vec::new();
+        loop {
+            while batch.len() < max_items {
+                match self.pop(&mut inner) {
+                    Some(item) => {
+                        batch.push(item);
+                        self.not_full.notify_one();
+                    }
+                    None => break,
+                }
+            }
+
+            if batch.len() == max_items || inner.shutdown {
+                return (batch, blocked);
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(self.clock.now()) {
+                    Some(remaining) if !remaining.is_zero() => remaining,
+                    _ => return (batch, blocked),
+                },
+                None => Duration::MAX,
+            };
+            let timeout = self.clock.wait_timeout(timeout);
+            inner.blocked_consumers += 1;
+            self.waiters_changed();
+            inner = self.not_empty.wait_timeout(inner, timeout).unwrap().0;
+            inner.blocked_consumers -= 1;
+        }
+    }
+
+    /// Returns the estimated size of a single item.
+    fn size_of_item(&self, item: &T) -> usize {
+        match &self.item_size {
+            Some(f) => f(item),
+            None => std::mem::size_of::<T>(),
+        }
+    }
+
+    /// Appends an item to the buffer, updating the memory accounting.
+    fn push(&self, inner: &mut Inner<T>, item: T) {
+        self.item_bytes
+            .fetch_add(self.size_of_item(&item), Ordering::Relaxed);
+        inner.buffer.push_back(item);
+        self.publish_len(inner);
+    }
+
+    /// Removes the front item from the buffer, updating the memory accounting.
+    fn pop(&self, inner: &mut Inner<T>) -> Option<T> {
+        self.remove(inner, 0)
+    }
+
+    /// Removes the item at `index` from the buffer, updating the memory accounting.
+    fn remove(&self, inner: &mut Inner<T>, index: usize) -> Option<T> {
+        let item = inner.buffer.remove(index)?;
+        self.item_bytes
+            .fetch_sub(self.size_of_item(&item), Ordering::Relaxed);
+        self.publish_len(inner);
+        Some(item)
+    }
+
+    /// Makes the buffer length visible to spinning waiters; skipped when spinning
+    /// is disabled so the default configuration pays nothing for it.
+    fn publish_len(&self, inner: &Inner<T>) {
+        if self.spin_budget > 0 {
+            self.len_hint.store(inner.buffer.len(), Ordering::Release);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Queue")
+            .field("inner", &self.inner)
+            .field("capacity", &self.capacity)
+            .field("item_bytes", &self.item_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Compares the buffered items front to back. The shutdown state is ignored.
+impl<T: PartialEq> PartialEq<[T]> for Queue<T> {
+    fn eq(&self, other: &[T]) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.buffer.iter().eq(other)
+    }
+}
+
+/// Compares the buffered items front to back. The shutdown state is ignored.
+impl<T: PartialEq> PartialEq<Vec<T>> for Queue<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        *self == *other.as_slice()
+    }
+}
+
+/// Compares the buffered items of two queues front to back.
+///
+/// Both queues are locked in address order, so concurrent comparisons in either
+/// direction cannot deadlock. Capacity and shutdown state are ignored.
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        let (ours, theirs) = self.lock_pair(other);
+        ours.buffer == theirs.buffer
+    }
+}
+
+impl<T: Eq> Eq for Queue<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_create_destroy() {
+        let _queue: Arc<Queue<usize>> = Queue::new(10);
+        // Simply creating and letting it go out of scope to "destroy" it.
+        // In Rust, destruction is handled automatically via Drop.
+    }
+
+    #[test]
+    fn test_queue_dequeue() {
+        let queue = Arc::new(Queue::new(10));
+        let data = Box::new(1usize);
+        let ptr = data.as_ref() as *const usize;
+
+        queue.enqueue(data);
+        if let Some(result) = queue.dequeue() {
+            let result_ptr = result.as_ref() as *const usize;
+            assert_eq!(ptr, result_ptr);
+        } else {
+            panic!("Expected to dequeue an item, but got None");
         }
     }
 
@@ -343,7 +1451,7 @@ mod tests {
             q_clone.enqueue(2);
         });
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(queue.wait_for_blocked_producers(1, std::time::Duration::from_secs(5)));
         assert_eq!(queue.dequeue(), Some(1));
         handle.join().unwrap();
 
@@ -360,7 +1468,7 @@ mod tests {
             q_clone.dequeue()
         });
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(queue.wait_for_blocked_consumers(1, std::time::Duration::from_secs(5)));
         queue.enqueue(42);
         let result = handle.join().unwrap();
 
@@ -377,7 +1485,7 @@ mod tests {
             q_clone.dequeue()
         });
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(queue.wait_for_blocked_consumers(1, std::time::Duration::from_secs(5)));
         queue.shutdown();
 
         let result = handle.join().unwrap();
@@ -408,6 +1516,514 @@ mod tests {
         assert!(queue.is_shutdown());
     }
 
+    #[test]
+    fn test_transfer_moves_only_what_fits() {
+        let src = Queue::new(5);
+        let dst = Queue::new(3);
+        for i in 0..5 {
+            src.enqueue(i);
+        }
+        dst.enqueue(100);
+
+        assert_eq!(src.transfer_to(&dst, 10), 2);
+        assert_eq!(src.transfer_to(&dst, 10), 0);
+
+        assert_eq!(dst.dequeue(), Some(100));
+        assert_eq!(dst.dequeue(), Some(0));
+        assert_eq!(dst.dequeue(), Some(1));
+        assert_eq!(src.dequeue(), Some(2));
+        assert_eq!(src.transfer_to(&dst, 1), 1);
+        assert_eq!(dst.dequeue(), Some(3));
+    }
+
+    #[test]
+    fn test_transfer_aborts_on_shutdown_or_self() {
+        let a = Queue::new(4);
+        let b = Queue::new(4);
+        a.enqueue(1);
+        a.enqueue(2);
+
+        assert_eq!(a.transfer_to(&a, 2), 0);
+
+        b.shutdown();
+        assert_eq!(a.transfer_to(&b, 2), 0);
+        assert_eq!(b.transfer_to(&a, 2), 0);
+
+        a.shutdown();
+        assert_eq!(a.dequeue(), Some(1));
+        assert_eq!(a.dequeue(), Some(2));
+    }
+
+    #[test]
+    fn test_transfer_wakes_blocked_producer() {
+        let src = Queue::new(1);
+        let dst = Queue::new(1);
+        src.enqueue(1);
+
+        let q = Arc::clone(&src);
+        let handle = std::thread::spawn(move || q.enqueue(2));
+
+        assert!(src.wait_for_blocked_producers(1, std::time::Duration::from_secs(5)));
+        assert_eq!(src.transfer_to(&dst, 1), 1);
+        handle.join().unwrap();
+
+        assert_eq!(src.dequeue(), Some(2));
+        assert_eq!(dst.dequeue(), Some(1));
+    }
+
+    #[test]
+    fn test_concurrent_bidirectional_transfers_conserve_items() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::thread;
+
+        let a = Queue::new(8);
+        let b = Queue::new(8);
+        let consumed = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let per_producer = 2_000;
+
+        let producers: Vec<_> = [&a, &b]
+            .into_iter()
+            .map(|q| {
+                let q = Arc::clone(q);
+                thread::spawn(move || {
+                    for i in 0..per_producer {
+                        q.enqueue(i);
+                    }
+                })
+            })
+            .collect();
+
+        let movers: Vec<_> = [(&a, &b), (&b, &a)]
+            .into_iter()
+            .map(|(src, dst)| {
+                let (src, dst) = (Arc::clone(src), Arc::clone(dst));
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        src.transfer_to(&dst, 3);
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = [&a, &b]
+            .into_iter()
+            .map(|q| {
+                let q = Arc::clone(q);
+                let consumed = Arc::clone(&consumed);
+                thread::spawn(move || {
+                    while q.dequeue().is_some() {
+                        consumed.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        stop.store(true, Ordering::Relaxed);
+        for m in movers {
+            m.join().unwrap();
+        }
+        a.shutdown();
+        b.shutdown();
+        for c in consumers {
+            c.join().unwrap();
+        }
+
+        assert_eq!(consumed.load(Ordering::Relaxed), 2 * per_producer);
+    }
+
+    #[test]
+    fn test_consume_batches_respects_max_items() {
+        let queue = Queue::new(32);
+        for i in 0..25 {
+            queue.enqueue(i);
+        }
+        queue.shutdown();
+
+        let mut sizes = Vec::new();
+        let mut items = Vec::new();
+        queue.consume_batches(10, std::time::Duration::from_secs(10), |batch| {
+            sizes.push(batch.len());
+            items.extend(batch);
+        });
+
+        assert_eq!(sizes, vec![10, 10, 5]);
+        assert_eq!(items, (0..25).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_consume_batches_flushes_on_time_bound() {
+        use crate::clock::MockClock;
+        use std::time::Duration;
+
+        let clock = Arc::new(MockClock::new());
+        let queue = Queue::with_clock(100, clock.clone());
+        queue.enqueue(0);
+        queue.enqueue(1);
+        queue.enqueue(2);
+
+        // The batch never fills, so each one is cut when the window expires. The
+        // callback plays the slow producer, trickling in the next items.
+        let mut batches = Vec::new();
+        queue.consume_batches(100, Duration::from_millis(20), |batch| {
+            match batches.len() {
+                0 => {
+                    queue.enqueue(3);
+                    queue.enqueue(4);
+                }
+                _ => queue.shutdown(),
+            }
+            batches.push(batch);
+        });
+
+        assert_eq!(batches, vec![vec![0, 1, 2], vec![3, 4]]);
+        assert_eq!(clock.elapsed(), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_consume_batches_window_starts_at_first_item() {
+        use crate::clock::MockClock;
+        use std::time::Duration;
+
+        let clock = Arc::new(MockClock::new());
+        let queue = Queue::with_clock(8, clock.clone());
+        queue.enqueue(0);
+
+        let mut batches = Vec::new();
+        queue.consume_batches(2, Duration::from_millis(50), |batch| {
+            batches.push(batch);
+            if batches.len() == 1 {
+                // Time spent outside the queue does not shorten the next window.
+                clock.advance(Duration::from_secs(1));
+                queue.enqueue(1);
+                queue.enqueue(2);
+                queue.enqueue(3);
+            } else if batches.len() == 2 {
+                queue.shutdown();
+            }
+        });
+
+        assert_eq!(batches, vec![vec![0], vec![1, 2], vec![3]]);
+        assert_eq!(clock.elapsed(), Duration::from_millis(1050));
+    }
+
+    #[test]
+    fn test_consume_batches_final_flush_on_shutdown() {
+        use std::time::{Duration, Instant};
+
+        let queue = Queue::new(8);
+        let q = Arc::clone(&queue);
+        let consumer = std::thread::spawn(move || {
+            let mut batches = Vec::new();
+            q.consume_batches(100, Duration::from_secs(60), |batch| {
+                batches.push(batch);
+            });
+            batches
+        });
+
+        queue.enqueue(1);
+        queue.enqueue(2);
+        queue.enqueue(3);
+        assert!(queue.wait_for_blocked_consumers(1, Duration::from_secs(5)));
+
+        let start = Instant::now();
+        queue.shutdown();
+        let batches = consumer.join().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(batches.concat(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_consume_batches_panic_leaves_queue_usable() {
+        let queue = Queue::new(8);
+        for i in 0..4 {
+            queue.enqueue(i);
+        }
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            queue.consume_batches(2, std::time::Duration::ZERO, |_| panic!("boom"));
+        }));
+        assert!(result.is_err());
+
+        queue.enqueue(4);
+        assert_eq!(queue.dequeue(), Some(2));
+        assert_eq!(queue.dequeue(), Some(3));
+        assert_eq!(queue.dequeue(), Some(4));
+    }
+
+    #[test]
+    fn test_enqueue_from_iter_with_concurrent_consumers() {
+        let queue = Queue::new(8);
+        let consumers: Vec<_> = (0..3)
+            .map(|_| {
+                let q = Arc::clone(&queue);
+                std::thread::spawn(move || {
+                    let mut sum = 0u64;
+                    while let Some(item) = q.dequeue() {
+                        sum += item;
+                    }
+                    sum
+                })
+            })
+            .collect();
+
+        assert_eq!(queue.enqueue_from_iter(0..100_000u64), Ok(100_000));
+        queue.shutdown();
+
+        let sum: u64 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+        assert_eq!(sum, (0..100_000u64).sum::<u64>());
+    }
+
+    #[test]
+    fn test_enqueue_from_iter_pulls_only_when_room() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let queue = Queue::new(4);
+        let pulled = Arc::new(AtomicUsize::new(0));
+
+        let q = Arc::clone(&queue);
+        let p = Arc::clone(&pulled);
+        let producer = std::thread::spawn(move || {
+            q.enqueue_from_iter((0..).inspect(|_| {
+                p.fetch_add(1, Ordering::SeqCst);
+            }))
+        });
+
+        assert!(queue.wait_for_blocked_producers(1, std::time::Duration::from_secs(5)));
+        assert_eq!(pulled.load(Ordering::SeqCst), 4);
+
+        for expected in 0..50 {
+            assert_eq!(queue.dequeue(), Some(expected));
+        }
+        queue.shutdown();
+
+        let (count, undelivered) = producer.join().unwrap().unwrap_err();
+        assert!(count >= 50);
+        assert_eq!(undelivered, count);
+        assert_eq!(pulled.load(Ordering::SeqCst), count + 1);
+    }
+
+    #[test]
+    fn test_exhausted_iterator_does_not_swallow_wakeup() {
+        let queue = Queue::new(1);
+        queue.enqueue(0);
+
+        let q = Arc::clone(&queue);
+        let empty = std::thread::spawn(move || q.enqueue_from_iter(std::iter::empty()));
+        let q = Arc::clone(&queue);
+        let single = std::thread::spawn(move || q.enqueue(1));
+        assert!(queue.wait_for_blocked_producers(2, std::time::Duration::from_secs(5)));
+
+        // Whichever producer is woken, the freed slot must reach `single`.
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(empty.join().unwrap(), Ok(0));
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !single.is_finished() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(single.is_finished(), "blocked producer was never woken");
+        assert_eq!(queue.dequeue(), Some(1));
+    }
+
+    #[test]
+    fn test_try_unwrap_returns_leftovers_in_order() {
+        let queue = Queue::new(8);
+        for i in 1..=5 {
+            queue.enqueue(i);
+        }
+        assert_eq!(queue.dequeue(), Some(1));
+        queue.shutdown();
+
+        let clone = Arc::clone(&queue);
+        let queue = Queue::try_unwrap(queue).expect_err("queue is still shared");
+        drop(clone);
+
+        let leftovers = Queue::try_unwrap(queue).expect("queue is uniquely owned");
+        assert_eq!(leftovers, [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_into_inner_empty_queue() {
+        let queue = Arc::into_inner(Queue::<String>::new(2)).unwrap();
+        assert!(queue.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_eq_compares_contents() {
+        let queue = Queue::new(4);
+        assert_eq!(*queue, Vec::<u32>::new());
+
+        queue.enqueue(1);
+        queue.enqueue(2);
+        queue.enqueue(3);
+        queue.dequeue();
+        assert_eq!(*queue, vec![2, 3]);
+        assert_eq!(*queue, [2, 3][..]);
+        assert_ne!(*queue, vec![3, 2]);
+        assert_ne!(*queue, vec![2]);
+
+        // Shutdown state does not participate in equality.
+        queue.shutdown();
+        assert_eq!(*queue, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_eq_between_queues() {
+        let a = Queue::new(4);
+        let b = Queue::new(8);
+        assert_eq!(a, b);
+
+        a.enqueue('x');
+        a.enqueue('y');
+        b.enqueue('y');
+        b.enqueue('x');
+        assert_ne!(a, b);
+        assert_eq!(a, a);
+
+        // Capacity and shutdown state do not participate in equality.
+        b.dequeue();
+        b.enqueue('y');
+        b.shutdown();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_concurrent_queue_comparison_does_not_deadlock() {
+        let a = Queue::new(4);
+        let b = Queue::new(4);
+
+        let comparers: Vec<_> = [(&a, &b), (&b, &a)]
+            .into_iter()
+            .map(|(x, y)| {
+                let (x, y) = (Arc::clone(x), Arc::clone(y));
+                std::thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        let _ = *x == *y;
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..1_000 {
+            a.enqueue(i);
+            b.enqueue(i);
+            a.dequeue();
+            b.dequeue();
+        }
+        for c in comparers {
+            c.join().unwrap();
+        }
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_dequeue_into_buffer_sizes() {
+        let queue = Queue::new(8);
+        for i in 1..=5 {
+            queue.enqueue(i);
+        }
+
+        // Smaller than the backlog.
+        let mut small = [0; 2];
+        assert_eq!(queue.dequeue_into(&mut small), 2);
+        assert_eq!(small, [1, 2]);
+        assert_eq!(queue.dequeue_into(&mut small), 2);
+        assert_eq!(small, [3, 4]);
+
+        // Equal to the backlog.
+        queue.enqueue(6);
+        let mut equal = [0; 2];
+        assert_eq!(queue.dequeue_into(&mut equal), 2);
+        assert_eq!(equal, [5, 6]);
+
+        // Larger than the backlog: the tail of the buffer is left untouched.
+        queue.enqueue(7);
+        let mut large = [-1; 4];
+        assert_eq!(queue.dequeue_into(&mut large), 1);
+        assert_eq!(large, [7, -1, -1, -1]);
+
+        queue.shutdown();
+        assert_eq!(queue.dequeue_into(&mut large), 0);
+        assert_eq!(queue.dequeue_into(&mut []), 0);
+    }
+
+    #[test]
+    fn test_dequeue_into_blocks_until_item() {
+        let queue = Queue::new(2);
+        let q = Arc::clone(&queue);
+        let handle = std::thread::spawn(move || {
+            let mut buf = vec![String::new(); 3];
+            let n = q.dequeue_into(&mut buf);
+            (n, buf)
+        });
+
+        assert!(queue.wait_for_blocked_consumers(1, std::time::Duration::from_secs(5)));
+        queue.enqueue(String::from("late"));
+        let (n, buf) = handle.join().unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(buf[0], "late");
+    }
+
+    #[test]
+    fn test_try_dequeue_into_does_not_block() {
+        let queue = Queue::new(4);
+        let mut buf = [0u8; 3];
+        assert_eq!(queue.try_dequeue_into(&mut buf), 0);
+
+        for i in 0..4 {
+            queue.enqueue(i);
+        }
+        assert_eq!(queue.try_dequeue_into(&mut buf), 3);
+        assert_eq!(buf, [0, 1, 2]);
+        assert_eq!(queue.try_dequeue_into(&mut buf), 1);
+        assert_eq!(buf, [3, 1, 2]);
+        assert_eq!(queue.try_dequeue_into(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_memory_usage_tracks_item_sizes() {
+        let queue = Queue::<Vec<u8>>::builder(8)
+            .item_size(|item| item.len())
+            .build();
+        let base = std::mem::size_of::<Queue<Vec<u8>>>();
+        assert_eq!(queue.memory_usage(), base);
+
+        let sizes = [10usize, 0, 4096, 7, 300];
+        for &size in &sizes {
+            queue.enqueue(vec![0u8; size]);
+        }
+        assert_eq!(queue.item_bytes(), sizes.iter().sum::<usize>());
+        assert_eq!(queue.memory_usage(), base + sizes.iter().sum::<usize>());
+
+        queue.dequeue();
+        queue.dequeue();
+        assert_eq!(queue.item_bytes(), 4096 + 7 + 300);
+
+        for _ in 0..3 {
+            queue.dequeue();
+        }
+        assert!(queue.is_empty());
+        assert_eq!(queue.memory_usage(), base);
+    }
+
+    #[test]
+    fn test_memory_usage_without_sizing_closure() {
+        let queue = Queue::<Vec<u8>>::new(4);
+        queue.enqueue(vec![0u8; 1000]);
+        queue.enqueue(vec![0u8; 10]);
+
+        // Heap contents are invisible without a sizing closure.
+        assert_eq!(queue.item_bytes(), 2 * std::mem::size_of::<Vec<u8>>());
+
+        queue.dequeue();
+        assert_eq!(queue.item_bytes(), std::mem::size_of::<Vec<u8>>());
+    }
+
     #[test]
     fn test_is_empty_considers_state_correctly() {
         let queue = Queue::new(3);
@@ -419,4 +2035,304 @@ mod tests {
         let _ = queue.dequeue();
         assert!(queue.is_empty());
     }
+
+    #[test]
+    fn test_len_tracks_buffered_items() {
+        let queue = Queue::new(3);
+        assert_eq!(queue.len(), 0);
+
+        queue.enqueue(1);
+        queue.enqueue(2);
+        assert_eq!(queue.len(), 2);
+
+        queue.shutdown();
+        queue.enqueue(3);
+        assert_eq!(queue.len(), 2);
+
+        let _ = queue.dequeue();
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_zero_spin_budget_never_spins() {
+        let queue = Queue::new(1);
+        let q = Arc::clone(&queue);
+        let consumer = std::thread::spawn(move || q.dequeue());
+        assert!(queue.wait_for_blocked_consumers(1, std::time::Duration::from_secs(5)));
+
+        queue.enqueue(1);
+        queue.enqueue(2);
+        assert_eq!(consumer.join().unwrap(), Some(1));
+        assert_eq!(queue.spin_iterations.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_spinning_consumer_picks_up_item() {
+        let queue = Queue::builder(1).spin_then_park(u32::MAX).build();
+        let q = Arc::clone(&queue);
+        let consumer = std::thread::spawn(move || q.dequeue());
+
+        while queue.spin_iterations.load(Ordering::Relaxed) == 0 {
+            std::thread::yield_now();
+        }
+        queue.enqueue(7);
+        assert_eq!(consumer.join().unwrap(), Some(7));
+        assert_eq!(queue.blocked_consumers(), 0);
+    }
+
+    #[test]
+    fn test_spinning_waiters_observe_shutdown_promptly() {
+        let queue = Queue::builder(1).spin_then_park(u32::MAX).build();
+        queue.enqueue(0);
+
+        let q = Arc::clone(&queue);
+        let producer = std::thread::spawn(move || q.enqueue(1));
+        let empty = Queue::<u32>::builder(1).spin_then_park(u32::MAX).build();
+        let q = Arc::clone(&empty);
+        let consumer = std::thread::spawn(move || q.dequeue());
+
+        while queue.spin_iterations.load(Ordering::Relaxed) == 0
+            || empty.spin_iterations.load(Ordering::Relaxed) == 0
+        {
+            std::thread::yield_now();
+        }
+        let start = std::time::Instant::now();
+        queue.shutdown();
+        empty.shutdown();
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(*queue, [0][..]);
+    }
+
+    #[test]
+    fn test_enqueue_with_backoff_falls_back_to_blocking() {
+        let queue = Queue::new(1);
+        queue.enqueue(0);
+
+        let q = Arc::clone(&queue);
+        let producer = std::thread::spawn(move || q.enqueue_with_backoff(1));
+        assert!(queue.wait_for_blocked_producers(1, std::time::Duration::from_secs(5)));
+
+        assert_eq!(queue.dequeue(), Some(0));
+        assert_eq!(producer.join().unwrap(), Ok(()));
+        assert_eq!(queue.dequeue(), Some(1));
+    }
+
+    #[test]
+    fn test_enqueue_with_backoff_returns_item_on_shutdown() {
+        let queue = Queue::new(1);
+        queue.enqueue(0);
+
+        let q = Arc::clone(&queue);
+        let producer = std::thread::spawn(move || q.enqueue_with_backoff(1));
+        assert!(queue.wait_for_blocked_producers(1, std::time::Duration::from_secs(5)));
+
+        queue.shutdown();
+        assert_eq!(producer.join().unwrap(), Err(1));
+        assert_eq!(*queue, [0][..]);
+    }
+
+    #[test]
+    fn test_contended_small_queue_delivers_everything() {
+        let queue = Queue::new(2);
+        let producers: Vec<_> = (0..16)
+            .map(|p| {
+                let q = Arc::clone(&queue);
+                std::thread::spawn(move || {
+                    for i in 0..200 {
+                        if p % 2 == 0 {
+                            q.enqueue(i);
+                        } else {
+                            q.enqueue_with_backoff(i).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = (0..16)
+            .map(|_| {
+                let q = Arc::clone(&queue);
+                std::thread::spawn(move || std::iter::from_fn(|| q.dequeue()).count())
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        queue.shutdown();
+        let consumed: usize = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+        assert_eq!(consumed, 16 * 200);
+    }
+
+    /// Runs 8 labeled consumers against a single producer that enqueues only
+    /// once every consumer is parked again with the queue empty, returning
+    /// how many items each consumer took.
+    fn consumer_deliveries(fair: bool) -> Vec<u64> {
+        let queue = Queue::builder(8).fair_consumers(fair).build();
+        let consumers: Vec<_> = (0..8)
+            .map(|i| {
+                let handle = queue.consumer(format!("consumer-{i}"));
+                std::thread::spawn(move || while handle.dequeue().is_some() {})
+            })
+            .collect();
+
+        for item in 0..400 {
+            assert!(queue.wait_for_blocked_consumers(8, std::time::Duration::from_secs(5)));
+            assert!(queue.is_empty());
+            queue.enqueue(item);
+        }
+        queue.shutdown();
+        for c in consumers {
+            c.join().unwrap();
+        }
+
+        let counts: Vec<u64> = queue.consumer_stats().iter().map(|s| s.consumed).collect();
+        assert_eq!(counts.iter().sum::<u64>(), 400);
+        counts
+    }
+
+    #[test]
+    fn test_fair_consumers_share_items_evenly() {
+        // Each item goes to the consumer that has waited longest, so they take
+        // turns in a fixed order
+        assert_eq!(consumer_deliveries(true), [50; 8]);
+    }
+
+    #[test]
+    fn test_fair_consumers_are_served_in_arrival_order() {
+        let queue = Queue::builder(4).fair_consumers(true).build();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let consumers: Vec<_> = (0..3)
+            .map(|i| {
+                let q = Arc::clone(&queue);
+                let tx = tx.clone();
+                let consumer = std::thread::spawn(move || {
+                    while let Some(item) = q.dequeue() {
+                        tx.send((i, item)).unwrap();
+                    }
+                });
+                assert!(queue.wait_for_blocked_consumers(i + 1, std::time::Duration::from_secs(5)));
+                consumer
+            })
+            .collect();
+
+        // Two items go to the two consumers that arrived first
+        queue.enqueue(10);
+        queue.enqueue(11);
+        let mut served: Vec<_> = rx.iter().take(2).map(|(i, _)| i).collect();
+        served.sort();
+        assert_eq!(served, [0, 1]);
+        assert!(queue.wait_for_blocked_consumers(3, std::time::Duration::from_secs(5)));
+        assert!(rx.try_recv().is_err());
+
+        // The next goes to the one that has waited longest since
+        queue.enqueue(12);
+        assert_eq!(rx.recv().unwrap(), (2, 12));
+
+        queue.shutdown();
+        for c in consumers {
+            c.join().unwrap();
+        }
+        assert_eq!(queue.blocked_consumers(), 0);
+    }
+
+    #[test]
+    fn test_drain_while_stops_at_first_mismatch() {
+        let queue = Queue::new(8);
+        for item in [1, 2, 9, 3, 4] {
+            queue.enqueue(item);
+        }
+
+        assert_eq!(queue.drain_while(|&n| n < 5), vec![1, 2]);
+        assert_eq!(*queue, [9, 3, 4][..]);
+        assert_eq!(queue.item_bytes(), 3 * std::mem::size_of::<i32>());
+        assert!(queue.drain_while(|&n| n < 5).is_empty());
+    }
+
+    #[test]
+    fn test_drain_while_all_matching_and_empty() {
+        let queue = Queue::new(4);
+        assert!(queue.drain_while(|_: &u8| true).is_empty());
+
+        queue.enqueue(1u8);
+        queue.enqueue(2u8);
+        assert_eq!(queue.drain_while(|_| true), vec![1, 2]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_drain_while_wakes_blocked_producers() {
+        let queue = Queue::new(2);
+        queue.enqueue(1);
+        queue.enqueue(2);
+
+        let producers: Vec<_> = [3, 4]
+            .into_iter()
+            .map(|item| {
+                let q = Arc::clone(&queue);
+                std::thread::spawn(move || q.enqueue(item))
+            })
+            .collect();
+        assert!(queue.wait_for_blocked_producers(2, std::time::Duration::from_secs(5)));
+
+        assert_eq!(queue.drain_while(|_| true), vec![1, 2]);
+        for p in producers {
+            p.join().unwrap();
+        }
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_dequeue_filter_removes_first_match() {
+        let queue = Queue::new(8);
+        for item in [1, 2, 3, 2, 4] {
+            queue.enqueue(item);
+        }
+
+        assert_eq!(queue.dequeue_filter(|&n| n == 2), Some(2));
+        assert_eq!(*queue, [1, 3, 2, 4][..]);
+        assert_eq!(queue.dequeue_filter(|&n| n == 1), Some(1));
+        assert_eq!(*queue, [3, 2, 4][..]);
+        assert_eq!(queue.item_bytes(), 3 * std::mem::size_of::<i32>());
+    }
+
+    #[test]
+    fn test_dequeue_filter_no_match_does_not_block() {
+        let queue = Queue::new(2);
+        assert_eq!(queue.dequeue_filter(|_: &i32| true), None);
+
+        queue.enqueue(1);
+        assert_eq!(queue.dequeue_filter(|&n| n > 1), None);
+        assert_eq!(*queue, [1][..]);
+    }
+
+    #[test]
+    fn test_dequeue_filter_alongside_consumers() {
+        let queue = Queue::new(4);
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let q = Arc::clone(&queue);
+                std::thread::spawn(move || std::iter::from_fn(|| q.dequeue()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        let mut cancelled = Vec::new();
+        for item in 0..1_000 {
+            queue.enqueue(item);
+            if let Some(n) = queue.dequeue_filter(|n| n % 10 == 0) {
+                cancelled.push(n);
+            }
+        }
+        queue.shutdown();
+
+        let mut all: Vec<i32> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        assert!(cancelled.iter().all(|n| n % 10 == 0));
+        all.extend(&cancelled);
+        all.sort_unstable();
+        assert_eq!(all, (0..1_000).collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,786 @@
+//! Results of the simulation in a form scripts can consume.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::chan::{Backend, QueueImpl};
+use crate::checksum::Checksum;
+use crate::histogram::{Histogram, Percentiles};
+use crate::memory;
+use crate::occupancy;
+use crate::payload;
+use crate::sim::{Config, Outcome, TrialFailure};
+use crate::stats::Summary;
+use crate::threads::{DeadThread, ThreadStats};
+use crate::throughput;
+
+/// How a run ended, so scripts can tell results from failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Every trial finished and passed verification.
+    Ok,
+    /// The last trial was stopped early by Ctrl-C.
+    Interrupted,
+    /// A trial failed verification; the report describes that trial.
+    Failed,
+}
+
+/// Everything the measured trials recorded, serialized by `--output json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// How the run ended.
+    pub status: Status,
+    /// Why the run failed, when `status` is `failed`.
+    pub error: Option<String>,
+    /// Configuration the runs used, after thread limits were applied.
+    pub config: Config,
+    /// Number of measured trials.
+    pub trials: usize,
+    /// Number of trials run first and left out of the statistics.
+    pub warmup: usize,
+    /// Whether the last trial was stopped early by Ctrl-C.
+    pub interrupted: bool,
+    /// Items each producer was asked to enqueue, unless producers ran for a
+    /// fixed duration; they add up to the configuration's `items`.
+    pub items_per_producer: Option<Vec<usize>>,
+    /// Work of the last trial's warmup phase, left out of the rates, with
+    /// `--warmup-items` or `--warmup-ms`.
+    pub warmup_excluded: Option<WarmupExcluded>,
+    /// Items enqueued by all producers in the last trial.
+    pub produced: usize,
+    /// Items dequeued by all consumers in the last trial.
+    pub consumed: usize,
+    /// Wall time of each measured trial in seconds.
+    pub trial_secs: Vec<f64>,
+    /// Wall time in seconds across the measured trials.
+    pub elapsed_secs: Summary,
+    /// Items consumed per second of wall time across the measured trials.
+    pub items_per_sec: Summary,
+    /// Payload bytes consumed per second of wall time across the measured trials.
+    pub bytes_per_sec: Summary,
+    /// Items enqueued by each producer in the last trial.
+    pub producer_counts: Vec<usize>,
+    /// Items dequeued by each consumer in the last trial.
+    pub consumer_counts: Vec<usize>,
+    /// What each producer and then each consumer thread did in the last trial.
+    pub threads: Vec<ThreadStats>,
+    /// Most items seen in the queue by `--sample-ms` across the measured trials.
+    pub queue_high_watermark: Option<usize>,
+    /// Queue depth sampled by `--sample-ms` across the measured trials.
+    pub occupancy: Option<occupancy::Summary>,
+    /// Process and queue memory sampled by `--report-memory` across the
+    /// measured trials.
+    pub memory: Option<memory::Summary>,
+    /// Throughput of every `--sample-ms` interval of the last trial.
+    pub throughput: Option<throughput::Series>,
+    /// Fingerprints of the items produced and consumed in the last trial,
+    /// with `--verify checksum`.
+    pub checksums: Option<(Checksum, Checksum)>,
+    /// Throughput and input queue of each pipeline stage in the last trial,
+    /// with `--stages` above one.
+    pub stages: Option<Vec<StageReport>>,
+    /// Ordering violations found by `--verify-order` across the measured trials.
+    pub order_violations: usize,
+    /// Queue residence time across the measured trials, with `--latency`.
+    pub latency: Option<Percentiles>,
+    /// Threads of the last trial that panicked instead of finishing.
+    pub panicked: Vec<DeadThread>,
+}
+
+impl Report {
+    /// Builds the report for a set of finished trials.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration the trials used.
+    /// * `outcomes` - What each measured trial observed, in order; must not be empty.
+    /// * `warmup` - Number of trials that ran before these and were discarded.
+    pub fn new(config: &Config, outcomes: &[Outcome], warmup: usize) -> Self {
+        let last = outcomes.last().expect("at least one measured trial");
+        let trial_secs: Vec<f64> = outcomes.iter().map(|o| o.elapsed.as_secs_f64()).collect();
+        let rates: Vec<f64> = outcomes
+            .iter()
+            .zip(&trial_secs)
+            .map(|(o, &secs)| per_sec(o.measured(), secs))
+            .collect();
+        let item_bytes = payload::item_bytes(config.payload, config.payload_bytes) as f64;
+        let samples: Vec<_> = outcomes
+            .iter()
+            .flat_map(|o| o.occupancy.iter().copied())
+            .collect();
+        let queue_samples = |queue: usize| -> Vec<_> {
+            samples
+                .iter()
+                .filter(|s| s.queue == queue)
+                .copied()
+                .collect()
+        };
+        let occupancy = occupancy::Summary::of(&queue_samples(0), config.queue_size);
+        let stages = (!config.stage_workers.is_empty()).then(|| {
+            (0..=config.stage_workers.len() + 1)
+                .map(|stage| {
+                    let input = stage
+                        .checked_sub(1)
+                        .and_then(|q| occupancy::Summary::of(&queue_samples(q), config.queue_size));
+                    // Thread counters include the warmup, so rate them over all of it
+                    let secs = (last.warmup_elapsed + last.elapsed).as_secs_f64();
+                    StageReport::new(stage, &last.threads, secs, input)
+                })
+                .collect()
+        });
+        let byte_rates: Vec<f64> = rates.iter().map(|r| r * item_bytes).collect();
+
+        Self {
+            status: if last.interrupted {
+                Status::Interrupted
+            } else {
+                Status::Ok
+            },
+            error: None,
+            config: config.clone(),
+            trials: outcomes.len(),
+            warmup,
+            interrupted: last.interrupted,
+            items_per_producer: config.items_per_producer(),
+            warmup_excluded: config.warmup_phase.map(|_| WarmupExcluded {
+                items: last.warmup_items,
+                secs: last.warmup_elapsed.as_secs_f64(),
+            }),
+            produced: last.produced,
+            consumed: last.consumed,
+            elapsed_secs: Summary::of(&trial_secs),
+            items_per_sec: Summary::of(&rates),
+            bytes_per_sec: Summary::of(&byte_rates),
+            trial_secs,
+            producer_counts: last.producer_counts.clone(),
+            consumer_counts: last.consumer_counts.clone(),
+            threads: last.threads.clone(),
+            queue_high_watermark: occupancy.map(|o| o.max_depth),
+            occupancy,
+            memory: memory::Summary::of(
+                &outcomes
+                    .iter()
+                    .flat_map(|o| o.memory.iter().copied())
+                    .collect::<Vec<_>>(),
+            ),
+            throughput: throughput::Series::of(&last.consumed_samples),
+            checksums: last.checksums,
+            stages,
+            order_violations: outcomes.iter().map(|o| o.violations.len()).sum(),
+            latency: merged_latency(outcomes).map(|h| h.percentiles()),
+            panicked: last.panicked.clone(),
+        }
+    }
+}
+
+impl Report {
+    /// Builds the report of a trial that failed verification.
+    ///
+    /// # Arguments
+    ///
+    /// * `failure` - The failed trial.
+    /// * `warmup` - Number of warmup trials configured for the run.
+    pub fn failed(failure: &TrialFailure, warmup: usize) -> Self {
+        Self {
+            status: Status::Failed,
+            error: Some(failure.to_string()),
+            ..Self::new(
+                &failure.config,
+                std::slice::from_ref(&*failure.outcome),
+                warmup,
+            )
+        }
+    }
+}
+
+/// Work a trial did during its warmup phase.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WarmupExcluded {
+    /// Items consumed during the warmup.
+    pub items: usize,
+    /// Wall time of the warmup in seconds.
+    pub secs: f64,
+}
+
+/// What the threads of one pipeline stage did.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StageReport {
+    /// Position in the pipeline: 0 for producers, consumers last.
+    pub stage: usize,
+    /// Number of threads in the stage.
+    pub threads: usize,
+    /// Items the stage's threads handled.
+    pub items: usize,
+    /// Items handled per second of the trial's wall time.
+    pub items_per_sec: f64,
+    /// Average percentage of the threads' time spent in queue calls; the
+    /// stage that waits least is the bottleneck.
+    pub queue_pct: f64,
+    /// Occupancy of the queue the stage reads from, with `--sample-ms`.
+    pub input: Option<occupancy::Summary>,
+}
+
+impl StageReport {
+    /// Summarizes the threads of `stage` in a trial that took `secs` seconds.
+    pub fn new(
+        stage: usize,
+        threads: &[ThreadStats],
+        secs: f64,
+        input: Option<occupancy::Summary>,
+    ) -> Self {
+        let threads: Vec<_> = threads.iter().filter(|t| t.stage == stage).collect();
+        let items = threads.iter().map(|t| t.items).sum();
+        let queue_pct = if threads.is_empty() {
+            0.0
+        } else {
+            threads.iter().map(|t| t.queue_pct()).sum::<f64>() / threads.len() as f64
+        };
+        Self {
+            stage,
+            threads: threads.len(),
+            items,
+            items_per_sec: per_sec(items, secs),
+            queue_pct,
+            input,
+        }
+    }
+
+    /// The stage that spent the least time waiting on queues, which limits
+    /// the pipeline's throughput.
+    pub fn bottleneck(stages: &[StageReport]) -> Option<&StageReport> {
+        stages
+            .iter()
+            .min_by(|a, b| a.queue_pct.total_cmp(&b.queue_pct))
+    }
+}
+
+/// Reports of every point of a `--sweep-sizes` run, serialized by `--output json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepReport {
+    /// One report per queue size, in sweep order.
+    pub sweep: Vec<Report>,
+}
+
+/// Reports of every combination of a run matrix, serialized by `--output json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixReport {
+    /// One report per combination, in the order they ran.
+    pub matrix: Vec<Report>,
+    /// Index in `matrix` of the combination with the highest mean
+    /// throughput, or `None` if none ran.
+    pub best: Option<usize>,
+}
+
+impl MatrixReport {
+    /// Wraps the reports of a run matrix and picks its best combination.
+    pub fn new(matrix: Vec<Report>) -> Self {
+        let best = matrix
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.items_per_sec.mean.total_cmp(&b.items_per_sec.mean))
+            .map(|(index, _)| index);
+        Self { matrix, best }
+    }
+}
+
+/// Reports of every backend of a `--compare` run, serialized by `--output json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompareReport {
+    /// One report per backend, in the order they ran.
+    pub compare: Vec<Report>,
+    /// How much slower the mutex queue ran through a trait object than with
+    /// direct calls, in percent of the direct throughput.
+    pub dispatch_overhead_pct: Option<f64>,
+}
+
+impl CompareReport {
+    /// Wraps the reports of a `--compare` run and measures the trait object
+    /// overhead from its `fifo` and `fifo/mutex` points.
+    pub fn new(compare: Vec<Report>) -> Self {
+        let rate = |queue_impl| {
+            compare
+                .iter()
+                .find(|r| r.config.backend == Backend::Fifo && r.config.queue_impl == queue_impl)
+                .map(|r| r.items_per_sec.mean)
+        };
+        let dispatch_overhead_pct = match (rate(None), rate(Some(QueueImpl::Mutex))) {
+            (Some(direct), Some(dynamic)) if direct > 0.0 => {
+                Some((direct - dynamic) * 100.0 / direct)
+            }
+            _ => None,
+        };
+        Self {
+            compare,
+            dispatch_overhead_pct,
+        }
+    }
+}
+
+/// Result of one scenario of a `--scenarios` run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedReport {
+    /// Name the scenario file gave the workload.
+    pub name: String,
+    /// What the scenario's trials recorded, or the trial that failed.
+    #[serde(flatten)]
+    pub report: Report,
+}
+
+/// Results of a `--scenarios` run, serialized by `--output json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioReport {
+    /// One report per scenario that ran, in file order.
+    pub scenarios: Vec<NamedReport>,
+    /// Number of scenarios that failed verification.
+    pub failed: usize,
+    /// Wall time of every scenario's trials, warmup included, in seconds.
+    pub total_secs: f64,
+}
+
+/// Combines the latency histograms of every outcome that recorded one.
+fn merged_latency(outcomes: &[Outcome]) -> Option<Histogram> {
+    outcomes
+        .iter()
+        .filter_map(|o| o.latency.as_ref())
+        .fold(None, |merged, h| {
+            let mut merged = merged.unwrap_or_default();
+            merged.merge(h);
+            Some(merged)
+        })
+}
+
+/// Column names written as the first line of a new `--csv` file.
+pub const CSV_HEADER: &str = "timestamp,producers,consumers,items,queue_size,backend,\
+    producer_delay_ns,consumer_delay_ns,seed,\
+    burst_size,burst_pause_ms,trials,\
+    elapsed_secs_min,elapsed_secs_median,elapsed_secs_mean,elapsed_secs_stddev,\
+    items_per_sec_min,items_per_sec_median,items_per_sec_mean,items_per_sec_stddev,\
+    bytes_per_sec_mean,rss_peak_bytes,rss_mean_bytes,queue_peak_bytes";
+
+impl Report {
+    /// Formats the report as one CSV row matching [`CSV_HEADER`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - Seconds since the Unix epoch when the run finished.
+    ///
+    /// # Returns
+    ///
+    /// The row, terminated by a newline.
+    pub fn csv_row(&self, timestamp: u64) -> String {
+        let (t, r) = (&self.elapsed_secs, &self.items_per_sec);
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{:.6},{:.6},{:.6},{:.6},{:.1},{:.1},{:.1},{:.1},{:.1},{},{},{}\n",
+            timestamp,
+            self.config.producers,
+            self.config.consumers,
+            // Timed runs have no item target; record what they produced
+            match self.config.duration_ms {
+                Some(_) => self.produced,
+                None => self.config.items,
+            },
+            self.config.queue_size,
+            self.config.channel(),
+            self.config.producer_delay,
+            self.config.consumer_delay,
+            self.config.seed,
+            self.config.burst.map_or(0, |b| b.size),
+            self.config.burst.map_or(0, |b| b.pause_ms),
+            self.trials,
+            t.min,
+            t.median,
+            t.mean,
+            t.stddev,
+            r.min,
+            r.median,
+            r.mean,
+            r.stddev,
+            self.bytes_per_sec.mean,
+            // Left empty when memory was not sampled or could not be read
+            optional(self.memory.and_then(|m| m.peak_rss_bytes)),
+            optional(
+                self.memory
+                    .and_then(|m| m.mean_rss_bytes)
+                    .map(|bytes| bytes.round() as usize)
+            ),
+            optional(self.memory.and_then(|m| m.peak_queue_bytes)),
+        )
+    }
+
+    /// Appends the report as a CSV row to the file at `path`.
+    ///
+    /// The file is created with a header line if it does not exist or is
+    /// empty. Header and row go out in a single append write, so concurrent
+    /// runs appending to the same file do not interleave within a row.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from opening or writing the file.
+    pub fn append_csv(&self, path: &Path) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let mut text = String::new();
+        if file.metadata()?.len() == 0 {
+            text.push_str(CSV_HEADER);
+            text.push('\n');
+        }
+        text.push_str(&self.csv_row(timestamp));
+        file.write_all(text.as_bytes())
+    }
+}
+
+/// `value` as a CSV field, empty if it is `None`.
+fn optional(value: Option<usize>) -> String {
+    value.map_or_else(String::new, |v| v.to_string())
+}
+
+/// The closing line of the human-readable summary.
+///
+/// # Arguments
+///
+/// * `secs` - Wall time of a trial in seconds.
+/// * `produced` - Items produced in that time.
+///
+/// # Returns
+///
+/// The line, e.g. `Took 0.250000s with 1000 produced (4000 items/sec).`
+pub fn took(secs: f64, produced: usize) -> String {
+    format!(
+        "Took {secs:.6}s with {produced} produced ({:.0} items/sec).",
+        per_sec(produced, secs)
+    )
+}
+
+/// Rate of `count` events over `secs` seconds, zero if no time passed.
+fn per_sec(count: usize, secs: f64) -> f64 {
+    if secs > 0.0 { count as f64 / secs } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{self, DelayRange};
+    use crate::warmup::WarmupPhase;
+    use std::time::Duration;
+
+    #[test]
+    fn test_json_round_trip() {
+        let config = Config {
+            producers: 2,
+            consumers: 3,
+            items: 100,
+            queue_size: 4,
+            verify_order: true,
+            checksum: true,
+            latency: true,
+            sample_ms: Some(1),
+            ..Config::default()
+        };
+        let outcome = sim::run(&config, &Default::default());
+        let report = Report::new(&config, &[outcome], 0);
+
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+        assert_eq!((parsed.status, parsed.error), (Status::Ok, None));
+        assert_eq!(parsed.producer_counts, vec![50, 50]);
+        assert_eq!(parsed.consumer_counts.iter().sum::<usize>(), 100);
+        assert_eq!(parsed.order_violations, 0);
+        assert!(parsed.latency.is_some());
+        let (sent, received) = parsed.checksums.unwrap();
+        assert_eq!((sent, sent.count), (received, 100));
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["config"]["queue_size"], 4);
+        assert_eq!(value["consumed"], 100);
+        assert_eq!(value["threads"].as_array().unwrap().len(), 5);
+        assert_eq!(value["threads"][2]["role"], "consumer");
+    }
+
+    #[test]
+    fn test_failed_report() {
+        let config = Config {
+            items: 30,
+            ..Config::default()
+        };
+        let mut outcome = sim::run(&config, &Default::default());
+        outcome.consumed = 29;
+        let failure = TrialFailure {
+            config: Box::new(config),
+            trial: 2,
+            outcome: Box::new(outcome),
+        };
+
+        let value = serde_json::to_value(Report::failed(&failure, 1)).unwrap();
+        assert_eq!(value["status"], "failed");
+        assert_eq!(value["error"], failure.to_string());
+        assert_eq!(value["produced"], 30);
+        assert_eq!(value["consumed"], 29);
+    }
+
+    #[test]
+    fn test_took_prints_seconds() {
+        assert_eq!(
+            took(0.25, 1000),
+            "Took 0.250000s with 1000 produced (4000 items/sec)."
+        );
+        assert_eq!(
+            took(Duration::from_millis(1500).as_secs_f64(), 30),
+            "Took 1.500000s with 30 produced (20 items/sec)."
+        );
+        assert_eq!(
+            took(0.0, 0),
+            "Took 0.000000s with 0 produced (0 items/sec)."
+        );
+    }
+
+    #[test]
+    fn test_dispatch_overhead() {
+        let config = Config {
+            items: 20,
+            ..Config::default()
+        };
+        let outcome = sim::run(&config, &Default::default());
+        let report = |queue_impl, rate| {
+            let mut report = Report::new(
+                &Config {
+                    queue_impl,
+                    ..config.clone()
+                },
+                std::slice::from_ref(&outcome),
+                0,
+            );
+            report.items_per_sec.mean = rate;
+            report
+        };
+
+        let compare = CompareReport::new(vec![
+            report(None, 1000.0),
+            report(Some(QueueImpl::Mutex), 950.0),
+        ]);
+        assert_eq!(compare.dispatch_overhead_pct, Some(5.0));
+        let compare = CompareReport::new(vec![report(None, 1000.0)]);
+        assert_eq!(compare.dispatch_overhead_pct, None);
+    }
+
+    #[test]
+    fn test_matrix_best() {
+        let config = Config {
+            items: 20,
+            ..Config::default()
+        };
+        let outcome = sim::run(&config, &Default::default());
+        let report = |producers, rate| {
+            let mut report = Report::new(
+                &Config {
+                    producers,
+                    ..config.clone()
+                },
+                std::slice::from_ref(&outcome),
+                0,
+            );
+            report.items_per_sec.mean = rate;
+            report
+        };
+
+        let matrix = MatrixReport::new(vec![report(1, 500.0), report(2, 900.0), report(4, 700.0)]);
+        assert_eq!(matrix.best, Some(1));
+        assert_eq!(matrix.matrix[1].config.producers, 2);
+        assert_eq!(MatrixReport::new(Vec::new()).best, None);
+    }
+
+    #[test]
+    fn test_stage_reports() {
+        use crate::threads::Role;
+
+        let thread = |role, stage, items, queue_secs| ThreadStats {
+            role,
+            id: 0,
+            stage,
+            items,
+            queue_secs,
+            active_secs: 1.0,
+            cpu: None,
+        };
+        let threads = [
+            thread(Role::Producer, 0, 60, 0.9),
+            thread(Role::Producer, 0, 40, 0.7),
+            thread(Role::Worker, 1, 100, 0.1),
+            thread(Role::Consumer, 2, 100, 0.95),
+        ];
+
+        let stages: Vec<_> = (0..3)
+            .map(|stage| StageReport::new(stage, &threads, 2.0, None))
+            .collect();
+        assert_eq!(
+            stages
+                .iter()
+                .map(|s| (s.threads, s.items))
+                .collect::<Vec<_>>(),
+            [(2, 100), (1, 100), (1, 100)]
+        );
+        assert_eq!(stages[0].items_per_sec, 50.0);
+        assert!((stages[0].queue_pct - 80.0).abs() < 1e-9);
+        assert_eq!(StageReport::bottleneck(&stages).unwrap().stage, 1);
+        assert_eq!(StageReport::bottleneck(&[]), None);
+    }
+
+    #[test]
+    fn test_pipeline_report() {
+        let config = Config {
+            producers: 2,
+            consumers: 2,
+            items: 200,
+            stage_workers: vec![2, 1],
+            sample_ms: Some(1),
+            ..Config::default()
+        };
+        let outcome = sim::run(&config, &Default::default());
+        let report = Report::new(&config, &[outcome], 0);
+        let stages = report.stages.unwrap();
+        assert_eq!(stages.len(), 4);
+        assert!(stages.iter().all(|s| s.items == 200));
+        assert_eq!(stages[0].input, None);
+        assert!(stages[1..].iter().all(|s| s.input.is_some()));
+    }
+
+    #[test]
+    fn test_trial_statistics() {
+        let config = Config {
+            producers: 1,
+            consumers: 1,
+            items: 20,
+            queue_size: 2,
+            ..Config::default()
+        };
+        let mut outcomes: Vec<_> = (0..3)
+            .map(|_| sim::run(&config, &Default::default()))
+            .collect();
+        // Pin the timings so the statistics can be checked by hand.
+        for (outcome, ms) in outcomes.iter_mut().zip([10, 40, 20]) {
+            outcome.elapsed = Duration::from_millis(ms);
+        }
+
+        let report = Report::new(&config, &outcomes, 1);
+        assert_eq!((report.trials, report.warmup), (3, 1));
+        assert_eq!(report.trial_secs, vec![0.01, 0.04, 0.02]);
+
+        let t = report.elapsed_secs;
+        assert_eq!((t.min, t.median), (0.01, 0.02));
+        assert!((t.mean - 0.07 / 3.0).abs() < 1e-12);
+        // deviations from 7/300: -4/300, 5/300, -1/300; squares sum to 42/90000
+        assert!((t.stddev - (21.0f64 / 90_000.0).sqrt()).abs() < 1e-12);
+
+        // 20 items in 10, 40 and 20 ms
+        let r = report.items_per_sec;
+        assert_eq!((r.min, r.median), (500.0, 1000.0));
+        assert!((r.mean - 3500.0 / 3.0).abs() < 1e-9);
+
+        // int payloads are eight bytes each
+        let b = report.bytes_per_sec;
+        assert_eq!((b.min, b.median), (4000.0, 8000.0));
+    }
+
+    #[test]
+    fn test_warmup_left_out_of_rates() {
+        let config = Config {
+            items: 20,
+            queue_size: 2,
+            warmup_phase: Some(WarmupPhase::Items(5)),
+            ..Config::default()
+        };
+        let mut outcome = sim::run(&config, &Default::default());
+        outcome.elapsed = Duration::from_millis(10);
+        outcome.warmup_elapsed = Duration::from_millis(4);
+
+        let report = Report::new(&config, &[outcome], 0);
+        // 15 measured items in 10 ms
+        assert_eq!(report.items_per_sec.mean, 1500.0);
+        let expected = WarmupExcluded {
+            items: 5,
+            secs: 0.004,
+        };
+        assert_eq!(report.warmup_excluded, Some(expected));
+        assert_eq!(report.consumed, 20);
+    }
+
+    #[test]
+    fn test_csv_row() {
+        let report = Report {
+            status: Status::Ok,
+            error: None,
+            config: Config {
+                producers: 4,
+                consumers: 2,
+                items: 1000,
+                queue_size: 16,
+                consumer_delay: DelayRange::UP_TO_1MS,
+                seed: 99,
+                ..Config::default()
+            },
+            trials: 2,
+            warmup: 0,
+            interrupted: false,
+            items_per_producer: Some(vec![250; 4]),
+            warmup_excluded: None,
+            produced: 1000,
+            consumed: 1000,
+            trial_secs: vec![0.2, 0.3],
+            elapsed_secs: Summary::of(&[0.2, 0.3]),
+            items_per_sec: Summary::of(&[5000.0, 3000.0]),
+            bytes_per_sec: Summary::of(&[40_000.0, 24_000.0]),
+            producer_counts: vec![250; 4],
+            consumer_counts: vec![500; 2],
+            threads: Vec::new(),
+            queue_high_watermark: None,
+            occupancy: None,
+            memory: None,
+            throughput: None,
+            checksums: None,
+            stages: None,
+            order_violations: 0,
+            latency: None,
+            panicked: Vec::new(),
+        };
+
+        let row = report.csv_row(1_700_000_000);
+        assert_eq!(
+            row,
+            "1700000000,4,2,1000,16,fifo,0..0,0..1000000,99,0,0,2,0.200000,0.250000,0.250000,0.070711,\
+             3000.0,4000.0,4000.0,1414.2,32000.0,,,\n"
+        );
+        assert_eq!(
+            row.trim_end_matches('\n').split(',').count(),
+            CSV_HEADER.split(',').count()
+        );
+
+        let sampled = Report {
+            memory: Some(memory::Summary {
+                samples: 3,
+                peak_rss_bytes: Some(8_000_000),
+                mean_rss_bytes: Some(6_500_000.4),
+                peak_queue_bytes: None,
+                mean_queue_bytes: None,
+            }),
+            ..report
+        };
+        assert!(
+            sampled
+                .csv_row(1_700_000_000)
+                .ends_with(",32000.0,8000000,6500000,\n")
+        );
+    }
+
+    #[test]
+    fn test_per_sec() {
+        assert_eq!(per_sec(100, 2.0), 50.0);
+        assert_eq!(per_sec(100, 0.0), 0.0);
+    }
+}
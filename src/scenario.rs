@@ -0,0 +1,164 @@
+//! Named workloads read from a TOML file by `--scenarios`.
+//!
+//! A file lists one `[[scenario]]` table per workload:
+//!
+//! ```toml
+//! [[scenario]]
+//! name = "balanced"
+//! producers = 2
+//! consumers = 2
+//! items = 20
+//! size = 5
+//! delay = false   # optional, defaults to false
+//! seed = 7        # optional, defaults to the run's seed
+//! ```
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// One workload of a scenario file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Name printed next to the scenario's result; unique within a file.
+    pub name: String,
+    /// Number of producer threads.
+    pub producers: usize,
+    /// Number of consumer threads.
+    pub consumers: usize,
+    /// Total items to produce across all producers.
+    pub items: usize,
+    /// Capacity of the queue.
+    pub size: usize,
+    /// Whether threads sleep a random 0-1 ms before each operation.
+    #[serde(default)]
+    pub delay: bool,
+    /// Seed of the delays, if the scenario pins one.
+    pub seed: Option<u64>,
+}
+
+/// The top level of a scenario file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    #[serde(rename = "scenario", default)]
+    scenarios: Vec<Scenario>,
+}
+
+/// Parses the text of a scenario file.
+///
+/// # Returns
+///
+/// The scenarios in the order they appear.
+///
+/// # Errors
+///
+/// Returns a message if the text is not valid TOML, a field is missing or
+/// unknown, a count is zero, a name repeats, or there are no scenarios.
+pub fn parse(text: &str) -> Result<Vec<Scenario>, String> {
+    let file: ScenarioFile = toml::from_str(text).map_err(|err| err.to_string())?;
+    if file.scenarios.is_empty() {
+        return Err(String::from("no [[scenario]] tables found"));
+    }
+
+    let mut names = HashSet::new();
+    for scenario in &file.scenarios {
+        let counts = [
+            ("producers", scenario.producers),
+            ("consumers", scenario.consumers),
+            ("items", scenario.items),
+            ("size", scenario.size),
+        ];
+        if let Some((field, _)) = counts.iter().find(|(_, n)| *n == 0) {
+            return Err(format!(
+                "scenario `{}`: {field} must be at least 1",
+                scenario.name
+            ));
+        }
+        if !names.insert(scenario.name.as_str()) {
+            return Err(format!("scenario `{}` is listed twice", scenario.name));
+        }
+    }
+    Ok(file.scenarios)
+}
+
+/// Reads and parses the scenario file at `path`.
+///
+/// # Errors
+///
+/// Returns a message naming the file if it cannot be read or [`parse`]
+/// rejects it.
+pub fn load(path: &Path) -> Result<Vec<Scenario>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let scenarios = parse(
+            r#"
+            [[scenario]]
+            name = "small"
+            producers = 2
+            consumers = 3
+            items = 20
+            size = 4
+
+            [[scenario]]
+            name = "slow"
+            producers = 1
+            consumers = 1
+            items = 5
+            size = 1
+            delay = true
+            seed = 42
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            scenarios[0],
+            Scenario {
+                name: String::from("small"),
+                producers: 2,
+                consumers: 3,
+                items: 20,
+                size: 4,
+                delay: false,
+                seed: None,
+            }
+        );
+        assert_eq!((scenarios[1].delay, scenarios[1].seed), (true, Some(42)));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_files() {
+        let one = |extra: &str| {
+            format!("[[scenario]]\nname = \"a\"\nproducers = 1\nconsumers = 1\nitems = 1\n{extra}")
+        };
+        assert!(parse(&one("size = 1")).is_ok());
+
+        let err = parse(&one("size = 0")).unwrap_err();
+        assert!(err.contains("size must be at least 1"), "{err}");
+        let err = parse(&format!("{}\n{}", one("size = 1"), one("size = 2"))).unwrap_err();
+        assert!(err.contains("listed twice"), "{err}");
+
+        assert!(parse(&one("")).is_err());
+        assert!(parse(&one("size = 1\nthreads = 4")).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn test_sample_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios.toml");
+        let scenarios = load(&path).unwrap();
+        assert_eq!(scenarios.len(), 21);
+        assert!(load(Path::new("no-such-scenarios.toml")).is_err());
+    }
+}
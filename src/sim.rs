@@ -0,0 +1,1792 @@
+//! The producer/consumer simulation driven by `main`.
+
+use fifo_bounded_buffer::Queue;
+use log::{Level, debug, info, log_enabled, trace};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::affinity::{self, PinStrategy};
+use crate::chan::{Backend, Chan, DynChan, QueueImpl, StdMpsc};
+use crate::checksum::Checksum;
+use crate::events::{EventKind, EventLog};
+use crate::histogram::Histogram;
+use crate::memory::{self, MemorySample};
+use crate::occupancy::Sample;
+use crate::payload::{Payload, PayloadKind};
+use crate::progress::{Progress, Reporter};
+use crate::threads::{self, DeadThread, PanicInjection, Role, ThreadStats};
+use crate::throughput::Snapshot;
+use crate::verify::{self, Tagged, Violation};
+use crate::warmup::{Warmup, WarmupPhase};
+use crate::watchdog::{self, Watchdog};
+
+/// Random sleep before every enqueue or dequeue of one side of the queue.
+///
+/// Delays are drawn uniformly from `min_ns..max_ns`; equal bounds always
+/// sleep exactly `min_ns`, so `0..0` means no delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelayRange {
+    /// Shortest delay in nanoseconds.
+    pub min_ns: u64,
+    /// Longest delay in nanoseconds, exclusive unless equal to `min_ns`.
+    pub max_ns: u64,
+}
+
+impl DelayRange {
+    /// No delay at all.
+    pub const NONE: DelayRange = DelayRange {
+        min_ns: 0,
+        max_ns: 0,
+    };
+    /// The 0-1 ms delay of `-d`.
+    pub const UP_TO_1MS: DelayRange = DelayRange {
+        min_ns: 0,
+        max_ns: 1_000_000,
+    };
+
+    /// Whether the range never sleeps.
+    pub fn is_none(&self) -> bool {
+        self.max_ns == 0
+    }
+
+    /// The next delay drawn from `rng`.
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        if self.min_ns == self.max_ns {
+            Duration::from_nanos(self.min_ns)
+        } else {
+            Duration::from_nanos(rng.random_range(self.min_ns..self.max_ns))
+        }
+    }
+}
+
+impl fmt::Display for DelayRange {
+    /// Formats the range as `--producer-delay-ns` takes it, e.g. `0..1000000`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.min_ns, self.max_ns)
+    }
+}
+
+/// Producers enqueue `size` items back to back, then pause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Burst {
+    /// Items per burst.
+    pub size: usize,
+    /// Milliseconds to sleep after each burst.
+    pub pause_ms: u64,
+}
+
+impl Burst {
+    /// How long to pause after enqueueing item `index`, counting from zero.
+    ///
+    /// # Returns
+    ///
+    /// The pause if `index` ends a burst, `None` otherwise.
+    pub fn pause_after(&self, index: usize) -> Option<Duration> {
+        (index + 1)
+            .is_multiple_of(self.size)
+            .then(|| Duration::from_millis(self.pause_ms))
+    }
+}
+
+/// Parameters of a single simulation run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Number of producer threads.
+    pub producers: usize,
+    /// Number of consumer threads.
+    pub consumers: usize,
+    /// Total items to produce across all producers, unless `duration_ms` is set.
+    pub items: usize,
+    /// Run producers for this many milliseconds instead of a fixed item count.
+    pub duration_ms: Option<u64>,
+    /// Capacity of the queue.
+    pub queue_size: usize,
+    /// Channel implementation items pass through.
+    pub backend: Backend,
+    /// Internals of the fifo backend, reached through a trait object; `None`
+    /// calls [`Queue`] directly.
+    pub queue_impl: Option<QueueImpl>,
+    /// Random sleep of producers before each enqueue.
+    pub producer_delay: DelayRange,
+    /// Random sleep of consumers before each dequeue.
+    pub consumer_delay: DelayRange,
+    /// Seed the per-thread delay generators are derived from.
+    pub seed: u64,
+    /// Whether producers enqueue in bursts separated by pauses.
+    pub burst: Option<Burst>,
+    /// Whether consumers log every item so FIFO delivery can be checked.
+    pub verify_order: bool,
+    /// Whether both sides fingerprint the items they saw so any difference
+    /// between what was produced and what was consumed is caught.
+    pub checksum: bool,
+    /// Whether items are timestamped to measure how long they wait in the queue.
+    pub latency: bool,
+    /// Type of value each item carries.
+    pub payload: PayloadKind,
+    /// Size in bytes of `Bytes` and `String` payloads.
+    pub payload_bytes: usize,
+    /// Interval in milliseconds between samples of the queue's length, if sampled.
+    pub sample_ms: Option<u64>,
+    /// How producers and consumers are pinned to CPUs, if they are.
+    pub pin: Option<PinStrategy>,
+    /// With trace logging, log only every this many items of each thread.
+    pub log_every: usize,
+    /// Worker threads of each stage between two queues; empty for a single
+    /// queue between producers and consumers.
+    pub stage_workers: Vec<usize>,
+    /// Nanoseconds of busy work stage workers do on every item.
+    pub work_ns: u64,
+    /// File every enqueue, dequeue and wait of the trial is written to.
+    pub trace_file: Option<PathBuf>,
+    /// Most rows written to `trace_file`.
+    pub trace_limit: usize,
+    /// Seconds without any item moving after which the run is dumped and
+    /// aborted, if watched.
+    pub watchdog_secs: Option<u64>,
+    /// Whether a line with the run's progress is printed to stderr every second.
+    pub progress: bool,
+    /// Thread made to panic partway through the run, to test recovery.
+    pub inject_panic: Option<PanicInjection>,
+    /// Work at the start of each trial left out of its measurements.
+    pub warmup_phase: Option<WarmupPhase>,
+    /// Whether the process's and the queues' memory is sampled, every
+    /// `sample_ms` or [`memory::DEFAULT_SAMPLE_MS`].
+    pub report_memory: bool,
+}
+
+impl Default for Config {
+    /// The configuration the binary runs without arguments.
+    fn default() -> Self {
+        Self {
+            producers: 1,
+            consumers: 1,
+            items: 10,
+            duration_ms: None,
+            queue_size: 5,
+            backend: Backend::Fifo,
+            queue_impl: None,
+            producer_delay: DelayRange::NONE,
+            consumer_delay: DelayRange::NONE,
+            seed: 0,
+            burst: None,
+            verify_order: false,
+            checksum: false,
+            latency: false,
+            payload: PayloadKind::Int,
+            payload_bytes: 64,
+            sample_ms: None,
+            pin: None,
+            log_every: 1000,
+            stage_workers: Vec::new(),
+            work_ns: 0,
+            trace_file: None,
+            trace_limit: 1_000_000,
+            watchdog_secs: Some(60),
+            progress: false,
+            inject_panic: None,
+            warmup_phase: None,
+            report_memory: false,
+        }
+    }
+}
+
+impl Config {
+    /// Whether producers or consumers sleep before their queue calls.
+    pub fn is_delayed(&self) -> bool {
+        !self.producer_delay.is_none() || !self.consumer_delay.is_none()
+    }
+
+    /// Name of the channel items pass through, e.g. `fifo` or `fifo/mutex`
+    /// for a queue chosen with `--queue-impl`.
+    pub fn channel(&self) -> String {
+        match self.queue_impl {
+            Some(imp) => format!("{}/{}", self.backend.name(), imp.name()),
+            None => self.backend.name().to_string(),
+        }
+    }
+
+    /// The items each producer enqueues, or `None` when producers run for
+    /// a fixed duration instead.
+    pub fn items_per_producer(&self) -> Option<Vec<usize>> {
+        self.duration_ms
+            .is_none()
+            .then(|| split_items(self.items, self.producers))
+    }
+
+    /// The items of each producer in words, e.g. `250 per producer` or
+    /// `3-4 per producer` when they do not divide evenly.
+    ///
+    /// # Returns
+    ///
+    /// The description, or `None` when producers run for a fixed duration.
+    pub fn per_producer(&self) -> Option<String> {
+        let shares = self.items_per_producer()?;
+        let min = shares.iter().min().copied().unwrap_or(0);
+        let max = shares.iter().max().copied().unwrap_or(0);
+        Some(if min == max {
+            format!("{max} per producer")
+        } else {
+            format!("{min}-{max} per producer")
+        })
+    }
+}
+
+/// An item passed through the queue.
+struct Item {
+    /// Producer and sequence number.
+    tag: Tagged,
+    /// When the producer started enqueueing the item, if latency is measured.
+    sent: Option<Instant>,
+    /// Data the consumer checks against the tag.
+    payload: Payload,
+}
+
+/// Seed of the delay generator of thread `index`.
+///
+/// Producers are numbered from zero and consumers follow them, so the same
+/// seed and thread counts always give each thread the same delays.
+pub fn thread_seed(seed: u64, index: usize) -> u64 {
+    seed ^ index as u64
+}
+
+/// What a consumer thread hands back when it is joined.
+struct Consumed {
+    stats: ThreadStats,
+    corrupted: usize,
+    checksum: Checksum,
+    log: Vec<Tagged>,
+    latency: Histogram,
+    /// Items taken during the warmup phase.
+    warmup: usize,
+}
+
+/// What a simulation run observed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// Items enqueued by all producers.
+    pub produced: usize,
+    /// Items dequeued by all consumers.
+    pub consumed: usize,
+    /// Items enqueued by each producer.
+    pub producer_counts: Vec<usize>,
+    /// Items dequeued by each consumer.
+    pub consumer_counts: Vec<usize>,
+    /// What each producer and then each consumer thread did.
+    pub threads: Vec<ThreadStats>,
+    /// Items whose payload did not match what their producer built.
+    pub corrupted: usize,
+    /// Whether the run was stopped before every item was produced.
+    pub interrupted: bool,
+    /// Whether every queue was empty after every thread was joined.
+    pub queue_empty: bool,
+    /// Wall time from the end of the warmup phase, or from spawning the
+    /// first thread without one, to joining the last thread.
+    pub elapsed: Duration,
+    /// Items consumed during the warmup phase, left out of the rates.
+    pub warmup_items: usize,
+    /// Wall time of the warmup phase.
+    pub warmup_elapsed: Duration,
+    /// Fingerprints of what was produced and what was consumed, when
+    /// `checksum` is set.
+    pub checksums: Option<(Checksum, Checksum)>,
+    /// Ordering problems found when `verify_order` is set.
+    pub violations: Vec<Violation>,
+    /// Nanoseconds each item spent between its enqueue call and being
+    /// dequeued, when `latency` is set.
+    pub latency: Option<Histogram>,
+    /// The length of every queue at every `sample_ms` interval of the run.
+    pub occupancy: Vec<Sample>,
+    /// Memory in use at every sample of the run, with `report_memory`.
+    pub memory: Vec<MemorySample>,
+    /// Items consumed so far at every `sample_ms` interval of the run, and
+    /// once more at its end.
+    pub consumed_samples: Vec<Snapshot>,
+    /// Threads that panicked instead of finishing.
+    pub panicked: Vec<DeadThread>,
+}
+
+/// Runs producers and consumers against a fresh channel of the configured
+/// backend until every item has been produced and consumed.
+///
+/// Producers check `stop` before each item and finish early once it is set;
+/// consumers still drain whatever was enqueued, so the run ends with every
+/// produced item consumed.
+///
+/// # Arguments
+///
+/// * `config` - Thread counts, item total, and queue capacity to use.
+/// * `stop` - Set from another thread to end the run early.
+///
+/// # Returns
+///
+/// The totals observed by the threads, the elapsed wall time, and any
+/// ordering violations.
+pub fn run(config: &Config, stop: &Arc<AtomicBool>) -> Outcome {
+    let capacity = config.queue_size;
+    let queues = config.stage_workers.len() + 1;
+    match (config.backend, config.queue_impl) {
+        // Sizing every item costs a little, so only when it is reported
+        (Backend::Fifo, None) if config.report_memory => run_on(
+            (0..queues)
+                .map(|_| {
+                    Queue::<Box<Item>>::builder(capacity)
+                        .item_size(|item| size_of::<Item>() + item.payload.heap_bytes())
+                        .build()
+                })
+                .collect(),
+            config,
+            stop,
+        ),
+        (Backend::Fifo, None) => run_on(
+            (0..queues).map(|_| Queue::new(capacity)).collect(),
+            config,
+            stop,
+        ),
+        (Backend::Fifo, Some(imp)) => run_on(
+            (0..queues)
+                .map(|_| Arc::new(DynChan(imp.build(capacity))))
+                .collect(),
+            config,
+            stop,
+        ),
+        (Backend::StdMpsc, _) => run_on(
+            (0..queues)
+                .map(|_| Arc::new(StdMpsc::new(capacity)))
+                .collect(),
+            config,
+            stop,
+        ),
+        #[cfg(feature = "crossbeam")]
+        (Backend::Crossbeam, _) => run_on(
+            (0..queues)
+                .map(|_| Arc::new(crate::chan::Crossbeam::new(capacity)))
+                .collect(),
+            config,
+            stop,
+        ),
+    }
+}
+
+/// Busy-waits for `ns` nanoseconds, standing in for work done on an item.
+fn spin_for(ns: u64) {
+    let until = Instant::now() + Duration::from_nanos(ns);
+    while Instant::now() < until {
+        std::hint::spin_loop();
+    }
+}
+
+/// Runs the simulation described by `config` through `queues`, chained in
+/// series with the configured stage workers between each pair.
+fn run_on<C>(queues: Vec<Arc<C>>, config: &Config, stop: &Arc<AtomicBool>) -> Outcome
+where
+    C: Chan<Box<Item>> + 'static,
+{
+    let first = Arc::clone(&queues[0]);
+    let last = Arc::clone(queues.last().expect("at least one queue"));
+    let last_stage = queues.len();
+    let (producer_delay, consumer_delay) = (config.producer_delay, config.consumer_delay);
+    let burst = config.burst;
+    let seed = config.seed;
+    let first_consumer = config.producers;
+    let verify_order = config.verify_order;
+    let checksum = config.checksum;
+    let latency = config.latency;
+    let (kind, bytes) = (config.payload, config.payload_bytes);
+    let shares = config
+        .items_per_producer()
+        .unwrap_or_else(|| vec![usize::MAX; config.producers]);
+    let cpus = config.pin.map_or_else(Vec::new, |strategy| {
+        affinity::plan(
+            &affinity::topology(),
+            config.producers,
+            config.consumers,
+            strategy,
+        )
+    });
+    let capacity = config.queue_size;
+    let log_every = config.log_every;
+    // Checked once so runs without -vv pay nothing for logging per item
+    let log_waits = log_enabled!(Level::Debug);
+    let log_items = log_enabled!(Level::Trace);
+    let start = Instant::now();
+    let deadline = config
+        .duration_ms
+        .map(|ms| start + Duration::from_millis(ms));
+    let events = config.trace_file.as_ref().and_then(|path| {
+        EventLog::create(path, config.trace_limit, start)
+            .map_err(|err| eprintln!("warning: could not write {}: {err}", path.display()))
+            .ok()
+            .map(Arc::new)
+    });
+
+    // Thread indices in the progress counters: producers, workers, consumers
+    let first_worker = config.producers;
+    let progress = {
+        let producers = (0..config.producers).map(|id| format!("producer {id}"));
+        let workers = config
+            .stage_workers
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &n)| (0..n).map(move |id| format!("worker {}.{id}", index + 1)));
+        let consumers = (0..config.consumers).map(|id| format!("consumer {id}"));
+        Progress::new(producers.chain(workers).chain(consumers).collect())
+    };
+    let first_consumer_thread = first_worker + config.stage_workers.iter().sum::<usize>();
+    let watchdog = config.watchdog_secs.map(|secs| {
+        let queues = queues.clone();
+        Watchdog::arm(
+            Arc::clone(&progress),
+            Duration::from_secs(secs),
+            move || queues.iter().map(|q| q.state()).collect(),
+            watchdog::abort,
+        )
+    });
+    let reporter = config.progress.then(|| {
+        Reporter::start(
+            Arc::clone(&progress),
+            0..first_worker,
+            first_consumer_thread..first_consumer_thread + config.consumers,
+            config.duration_ms.is_none().then_some(config.items),
+            Duration::from_secs(1),
+        )
+    });
+    // Set by a thread that panics, so the others wind down instead of waiting
+    let aborted = Arc::new(AtomicBool::new(false));
+    let inject = config.inject_panic;
+    let warmup = Arc::new(Warmup::new(config.warmup_phase, start));
+
+    // Spawn the sampler first so it sees the queues fill up
+    let sampling = Arc::new(AtomicBool::new(true));
+    // Backends that cannot report their length are not sampled
+    let sample_queues = config.sample_ms.is_some() && first.len().is_some();
+    let sample_throughput = config.sample_ms.is_some();
+    let report_memory = config.report_memory;
+    let sample_ms = config
+        .sample_ms
+        .or(report_memory.then_some(memory::DEFAULT_SAMPLE_MS));
+    let consumer_threads = first_consumer_thread..first_consumer_thread + config.consumers;
+    let sampler = sample_ms.map(|ms| {
+        let queues = queues.clone();
+        let sampling = Arc::clone(&sampling);
+        let progress = Arc::clone(&progress);
+        thread::spawn(move || {
+            let (mut samples, mut memory, mut consumed) = (Vec::new(), Vec::new(), Vec::new());
+            let snapshot = || Snapshot {
+                elapsed: start.elapsed(),
+                consumed: progress.sum(consumer_threads.clone()),
+            };
+            while sampling.load(Ordering::Relaxed) {
+                let elapsed = start.elapsed();
+                if sample_throughput {
+                    consumed.push(snapshot());
+                }
+                if sample_queues {
+                    for (queue, q) in queues.iter().enumerate() {
+                        samples.push(Sample {
+                            queue,
+                            elapsed,
+                            len: q.len().unwrap_or(0),
+                        });
+                    }
+                }
+                if report_memory {
+                    memory.push(MemorySample {
+                        elapsed,
+                        rss_bytes: memory::rss_bytes(),
+                        queue_bytes: queues.iter().map(|q| q.memory_usage()).sum(),
+                    });
+                }
+                thread::sleep(Duration::from_millis(ms));
+            }
+            // The last interval runs up to when every item was through
+            if sample_throughput {
+                consumed.push(snapshot());
+            }
+            (samples, memory, consumed)
+        })
+    });
+
+    // Spawn producers
+    let producers: Vec<_> = shares
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(id, share)| {
+            let q = Arc::clone(&first);
+            let stop = Arc::clone(stop);
+            let cpu = cpus.get(id).copied();
+            let label = format!("producer-{id}");
+            let mut events = events.as_ref().map(EventLog::recorder);
+            let progress = Arc::clone(&progress);
+            let aborted = Arc::clone(&aborted);
+            let guard = CloseOnPanic::new(&queues, &aborted);
+            thread::spawn(move || {
+                let _guard = guard;
+                let cpu = affinity::try_pin(cpu);
+                match cpu {
+                    Some(cpu) => debug!("{label} started on cpu {cpu}"),
+                    None => debug!("{label} started"),
+                }
+                let mut rng = StdRng::seed_from_u64(thread_seed(seed, id));
+                let mut sent_sum = Checksum::default();
+                let mut count = share;
+                let mut in_queue = Duration::ZERO;
+                let started = Instant::now();
+                for i in 0..share {
+                    if stop.load(Ordering::Relaxed)
+                        || aborted.load(Ordering::Relaxed)
+                        || deadline.is_some_and(|d| Instant::now() >= d)
+                    {
+                        count = i;
+                        break;
+                    }
+                    if inject.is_some_and(|p| p.hits(Role::Producer, id, i)) {
+                        panic!("injected panic");
+                    }
+                    if !producer_delay.is_none() {
+                        thread::sleep(producer_delay.sample(&mut rng));
+                    }
+
+                    let tag = (id, i);
+                    let payload = Payload::new(kind, bytes, tag);
+                    let sent = latency.then(Instant::now);
+                    let full = (log_waits || events.is_some()) && q.len() == Some(capacity);
+                    if full {
+                        debug!("{label} waiting for space: queue full");
+                        if let Some(events) = &mut events {
+                            events.record(EventKind::BlockStart, Some(capacity));
+                        }
+                    }
+                    let call = Instant::now();
+                    q.send(Box::new(Item { tag, sent, payload }));
+                    in_queue += call.elapsed();
+                    progress.tick(id);
+                    if let Some(events) = &mut events {
+                        let len = q.len();
+                        if full {
+                            events.record(EventKind::BlockEnd, len);
+                        }
+                        events.record(EventKind::Enqueue, len);
+                    }
+                    if log_items && i.is_multiple_of(log_every) {
+                        trace!("{label} enqueued item {i}");
+                    }
+                    if checksum {
+                        sent_sum.add(tag);
+                    }
+
+                    if let Some(pause) = burst.and_then(|b| b.pause_after(i)) {
+                        thread::sleep(pause);
+                    }
+                }
+                debug!("{label} finished after {count} items");
+                progress.finish(id);
+                let stats = ThreadStats {
+                    role: Role::Producer,
+                    id,
+                    stage: 0,
+                    items: count,
+                    queue_secs: in_queue.as_secs_f64(),
+                    active_secs: started.elapsed().as_secs_f64(),
+                    cpu,
+                };
+                (stats, sent_sum)
+            })
+        })
+        .collect();
+
+    // Spawn the workers of each stage between two queues
+    let work_ns = config.work_ns;
+    let stages: Vec<Vec<_>> = config
+        .stage_workers
+        .iter()
+        .enumerate()
+        .map(|(index, &workers)| {
+            let stage = index + 1;
+            let first_thread = first_worker + config.stage_workers[..index].iter().sum::<usize>();
+            (0..workers)
+                .map(|id| {
+                    let input = Arc::clone(&queues[index]);
+                    let output = Arc::clone(&queues[stage]);
+                    let label = format!("worker-{stage}.{id}");
+                    let progress = Arc::clone(&progress);
+                    let guard = CloseOnPanic::new(&queues, &aborted);
+                    thread::spawn(move || {
+                        let _guard = guard;
+                        debug!("{label} started");
+                        let mut in_queue = Duration::ZERO;
+                        let mut items = 0;
+                        let started = Instant::now();
+                        loop {
+                            let call = Instant::now();
+                            let item = input.recv();
+                            in_queue += call.elapsed();
+                            let Some(item) = item else {
+                                break;
+                            };
+                            if work_ns > 0 {
+                                spin_for(work_ns);
+                            }
+                            let call = Instant::now();
+                            output.send(item);
+                            in_queue += call.elapsed();
+                            items += 1;
+                            progress.tick(first_thread + id);
+                        }
+                        debug!("{label} finished after {items} items");
+                        progress.finish(first_thread + id);
+                        ThreadStats {
+                            role: Role::Worker,
+                            id,
+                            stage,
+                            items,
+                            queue_secs: in_queue.as_secs_f64(),
+                            active_secs: started.elapsed().as_secs_f64(),
+                            cpu: None,
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    // Spawn consumers
+    let consumers: Vec<_> = (0..config.consumers)
+        .map(|id| {
+            let q = Arc::clone(&last);
+            let cpu = cpus.get(first_consumer + id).copied();
+            let label = format!("consumer-{id}");
+            let mut events = events.as_ref().map(EventLog::recorder);
+            let progress = Arc::clone(&progress);
+            let warmup = Arc::clone(&warmup);
+            let guard = CloseOnPanic::new(&queues, &aborted);
+            thread::spawn(move || {
+                let _guard = guard;
+                let cpu = affinity::try_pin(cpu);
+                match cpu {
+                    Some(cpu) => debug!("{label} started on cpu {cpu}"),
+                    None => debug!("{label} started"),
+                }
+                let mut rng = StdRng::seed_from_u64(thread_seed(seed, first_consumer + id));
+                let mut in_queue = Duration::ZERO;
+                let started = Instant::now();
+                let mut consumed = Consumed {
+                    stats: ThreadStats {
+                        role: Role::Consumer,
+                        id,
+                        stage: last_stage,
+                        items: 0,
+                        queue_secs: 0.0,
+                        active_secs: 0.0,
+                        cpu,
+                    },
+                    corrupted: 0,
+                    checksum: Checksum::default(),
+                    log: Vec::new(),
+                    latency: Histogram::new(),
+                    warmup: 0,
+                };
+                loop {
+                    if inject.is_some_and(|p| p.hits(Role::Consumer, id, consumed.stats.items)) {
+                        panic!("injected panic");
+                    }
+                    if !consumer_delay.is_none() {
+                        thread::sleep(consumer_delay.sample(&mut rng));
+                    }
+
+                    let empty = (log_waits || events.is_some()) && q.len() == Some(0);
+                    if empty {
+                        debug!("{label} waiting for an item: queue empty");
+                        if let Some(events) = &mut events {
+                            events.record(EventKind::BlockStart, Some(0));
+                        }
+                    }
+                    let call = Instant::now();
+                    let item = q.recv();
+                    in_queue += call.elapsed();
+                    if let Some(events) = &mut events {
+                        let len = q.len();
+                        if empty {
+                            events.record(EventKind::BlockEnd, len);
+                        }
+                        if item.is_some() {
+                            events.record(EventKind::Dequeue, len);
+                        }
+                    }
+                    let Some(item) = item else {
+                        break;
+                    };
+                    if log_items && consumed.stats.items.is_multiple_of(log_every) {
+                        trace!("{label} dequeued item {:?}", item.tag);
+                    }
+                    let warm = warmup.take();
+                    consumed.warmup += usize::from(warm);
+                    if let Some(sent) = item.sent.filter(|_| !warm) {
+                        consumed.latency.record(sent.elapsed().as_nanos() as u64);
+                    }
+                    if !item.payload.is_valid(kind, bytes, item.tag) {
+                        consumed.corrupted += 1;
+                    }
+                    if checksum {
+                        consumed.checksum.add(item.tag);
+                    }
+                    if verify_order {
+                        consumed.log.push(item.tag);
+                    }
+                    drop(item); // free the boxed item
+                    consumed.stats.items += 1;
+                    progress.tick(first_consumer_thread + id);
+                }
+                debug!("{label} finished after {} items", consumed.stats.items);
+                progress.finish(first_consumer_thread + id);
+                consumed.stats.queue_secs = in_queue.as_secs_f64();
+                consumed.stats.active_secs = started.elapsed().as_secs_f64();
+                consumed
+            })
+        })
+        .collect();
+
+    // A thread that panicked is reported with the items its counter saw
+    let mut panicked = Vec::new();
+    let mut dead = |role, id, stage, died: DeadThread| {
+        let items = died.items;
+        debug!("{} died: {}", died.thread, died.message);
+        panicked.push(died);
+        ThreadStats {
+            role,
+            id,
+            stage,
+            items,
+            queue_secs: 0.0,
+            active_secs: 0.0,
+            cpu: None,
+        }
+    };
+
+    // Wait for all producers
+    let (producer_stats, sent_sums): (Vec<_>, Vec<_>) = producers
+        .into_iter()
+        .enumerate()
+        .map(|(id, p)| {
+            join(p, &progress, id).unwrap_or_else(|died| {
+                let stats = dead(Role::Producer, id, 0, died);
+                (stats, Checksum::default())
+            })
+        })
+        .unzip();
+
+    // Close each queue once everything feeding it has finished, so the
+    // shutdown cascades down the pipeline to the consumers
+    debug!("producers joined, closing queue 1");
+    first.close();
+    let mut workers = Vec::new();
+    for (index, stage) in stages.into_iter().enumerate() {
+        let first_thread = first_worker + config.stage_workers[..index].iter().sum::<usize>();
+        workers.extend(stage.into_iter().enumerate().map(|(id, w)| {
+            join(w, &progress, first_thread + id)
+                .unwrap_or_else(|died| dead(Role::Worker, id, index + 1, died))
+        }));
+        debug!("stage {} joined, closing queue {}", index + 1, index + 2);
+        queues[index + 1].close();
+    }
+
+    // Wait for all consumers
+    let results: Vec<Consumed> = consumers
+        .into_iter()
+        .enumerate()
+        .map(|(id, c)| {
+            join(c, &progress, first_consumer_thread + id).unwrap_or_else(|died| Consumed {
+                stats: dead(Role::Consumer, id, last_stage, died),
+                corrupted: 0,
+                checksum: Checksum::default(),
+                log: Vec::new(),
+                latency: Histogram::new(),
+                warmup: 0,
+            })
+        })
+        .collect();
+
+    // Every thread is joined, so nothing can stall from here on
+    if let Some(watchdog) = watchdog {
+        watchdog.disarm();
+    }
+    if let Some(reporter) = reporter {
+        reporter.stop();
+    }
+
+    // A warmup that outlasted the trial leaves nothing measured
+    let end = Instant::now();
+    let measured_from = warmup.ended().unwrap_or(end).min(end);
+    let elapsed = end - measured_from;
+
+    // Stop the sampler once every item is through
+    sampling.store(false, Ordering::Relaxed);
+    let (occupancy, memory, consumed) =
+        sampler.map_or_else(Default::default, |s| s.join().unwrap());
+
+    // Every recorder went with its thread, so the writer gets the last batches
+    if let Some(events) = events.and_then(Arc::into_inner)
+        && let Err(err) = events.finish()
+    {
+        let path = config
+            .trace_file
+            .as_ref()
+            .expect("events are only logged to a file");
+        eprintln!("warning: could not write {}: {err}", path.display());
+    }
+
+    let threads: Vec<ThreadStats> = producer_stats
+        .into_iter()
+        .chain(workers)
+        .chain(results.iter().map(|r| r.stats))
+        .collect();
+    let producer_counts = threads::counts(&threads, Role::Producer);
+    let violations = if verify_order {
+        let logs: Vec<_> = results.iter().map(|r| r.log.clone()).collect();
+        verify::check(&producer_counts, &logs)
+    } else {
+        Vec::new()
+    };
+    let checksums = checksum.then(|| {
+        let (mut sent, mut received) = (Checksum::default(), Checksum::default());
+        sent_sums.iter().for_each(|c| sent.merge(c));
+        results.iter().for_each(|r| received.merge(&r.checksum));
+        (sent, received)
+    });
+    let latency = latency.then(|| {
+        let mut merged = Histogram::new();
+        for result in &results {
+            merged.merge(&result.latency);
+        }
+        merged
+    });
+
+    Outcome {
+        corrupted: results.iter().map(|r| r.corrupted).sum(),
+        interrupted: match deadline {
+            Some(_) => stop.load(Ordering::Relaxed),
+            None => producer_counts != shares,
+        },
+        produced: threads::total(&threads, Role::Producer),
+        consumed: threads::total(&threads, Role::Consumer),
+        producer_counts,
+        consumer_counts: threads::counts(&threads, Role::Consumer),
+        threads,
+        queue_empty: queues.iter().all(|q| q.len().is_none_or(|len| len == 0)),
+        elapsed,
+        warmup_items: results.iter().map(|r| r.warmup).sum(),
+        warmup_elapsed: measured_from - start,
+        checksums,
+        violations,
+        latency,
+        occupancy,
+        memory,
+        consumed_samples: consumed,
+        panicked,
+    }
+}
+
+/// Closes every queue of a run if the thread holding it panics, so the
+/// threads blocked on them wake up instead of waiting for it forever.
+struct CloseOnPanic<C: Chan<Box<Item>>> {
+    queues: Vec<Arc<C>>,
+    aborted: Arc<AtomicBool>,
+}
+
+impl<C: Chan<Box<Item>>> CloseOnPanic<C> {
+    fn new(queues: &[Arc<C>], aborted: &Arc<AtomicBool>) -> Self {
+        Self {
+            queues: queues.to_vec(),
+            aborted: Arc::clone(aborted),
+        }
+    }
+}
+
+impl<C: Chan<Box<Item>>> Drop for CloseOnPanic<C> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.aborted.store(true, Ordering::Relaxed);
+            for queue in &self.queues {
+                queue.close();
+            }
+        }
+    }
+}
+
+/// Joins thread `index` of `progress`.
+///
+/// # Returns
+///
+/// What the thread returned, or what it had done if it panicked.
+fn join<T>(handle: JoinHandle<T>, progress: &Progress, index: usize) -> Result<T, DeadThread> {
+    handle.join().map_err(|panic| DeadThread {
+        thread: progress.label(index).to_string(),
+        items: progress.items(index),
+        message: panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("non-string panic payload")),
+    })
+}
+
+impl Outcome {
+    /// Items consumed after the warmup phase.
+    pub fn measured(&self) -> usize {
+        self.consumed - self.warmup_items
+    }
+
+    /// Whether every produced item was consumed exactly once with an intact
+    /// payload and no ordering violations, and every requested item was
+    /// produced unless the run was interrupted or ran for a fixed duration.
+    pub fn is_complete(&self, config: &Config) -> bool {
+        self.panicked.is_empty()
+            && (self.interrupted || config.duration_ms.is_some() || self.produced == config.items)
+            && self.consumed == self.produced
+            && self.leaking_stage().is_none()
+            && self.corrupted == 0
+            && self
+                .checksums
+                .is_none_or(|(sent, received)| sent == received)
+            && self.violations.is_empty()
+    }
+
+    /// The first pipeline stage whose workers moved a different number of
+    /// items than the producers produced.
+    ///
+    /// # Returns
+    ///
+    /// The stage and the items it moved, or `None` if every stage passed on
+    /// each item exactly once.
+    pub fn leaking_stage(&self) -> Option<(usize, usize)> {
+        let mut moved = std::collections::BTreeMap::new();
+        for t in self.threads.iter().filter(|t| t.role == Role::Worker) {
+            *moved.entry(t.stage).or_insert(0) += t.items;
+        }
+        moved.into_iter().find(|&(_, items)| items != self.produced)
+    }
+}
+
+/// A trial whose outcome was incomplete.
+#[derive(Debug)]
+pub struct TrialFailure {
+    /// Configuration the failed trial used.
+    pub config: Box<Config>,
+    /// Zero-based index of the failed trial, counting warmup trials.
+    pub trial: usize,
+    /// What the failed trial observed.
+    pub outcome: Box<Outcome>,
+}
+
+impl fmt::Display for TrialFailure {
+    /// Names the trial and the first check its outcome failed.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (config, outcome) = (&self.config, &self.outcome);
+        write!(
+            f,
+            "trial {} (queue size {}, {}): ",
+            self.trial + 1,
+            config.queue_size,
+            config.channel()
+        )?;
+        if let Some(dead) = outcome.panicked.first() {
+            write!(
+                f,
+                "{} panicked after {} items: {}",
+                dead.thread, dead.items, dead.message
+            )
+        } else if outcome.corrupted > 0 {
+            write!(
+                f,
+                "{} items arrived with a corrupted or mismatched payload",
+                outcome.corrupted
+            )
+        } else if outcome
+            .checksums
+            .is_some_and(|(sent, received)| sent != received)
+        {
+            write!(f, "checksums of produced and consumed items differ")
+        } else if !outcome.violations.is_empty() {
+            write!(f, "{} ordering violations", outcome.violations.len())
+        } else if let Some((stage, items)) = outcome.leaking_stage() {
+            write!(
+                f,
+                "produced {}, stage {stage} moved {items}",
+                outcome.produced
+            )
+        } else if config.duration_ms.is_some() {
+            write!(
+                f,
+                "produced {}, consumed {}",
+                outcome.produced, outcome.consumed
+            )
+        } else {
+            write!(
+                f,
+                "requested {}, produced {}, consumed {}",
+                config.items, outcome.produced, outcome.consumed
+            )
+        }
+    }
+}
+
+/// Runs `warmup + trials` simulations back to back, each on a fresh queue.
+///
+/// Every trial is joined and checked with [`Outcome::is_complete`] before the
+/// next one starts, and the first incomplete trial stops the sequence. An
+/// interrupted trial is kept, even during warmup, and ends the sequence.
+///
+/// # Arguments
+///
+/// * `config` - Configuration shared by every trial.
+/// * `trials` - Number of trials to keep.
+/// * `warmup` - Number of trials to run first and discard.
+/// * `stop` - Set from another thread to end the current trial early.
+///
+/// # Returns
+///
+/// The outcomes of the kept trials in order; the last one is interrupted if
+/// `stop` was set.
+///
+/// # Errors
+///
+/// Returns the first trial that was incomplete, warmup trials included.
+pub fn run_trials(
+    config: &Config,
+    trials: usize,
+    warmup: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<Vec<Outcome>, TrialFailure> {
+    let mut kept = Vec::with_capacity(trials);
+    for trial in 0..warmup + trials {
+        info!(
+            "trial {} of {}{}: {} producers, {} consumers, queue size {}, {}",
+            trial + 1,
+            warmup + trials,
+            if trial < warmup { " (warmup)" } else { "" },
+            config.producers,
+            config.consumers,
+            config.queue_size,
+            config.channel()
+        );
+        let outcome = run(config, stop);
+        info!(
+            "trial {} done: produced {}, consumed {} in {:.3} ms",
+            trial + 1,
+            outcome.produced,
+            outcome.consumed,
+            outcome.elapsed.as_secs_f64() * 1000.0
+        );
+        if !outcome.is_complete(config) {
+            return Err(TrialFailure {
+                config: Box::new(config.clone()),
+                trial,
+                outcome: Box::new(outcome),
+            });
+        }
+        let interrupted = outcome.interrupted;
+        if trial >= warmup || interrupted {
+            kept.push(outcome);
+        }
+        if interrupted {
+            break;
+        }
+    }
+    Ok(kept)
+}
+
+/// Runs the trials of `config` once for each queue capacity in `sizes`.
+///
+/// The sweep stops after the first point whose trials were interrupted.
+///
+/// # Arguments
+///
+/// * `config` - Configuration shared by every point, apart from its queue size.
+/// * `sizes` - Queue capacities to try, in order.
+/// * `trials` - Number of trials to keep per point.
+/// * `warmup` - Number of trials to run first and discard per point.
+/// * `stop` - Set from another thread to end the sweep early.
+///
+/// # Returns
+///
+/// The configuration and kept outcomes of each point that ran, in order.
+///
+/// # Errors
+///
+/// Returns the first incomplete trial of any point.
+pub fn sweep(
+    config: &Config,
+    sizes: &[usize],
+    trials: usize,
+    warmup: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<Vec<(Config, Vec<Outcome>)>, TrialFailure> {
+    let configs = sizes.iter().map(|&queue_size| Config {
+        queue_size,
+        ..config.clone()
+    });
+    run_points(configs, trials, warmup, stop)
+}
+
+/// Runs the trials of `config` once on each of `backends`, and once on each
+/// of `queue_impls` after the fifo backend.
+///
+/// Like [`sweep`], but varying the channel implementation instead of the
+/// queue size. The fifo backend runs first with direct calls, so the
+/// `queue_impls` points show what going through
+/// [`DynQueue`](fifo_bounded_buffer::DynQueue) costs.
+///
+/// # Errors
+///
+/// Returns the first incomplete trial of any backend.
+pub fn compare(
+    config: &Config,
+    backends: &[Backend],
+    queue_impls: &[QueueImpl],
+    trials: usize,
+    warmup: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<Vec<(Config, Vec<Outcome>)>, TrialFailure> {
+    let configs = backends.iter().flat_map(|&backend| {
+        let impls = if backend == Backend::Fifo {
+            queue_impls
+        } else {
+            &[]
+        };
+        let direct = std::iter::once(None);
+        direct
+            .chain(impls.iter().copied().map(Some))
+            .map(move |queue_impl| Config {
+                backend,
+                queue_impl,
+                ..config.clone()
+            })
+    });
+    run_points(configs, trials, warmup, stop)
+}
+
+/// Runs the trials of every configuration of a run matrix, in order.
+///
+/// Like [`sweep`], but the configurations come already expanded, e.g. by
+/// [`Args::matrix`](crate::args::Args::matrix).
+///
+/// # Errors
+///
+/// Returns the first incomplete trial of any combination.
+pub fn run_matrix(
+    configs: Vec<Config>,
+    trials: usize,
+    warmup: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<Vec<(Config, Vec<Outcome>)>, TrialFailure> {
+    run_points(configs.into_iter(), trials, warmup, stop)
+}
+
+/// Runs the trials of each configuration in turn, stopping after the first
+/// one that was interrupted.
+fn run_points(
+    configs: impl Iterator<Item = Config>,
+    trials: usize,
+    warmup: usize,
+    stop: &Arc<AtomicBool>,
+) -> Result<Vec<(Config, Vec<Outcome>)>, TrialFailure> {
+    let mut points = Vec::new();
+    for config in configs {
+        let outcomes = run_trials(&config, trials, warmup, stop)?;
+        let interrupted = outcomes.last().is_some_and(|o| o.interrupted);
+        points.push((config, outcomes));
+        if interrupted {
+            break;
+        }
+    }
+    Ok(points)
+}
+
+/// Splits `total` items between `producers` threads as evenly as possible.
+///
+/// The first `total % producers` producers get one extra item, so the shares
+/// always add up to `total`.
+///
+/// # Arguments
+///
+/// * `total` - Number of items to produce overall.
+/// * `producers` - Number of producer threads; must be non-zero.
+///
+/// # Returns
+///
+/// The number of items each producer should enqueue, indexed by producer.
+pub fn split_items(total: usize, producers: usize) -> Vec<usize> {
+    let base = total / producers;
+    let extra = total % producers;
+    (0..producers)
+        .map(|i| base + usize::from(i < extra))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(producers: usize, consumers: usize, items: usize, queue_size: usize) -> Config {
+        Config {
+            producers,
+            consumers,
+            items,
+            queue_size,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_run_small() {
+        let outcome = run(&config(3, 2, 10, 4), &Arc::default());
+        assert_eq!(outcome.produced, 10);
+        assert_eq!(outcome.consumed, 10);
+        assert!(outcome.queue_empty);
+    }
+
+    #[test]
+    fn test_run_thread_stats() {
+        let outcome = run(&config(3, 2, 10, 4), &Arc::default());
+        let roles: Vec<_> = outcome.threads.iter().map(|t| (t.role, t.id)).collect();
+        assert_eq!(
+            roles,
+            [
+                (Role::Producer, 0),
+                (Role::Producer, 1),
+                (Role::Producer, 2),
+                (Role::Consumer, 0),
+                (Role::Consumer, 1),
+            ]
+        );
+        assert_eq!(outcome.producer_counts, [4, 3, 3]);
+        for thread in &outcome.threads {
+            assert!(thread.queue_secs <= thread.active_secs);
+        }
+    }
+
+    #[test]
+    fn test_run_pinned() {
+        let config = Config {
+            pin: Some(PinStrategy::Spread),
+            ..config(2, 2, 100, 4)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.is_complete(&config));
+        let pinned = outcome.threads.iter().filter(|t| t.cpu.is_some()).count();
+        assert_eq!(pinned, if affinity::SUPPORTED { 4 } else { 0 });
+    }
+
+    #[test]
+    fn test_run_verify_order() {
+        let outcome = run(
+            &Config {
+                verify_order: true,
+                ..config(4, 3, 2_000, 4)
+            },
+            &Arc::default(),
+        );
+        assert_eq!(outcome.consumed, 2_000);
+        assert!(outcome.violations.is_empty(), "{:?}", outcome.violations);
+    }
+
+    #[test]
+    fn test_run_latency() {
+        let outcome = run(
+            &Config {
+                latency: true,
+                ..config(2, 2, 500, 4)
+            },
+            &Arc::default(),
+        );
+        let latency = outcome.latency.unwrap();
+        assert_eq!(latency.count(), 500);
+        let p = latency.percentiles();
+        assert!(p.p50_ns <= p.p99_ns && p.p99_ns <= p.max_ns);
+
+        assert!(run(&config(1, 1, 10, 2), &Arc::default()).latency.is_none());
+    }
+
+    #[test]
+    fn test_run_payload_kinds() {
+        for payload in [PayloadKind::Int, PayloadKind::Bytes, PayloadKind::String] {
+            let config = Config {
+                payload,
+                payload_bytes: 256,
+                ..config(2, 2, 400, 4)
+            };
+            let outcome = run(&config, &Arc::default());
+            assert_eq!(outcome.consumed, 400);
+            assert_eq!(outcome.corrupted, 0);
+            assert!(outcome.is_complete(&config));
+        }
+    }
+
+    #[test]
+    fn test_run_sampled() {
+        assert!(
+            run(&config(1, 1, 10, 2), &Arc::default())
+                .occupancy
+                .is_empty()
+        );
+
+        // A slow consumer keeps the queue mostly full
+        let config = Config {
+            consumer_delay: DelayRange::UP_TO_1MS,
+            sample_ms: Some(1),
+            ..config(2, 1, 200, 4)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert_eq!(outcome.consumed, 200);
+        assert!(!outcome.occupancy.is_empty());
+        assert!(outcome.occupancy.iter().all(|s| s.len <= 4));
+        assert!(
+            outcome
+                .occupancy
+                .windows(2)
+                .all(|w| w[0].elapsed <= w[1].elapsed)
+        );
+        // The consumed counter is sampled too, ending with every item
+        assert_eq!(outcome.consumed_samples.last().unwrap().consumed, 200);
+        assert!(
+            outcome
+                .consumed_samples
+                .windows(2)
+                .all(|w| w[0].consumed <= w[1].consumed)
+        );
+    }
+
+    #[test]
+    fn test_run_trials_discards_warmup() {
+        let outcomes = run_trials(&config(2, 2, 30, 3), 3, 1, &Arc::default()).unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes.iter().all(|o| o.consumed == 30));
+    }
+
+    #[test]
+    fn test_run_stopped() {
+        let stop = Arc::new(AtomicBool::new(true));
+        let outcome = run(&config(2, 2, 100, 4), &stop);
+        assert!(outcome.interrupted);
+        assert_eq!((outcome.produced, outcome.consumed), (0, 0));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                stop.store(true, Ordering::Relaxed);
+            })
+        };
+        let config = Config {
+            producer_delay: DelayRange::UP_TO_1MS,
+            consumer_delay: DelayRange::UP_TO_1MS,
+            verify_order: true,
+            ..config(2, 2, 100_000, 4)
+        };
+        let outcomes = run_trials(&config, 3, 0, &stop).unwrap();
+        stopper.join().unwrap();
+
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert!(outcome.interrupted);
+        assert!(outcome.produced < 100_000);
+        assert_eq!(outcome.consumed, outcome.produced);
+        assert!(outcome.is_complete(&config), "{:?}", outcome.violations);
+    }
+
+    #[test]
+    fn test_sweep_two_points() {
+        let points = sweep(&config(2, 2, 50, 99), &[1, 8], 2, 1, &Arc::default()).unwrap();
+        assert_eq!(points.len(), 2);
+        for ((config, outcomes), size) in points.iter().zip([1, 8]) {
+            assert_eq!(config.queue_size, size);
+            assert_eq!(outcomes.len(), 2);
+            assert!(outcomes.iter().all(|o| o.consumed == 50));
+        }
+    }
+
+    #[test]
+    fn test_compare_backends() {
+        let points = compare(
+            &config(2, 2, 200, 3),
+            Backend::available(),
+            &[QueueImpl::Mutex],
+            1,
+            0,
+            &Arc::default(),
+        )
+        .unwrap();
+        let channels: Vec<_> = points.iter().map(|(config, _)| config.channel()).collect();
+        let mut expected: Vec<_> = Backend::available()
+            .iter()
+            .map(|b| b.name().to_string())
+            .collect();
+        expected.insert(1, String::from("fifo/mutex"));
+        assert_eq!(channels, expected);
+        for (_, outcomes) in &points {
+            assert_eq!(outcomes[0].consumed, 200);
+            assert!(outcomes[0].queue_empty);
+        }
+    }
+
+    #[test]
+    fn test_run_queue_impls() {
+        for imp in QueueImpl::available() {
+            let config = Config {
+                queue_impl: Some(imp),
+                verify_order: true,
+                sample_ms: Some(1),
+                ..config(2, 2, 100, 2)
+            };
+            let outcome = run(&config, &Arc::default());
+            assert!(outcome.is_complete(&config), "{}", config.channel());
+            assert!(outcome.queue_empty);
+        }
+    }
+
+    #[test]
+    fn test_run_reports_memory() {
+        let sampled = Config {
+            report_memory: true,
+            payload: PayloadKind::Bytes,
+            payload_bytes: 1024,
+            producer_delay: DelayRange {
+                min_ns: 100_000,
+                max_ns: 100_000,
+            },
+            ..config(1, 1, 200, 8)
+        };
+        let outcome = run(&sampled, &Arc::default());
+        assert!(outcome.is_complete(&sampled));
+        assert!(!outcome.memory.is_empty());
+        assert!(outcome.occupancy.is_empty());
+        let queue_bytes = |s: &MemorySample| s.queue_bytes.unwrap();
+        assert!(
+            outcome
+                .memory
+                .iter()
+                .all(|s| queue_bytes(s) >= size_of::<Queue<Box<Item>>>())
+        );
+        if cfg!(target_os = "linux") {
+            assert!(outcome.memory.iter().all(|s| s.rss_bytes.is_some()));
+        }
+
+        let unsampled = run(&config(1, 1, 20, 2), &Arc::default());
+        assert!(unsampled.memory.is_empty());
+    }
+
+    #[test]
+    fn test_run_excludes_warmup() {
+        let warmed = Config {
+            warmup_phase: Some(WarmupPhase::Items(300)),
+            latency: true,
+            ..config(2, 3, 1000, 4)
+        };
+        let outcome = run(&warmed, &Arc::default());
+        assert!(outcome.is_complete(&warmed));
+        assert_eq!((outcome.consumed, outcome.warmup_items), (1000, 300));
+        assert_eq!(outcome.measured(), 700);
+        assert_eq!(outcome.latency.unwrap().count(), 700);
+
+        let unwarmed = run(&config(2, 3, 1000, 4), &Arc::default());
+        assert_eq!(unwarmed.measured(), 1000);
+        assert_eq!(unwarmed.warmup_elapsed, Duration::ZERO);
+
+        // A warmup longer than the trial leaves nothing measured
+        let slow = Config {
+            warmup_phase: Some(WarmupPhase::Millis(60_000)),
+            ..config(1, 1, 50, 4)
+        };
+        let outcome = run(&slow, &Arc::default());
+        assert_eq!((outcome.warmup_items, outcome.measured()), (50, 0));
+        assert_eq!(outcome.elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_run_survives_panics() {
+        for (role, id) in [(Role::Producer, 1), (Role::Consumer, 0)] {
+            let config = Config {
+                inject_panic: Some(PanicInjection { role, id, after: 5 }),
+                stage_workers: vec![1],
+                ..config(2, 2, 1000, 2)
+            };
+            let outcome = run(&config, &Arc::default());
+            assert!(!outcome.is_complete(&config));
+            let dead = &outcome.panicked;
+            assert_eq!(dead.len(), 1, "{dead:?}");
+            assert_eq!(dead[0].thread, format!("{} {id}", role_name(role)));
+            assert_eq!(
+                (dead[0].items, dead[0].message.as_str()),
+                (5, "injected panic")
+            );
+
+            let failure = TrialFailure {
+                config: Box::new(config),
+                trial: 0,
+                outcome: Box::new(outcome),
+            };
+            assert!(
+                failure
+                    .to_string()
+                    .ends_with("panicked after 5 items: injected panic"),
+                "{failure}"
+            );
+        }
+    }
+
+    fn role_name(role: Role) -> &'static str {
+        match role {
+            Role::Producer => "producer",
+            Role::Worker => "worker",
+            Role::Consumer => "consumer",
+        }
+    }
+
+    #[test]
+    fn test_std_mpsc_is_not_sampled() {
+        let config = Config {
+            backend: Backend::StdMpsc,
+            sample_ms: Some(1),
+            verify_order: true,
+            ..config(1, 1, 100, 2)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.is_complete(&config));
+        assert!(outcome.occupancy.is_empty());
+    }
+
+    #[test]
+    fn test_seeded_delays_repeat() {
+        let schedule = |seed, index| {
+            let mut rng = StdRng::seed_from_u64(thread_seed(seed, index));
+            (0..100)
+                .map(|_| DelayRange::UP_TO_1MS.sample(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(schedule(42, 0), schedule(42, 0));
+        assert_eq!(schedule(42, 3), schedule(42, 3));
+        assert_ne!(schedule(42, 0), schedule(42, 1));
+        assert_ne!(schedule(42, 0), schedule(43, 0));
+        assert!(schedule(7, 0).iter().all(|d| *d < Duration::from_millis(1)));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let fixed = DelayRange {
+            min_ns: 500,
+            max_ns: 500,
+        };
+        assert_eq!(fixed.sample(&mut rng), Duration::from_nanos(500));
+        assert_eq!(DelayRange::NONE.sample(&mut rng), Duration::ZERO);
+        assert!(DelayRange::NONE.is_none() && !fixed.is_none());
+
+        // A single producer and consumer see the same items in the same order
+        let config = Config {
+            producer_delay: DelayRange::UP_TO_1MS,
+            consumer_delay: DelayRange::UP_TO_1MS,
+            seed: 42,
+            verify_order: true,
+            ..config(1, 1, 50, 2)
+        };
+        let first = run(&config, &Arc::default());
+        let second = run(&config, &Arc::default());
+        assert_eq!(first.producer_counts, second.producer_counts);
+        assert_eq!(first.consumer_counts, second.consumer_counts);
+        assert!(first.is_complete(&config) && second.is_complete(&config));
+    }
+
+    #[test]
+    fn test_run_checksum() {
+        let config = Config {
+            checksum: true,
+            ..config(3, 2, 1_000, 4)
+        };
+        let outcome = run(&config, &Arc::default());
+        let (sent, received) = outcome.checksums.unwrap();
+        assert_eq!(sent, received);
+        assert_eq!(sent.count, 1_000);
+        assert!(outcome.is_complete(&config));
+
+        let mut tampered = outcome.clone();
+        tampered.checksums = Some((sent, Checksum::default()));
+        assert!(!tampered.is_complete(&config));
+    }
+
+    #[test]
+    fn test_run_trace_file() {
+        let path = std::env::temp_dir().join(format!("fifo-sim-{}.csv", std::process::id()));
+        let config = Config {
+            trace_file: Some(path.clone()),
+            ..config(2, 2, 300, 2)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.is_complete(&config));
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let events: Vec<_> = text
+            .lines()
+            .skip(crate::events::HEADER.lines().count())
+            .map(|row| crate::events::Event::parse(row).unwrap())
+            .collect();
+        let count = |kind| events.iter().filter(|e| e.kind == kind).count();
+        assert_eq!(count(EventKind::Enqueue), 300);
+        assert_eq!(count(EventKind::Dequeue), 300);
+        assert_eq!(count(EventKind::BlockStart), count(EventKind::BlockEnd));
+        assert!(
+            events
+                .iter()
+                .all(|e| e.queue_len.is_some_and(|len| len <= 2))
+        );
+    }
+
+    #[test]
+    fn test_run_pipeline() {
+        let config = Config {
+            stage_workers: vec![2, 1],
+            work_ns: 1_000,
+            checksum: true,
+            ..config(2, 3, 500, 2)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.is_complete(&config), "{outcome:?}");
+        assert!(outcome.queue_empty);
+        assert_eq!(outcome.checksums.unwrap().0.count, 500);
+
+        let stages: Vec<_> = outcome.threads.iter().map(|t| (t.role, t.stage)).collect();
+        assert_eq!(
+            stages,
+            [
+                (Role::Producer, 0),
+                (Role::Producer, 0),
+                (Role::Worker, 1),
+                (Role::Worker, 1),
+                (Role::Worker, 2),
+                (Role::Consumer, 3),
+                (Role::Consumer, 3),
+                (Role::Consumer, 3),
+            ]
+        );
+        assert_eq!(outcome.leaking_stage(), None);
+
+        let mut leaky = outcome.clone();
+        leaky.threads[2].items -= 1;
+        assert_eq!(leaky.leaking_stage(), Some((1, 499)));
+        assert!(!leaky.is_complete(&config));
+    }
+
+    #[test]
+    fn test_run_for_duration() {
+        let config = Config {
+            duration_ms: Some(200),
+            verify_order: true,
+            ..config(2, 2, 0, 8)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.elapsed >= Duration::from_millis(200));
+        assert!(
+            outcome.elapsed < Duration::from_secs(2),
+            "{:?}",
+            outcome.elapsed
+        );
+        assert!(!outcome.interrupted);
+        assert!(outcome.produced > 0);
+        assert_eq!(outcome.consumed, outcome.produced);
+        assert_eq!(
+            outcome.producer_counts.iter().sum::<usize>(),
+            outcome.produced
+        );
+        assert!(outcome.is_complete(&config), "{:?}", outcome.violations);
+    }
+
+    #[test]
+    fn test_burst_pauses() {
+        let burst = Burst {
+            size: 3,
+            pause_ms: 5,
+        };
+        let pauses: Vec<_> = (0..7).map(|i| burst.pause_after(i).is_some()).collect();
+        assert_eq!(pauses, [false, false, true, false, false, true, false]);
+        assert_eq!(burst.pause_after(2), Some(Duration::from_millis(5)));
+
+        let every = Burst {
+            size: 1,
+            pause_ms: 0,
+        };
+        assert!((0..5).all(|i| every.pause_after(i).is_some()));
+    }
+
+    #[test]
+    fn test_bursts_fill_queue() {
+        let config = Config {
+            burst: Some(Burst {
+                size: 200,
+                pause_ms: 2,
+            }),
+            sample_ms: Some(1),
+            ..config(1, 1, 4_000, 4)
+        };
+        let outcome = run(&config, &Arc::default());
+        assert!(outcome.is_complete(&config));
+        let max = outcome.occupancy.iter().map(|s| s.len).max();
+        assert_eq!(max, Some(4), "{} samples", outcome.occupancy.len());
+    }
+
+    #[test]
+    fn test_run_sixteen_by_sixteen() {
+        let outcome = run(&config(16, 16, 16_000, 8), &Arc::default());
+        assert_eq!(outcome.produced, 16_000);
+        assert_eq!(outcome.consumed, 16_000);
+        assert!(outcome.queue_empty);
+    }
+
+    #[test]
+    fn test_split_items_even() {
+        assert_eq!(split_items(10, 1), vec![10]);
+        assert_eq!(split_items(12, 4), vec![3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn test_per_producer() {
+        assert_eq!(
+            config(4, 1, 1000, 2).per_producer().unwrap(),
+            "250 per producer"
+        );
+        assert_eq!(
+            config(3, 1, 10, 2).per_producer().unwrap(),
+            "3-4 per producer"
+        );
+        let timed = Config {
+            duration_ms: Some(10),
+            ..config(3, 1, 10, 2)
+        };
+        assert_eq!(timed.items_per_producer(), None);
+        assert_eq!(timed.per_producer(), None);
+    }
+
+    #[test]
+    fn test_split_items_remainder() {
+        assert_eq!(split_items(10, 3), vec![4, 3, 3]);
+        assert_eq!(split_items(7, 4), vec![2, 2, 2, 1]);
+        assert_eq!(split_items(3, 5), vec![1, 1, 1, 0, 0]);
+
+        for total in 0..50 {
+            for producers in 1..9 {
+                let shares = split_items(total, producers);
+                assert_eq!(shares.len(), producers);
+                assert_eq!(shares.iter().sum::<usize>(), total, "{total}/{producers}");
+                let (min, max) = (shares.iter().min(), shares.iter().max());
+                assert!(max.unwrap() - min.unwrap() <= 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Summary statistics over repeated measurements.
+
+use serde::{Deserialize, Serialize};
+
+/// Spread of a set of measurements.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    /// Smallest measurement.
+    pub min: f64,
+    /// Middle measurement, or the mean of the two middle ones.
+    pub median: f64,
+    /// Arithmetic mean.
+    pub mean: f64,
+    /// Sample standard deviation, zero for fewer than two measurements.
+    pub stddev: f64,
+}
+
+impl Summary {
+    /// Summarizes `values`.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - Measurements in any order; NaN values are not supported.
+    ///
+    /// # Returns
+    ///
+    /// The summary, with every field zero if `values` is empty.
+    pub fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self {
+                min: 0.0,
+                median: 0.0,
+                mean: 0.0,
+                stddev: 0.0,
+            };
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let median = if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            let squares: f64 = sorted.iter().map(|v| (v - mean).powi(2)).sum();
+            (squares / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            min: sorted[0],
+            median,
+            mean,
+            stddev,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_odd() {
+        let s = Summary::of(&[4.0, 1.0, 7.0]);
+        assert_eq!(s.min, 1.0);
+        assert_eq!(s.median, 4.0);
+        assert_eq!(s.mean, 4.0);
+        // ((-3)^2 + 0 + 3^2) / 2 = 9
+        assert_eq!(s.stddev, 3.0);
+    }
+
+    #[test]
+    fn test_summary_even_and_single() {
+        let s = Summary::of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(s.median, 4.5);
+        assert_eq!(s.mean, 5.0);
+        // squared deviations sum to 32, over n - 1 = 7
+        assert!((s.stddev - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+
+        let s = Summary::of(&[3.5]);
+        assert_eq!((s.min, s.median, s.mean, s.stddev), (3.5, 3.5, 3.5, 0.0));
+        assert_eq!(Summary::of(&[]).mean, 0.0);
+    }
+}
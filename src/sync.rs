@@ -0,0 +1,26 @@
+//! Synchronization primitives used by the queue's monitor and its waiters.
+//!
+//! These are plain re-exports of `std::sync` in normal builds. Under
+//! `--cfg loom` they are replaced by `loom::sync`, so the wait loops and
+//! shutdown notifications can be model checked:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+//!
+//! `Arc` is not abstracted: methods take `self: &Arc<Self>`, which requires
+//! the standard `Arc`, and its reference count is not part of the monitor.
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::{Condvar, Mutex, MutexGuard};
+#[cfg(loom)]
+pub(crate) use loom::{hint::spin_loop, thread::yield_now};
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Condvar, Mutex, MutexGuard};
+#[cfg(not(loom))]
+pub(crate) use std::{hint::spin_loop, thread::yield_now};
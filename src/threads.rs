@@ -0,0 +1,254 @@
+//! What each producer and consumer thread did during a run.
+
+use serde::{Deserialize, Serialize};
+
+/// Which side of the queue a thread worked on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Producer,
+    /// Moves items from one queue of a pipeline to the next.
+    Worker,
+    Consumer,
+}
+
+/// Counters one thread kept for itself and handed back when joined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThreadStats {
+    /// Side of the queue the thread worked on.
+    pub role: Role,
+    /// Index of the thread among those of its stage.
+    pub id: usize,
+    /// Position in the pipeline: 0 for producers, 1 for workers reading the
+    /// first queue and so on, with consumers last.
+    pub stage: usize,
+    /// Items the thread enqueued or dequeued.
+    pub items: usize,
+    /// Seconds spent inside enqueue or dequeue calls, including any time
+    /// blocked waiting for space or items.
+    pub queue_secs: f64,
+    /// Seconds from the thread starting its loop to leaving it.
+    pub active_secs: f64,
+    /// CPU the thread was pinned to, with `--pin-threads`.
+    pub cpu: Option<usize>,
+}
+
+impl Role {
+    /// Lowercase name of the role for tables and messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Producer => "producer",
+            Role::Worker => "worker",
+            Role::Consumer => "consumer",
+        }
+    }
+}
+
+impl ThreadStats {
+    /// Name of the thread in tables and logs, e.g. `producer 2` or `worker 1.0`.
+    pub fn label(&self) -> String {
+        match self.role {
+            Role::Worker => format!("worker {}.{}", self.stage, self.id),
+            role => format!("{} {}", role.name(), self.id),
+        }
+    }
+
+    /// Items handled per second the thread was active, zero if it never ran.
+    pub fn items_per_sec(&self) -> f64 {
+        if self.active_secs > 0.0 {
+            self.items as f64 / self.active_secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Percentage of the thread's active time spent inside queue calls.
+    pub fn queue_pct(&self) -> f64 {
+        if self.active_secs > 0.0 {
+            self.queue_secs * 100.0 / self.active_secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A thread made to panic with `--inject-panic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanicInjection {
+    /// Side of the queue the thread works on.
+    pub role: Role,
+    /// Index of the thread among those with `role`.
+    pub id: usize,
+    /// Items the thread handles before it panics.
+    pub after: usize,
+}
+
+impl PanicInjection {
+    /// Whether the thread `id` with `role` must panic now, having handled
+    /// `items` items.
+    pub fn hits(&self, role: Role, id: usize, items: usize) -> bool {
+        self.role == role && self.id == id && self.after == items
+    }
+}
+
+/// A thread whose join returned a panic instead of its stats.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadThread {
+    /// Name of the thread, e.g. `consumer 1`.
+    pub thread: String,
+    /// Items the thread had handled before it died.
+    pub items: usize,
+    /// The panic message, if it was a string.
+    pub message: String,
+}
+
+/// Items handled by each thread with `role`, in the order the threads appear.
+pub fn counts(threads: &[ThreadStats], role: Role) -> Vec<usize> {
+    threads
+        .iter()
+        .filter(|t| t.role == role)
+        .map(|t| t.items)
+        .collect()
+}
+
+/// Items handled by all threads with `role`.
+pub fn total(threads: &[ThreadStats], role: Role) -> usize {
+    counts(threads, role).iter().sum()
+}
+
+/// The thread with `role` that handled the fewest items per second.
+///
+/// # Returns
+///
+/// The slowest thread, or `None` if no thread has `role`.
+pub fn slowest(threads: &[ThreadStats], role: Role) -> Option<&ThreadStats> {
+    threads
+        .iter()
+        .filter(|t| t.role == role)
+        .min_by(|a, b| a.items_per_sec().total_cmp(&b.items_per_sec()))
+}
+
+/// Formats one row per thread under a header, so a producer or consumer
+/// that lagged stands out.
+///
+/// # Returns
+///
+/// The header followed by one line per thread, without trailing newlines.
+pub fn table(threads: &[ThreadStats]) -> Vec<String> {
+    // Only pinned runs get a CPU column
+    let pinned = threads.iter().any(|t| t.cpu.is_some());
+    let mut header = format!(
+        "{:<14}  {:>10}  {:>11}  {:>9}  {:>12}",
+        "thread", "items", "active ms", "in queue", "items/sec"
+    );
+    if pinned {
+        header.push_str(&format!("  {:>4}", "cpu"));
+    }
+    let rows = threads.iter().map(|t| {
+        let mut row = format!(
+            "{:<14}  {:>10}  {:>11.3}  {:>8.1}%  {:>12.0}",
+            t.label(),
+            t.items,
+            t.active_secs * 1000.0,
+            t.queue_pct(),
+            t.items_per_sec()
+        );
+        if pinned {
+            let cpu = t
+                .cpu
+                .map_or_else(|| String::from("-"), |cpu| cpu.to_string());
+            row.push_str(&format!("  {cpu:>4}"));
+        }
+        row
+    });
+    std::iter::once(header).chain(rows).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(role: Role, id: usize, items: usize, active_secs: f64) -> ThreadStats {
+        ThreadStats {
+            role,
+            id,
+            stage: if role == Role::Producer { 0 } else { 1 },
+            items,
+            queue_secs: active_secs / 4.0,
+            active_secs,
+            cpu: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let threads = [
+            stats(Role::Producer, 0, 100, 1.0),
+            stats(Role::Producer, 1, 50, 1.0),
+            stats(Role::Consumer, 0, 120, 2.0),
+            stats(Role::Consumer, 1, 30, 0.1),
+        ];
+        assert_eq!(counts(&threads, Role::Producer), vec![100, 50]);
+        assert_eq!(counts(&threads, Role::Consumer), vec![120, 30]);
+        assert_eq!(total(&threads, Role::Producer), 150);
+        assert_eq!(total(&threads, Role::Consumer), 150);
+
+        assert_eq!(slowest(&threads, Role::Producer).unwrap().id, 1);
+        // 60 items/sec against 300 items/sec
+        assert_eq!(slowest(&threads, Role::Consumer).unwrap().id, 0);
+        assert_eq!(slowest(&threads[..2], Role::Consumer), None);
+    }
+
+    #[test]
+    fn test_table() {
+        let lines = table(&[stats(Role::Consumer, 3, 100, 2.0)]);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("thread"));
+        assert_eq!(
+            lines[1],
+            "consumer 3             100     2000.000      25.0%            50"
+        );
+
+        let pinned = ThreadStats {
+            cpu: Some(7),
+            ..stats(Role::Producer, 0, 100, 2.0)
+        };
+        let lines = table(&[pinned, stats(Role::Consumer, 0, 100, 2.0)]);
+        assert!(lines[0].ends_with("   cpu"));
+        assert!(lines[1].ends_with("     7"));
+        assert!(lines[2].ends_with("     -"));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(stats(Role::Producer, 2, 0, 0.0).label(), "producer 2");
+        let worker = ThreadStats {
+            stage: 3,
+            ..stats(Role::Worker, 1, 0, 0.0)
+        };
+        assert_eq!(worker.label(), "worker 3.1");
+    }
+
+    #[test]
+    fn test_panic_injection() {
+        let inject = PanicInjection {
+            role: Role::Consumer,
+            id: 1,
+            after: 3,
+        };
+        assert!(inject.hits(Role::Consumer, 1, 3));
+        assert!(!inject.hits(Role::Consumer, 1, 2));
+        assert!(!inject.hits(Role::Consumer, 0, 3));
+        assert!(!inject.hits(Role::Producer, 1, 3));
+    }
+
+    #[test]
+    fn test_rates() {
+        let thread = stats(Role::Producer, 0, 100, 2.0);
+        assert_eq!(thread.items_per_sec(), 50.0);
+        assert_eq!(thread.queue_pct(), 25.0);
+
+        let idle = stats(Role::Consumer, 0, 0, 0.0);
+        assert_eq!((idle.items_per_sec(), idle.queue_pct()), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,240 @@
+//! Throughput over the course of a run, from the items consumed at every
+//! `--sample-ms` interval.
+//!
+//! The sampler snapshots how many items the consumers have taken so far;
+//! the difference between two snapshots over the time between them is the
+//! instantaneous throughput of that interval.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::stats;
+
+/// Most characters the human output spends on a sparkline.
+pub const SPARKLINE_WIDTH: usize = 60;
+
+/// Characters of a sparkline, from the lowest rate to the highest.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Items consumed so far at one point in a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Time since the run started.
+    pub elapsed: Duration,
+    /// Items taken by the consumers since the run started.
+    pub consumed: usize,
+}
+
+/// Throughput during one interval between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    /// Time from the start of the run to the end of the interval, in seconds.
+    pub elapsed_secs: f64,
+    /// Items consumed during the interval.
+    pub items: usize,
+    /// Items consumed per second of the interval.
+    pub items_per_sec: f64,
+}
+
+/// Throughput of every interval of a run and how steady it was.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Series {
+    /// One point per sampling interval, in order.
+    pub points: Vec<Point>,
+    /// Standard deviation of the interval rates over their mean, or `None`
+    /// with fewer than two intervals or nothing consumed.
+    pub cv: Option<f64>,
+}
+
+impl Series {
+    /// Computes the series between consecutive `snapshots`.
+    ///
+    /// # Returns
+    ///
+    /// The series, or `None` if there are fewer than two snapshots.
+    pub fn of(snapshots: &[Snapshot]) -> Option<Self> {
+        let points = deltas(snapshots);
+        if points.is_empty() {
+            return None;
+        }
+        let rates: Vec<f64> = points.iter().map(|p| p.items_per_sec).collect();
+        let summary = stats::Summary::of(&rates);
+        let cv = (rates.len() > 1 && summary.mean > 0.0).then(|| summary.stddev / summary.mean);
+        Some(Self { points, cv })
+    }
+
+    /// Renders the rates as a line of bars at most `width` characters long,
+    /// averaging neighbouring intervals when there are more than that.
+    pub fn sparkline(&self, width: usize) -> String {
+        let rates: Vec<f64> = self.points.iter().map(|p| p.items_per_sec).collect();
+        sparkline(&rates, width)
+    }
+}
+
+/// Throughput of each interval between consecutive snapshots.
+///
+/// Intervals of no length are skipped, and a counter that went backwards
+/// counts as nothing consumed.
+///
+/// # Arguments
+///
+/// * `snapshots` - Counter readings in the order they were taken.
+///
+/// # Returns
+///
+/// One point per interval, one fewer than there are snapshots at most.
+pub fn deltas(snapshots: &[Snapshot]) -> Vec<Point> {
+    snapshots
+        .windows(2)
+        .filter_map(|pair| {
+            let secs = pair[1]
+                .elapsed
+                .saturating_sub(pair[0].elapsed)
+                .as_secs_f64();
+            let items = pair[1].consumed.saturating_sub(pair[0].consumed);
+            (secs > 0.0).then(|| Point {
+                elapsed_secs: pair[1].elapsed.as_secs_f64(),
+                items,
+                items_per_sec: items as f64 / secs,
+            })
+        })
+        .collect()
+}
+
+/// Renders `rates` as a line of bars scaled to the highest one.
+///
+/// # Arguments
+///
+/// * `rates` - Values to draw, in order; must not be negative.
+/// * `width` - Most characters to use; longer series are split into this
+///   many buckets and each drawn as its average.
+pub fn sparkline(rates: &[f64], width: usize) -> String {
+    let buckets = rates.len().min(width);
+    let averages: Vec<f64> = (0..buckets)
+        .map(|b| {
+            let bucket = &rates[b * rates.len() / buckets..(b + 1) * rates.len() / buckets];
+            bucket.iter().sum::<f64>() / bucket.len() as f64
+        })
+        .collect();
+    let max = averages.iter().copied().fold(0.0, f64::max);
+    averages
+        .iter()
+        .map(|&rate| {
+            let level = if max > 0.0 {
+                (rate / max * (BARS.len() - 1) as f64).round() as usize
+            } else {
+                0
+            };
+            BARS[level.min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Writes the series of each trial as CSV with
+/// `trial,elapsed_ms,items,items_per_sec` columns.
+///
+/// # Arguments
+///
+/// * `path` - File to create or truncate.
+/// * `trials` - Counter snapshots of each trial, in order.
+///
+/// # Errors
+///
+/// Returns any error from creating or writing the file.
+pub fn write_trace(path: &Path, trials: &[&[Snapshot]]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "trial,elapsed_ms,items,items_per_sec")?;
+    for (trial, snapshots) in trials.iter().enumerate() {
+        for point in deltas(snapshots) {
+            writeln!(
+                out,
+                "{},{:.3},{},{:.0}",
+                trial + 1,
+                point.elapsed_secs * 1000.0,
+                point.items,
+                point.items_per_sec
+            )?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshots(counts: &[(u64, usize)]) -> Vec<Snapshot> {
+        counts
+            .iter()
+            .map(|&(ms, consumed)| Snapshot {
+                elapsed: Duration::from_millis(ms),
+                consumed,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deltas() {
+        let points = deltas(&snapshots(&[
+            (0, 0),
+            (10, 100),
+            (20, 150),
+            (20, 150),
+            (40, 150),
+        ]));
+        let rates: Vec<_> = points.iter().map(|p| (p.items, p.items_per_sec)).collect();
+        assert_eq!(rates, [(100, 10_000.0), (50, 5_000.0), (0, 0.0)]);
+        assert_eq!(points[2].elapsed_secs, 0.04);
+
+        assert!(deltas(&snapshots(&[(0, 5)])).is_empty());
+        assert_eq!(deltas(&snapshots(&[(0, 5), (10, 3)]))[0].items, 0);
+    }
+
+    #[test]
+    fn test_series() {
+        assert_eq!(Series::of(&snapshots(&[(0, 0)])), None);
+
+        let steady = Series::of(&snapshots(&[(0, 0), (10, 50), (20, 100), (30, 150)])).unwrap();
+        assert_eq!(steady.points.len(), 3);
+        assert_eq!(steady.cv, Some(0.0));
+
+        // Fast at first, then collapsing once the queue saturates
+        let collapsing = Series::of(&snapshots(&[(0, 0), (10, 90), (20, 100), (30, 110)])).unwrap();
+        let cv = collapsing.cv.unwrap();
+        assert!((cv - 1.2597).abs() < 1e-4, "{cv}");
+
+        let single = Series::of(&snapshots(&[(0, 0), (10, 50)])).unwrap();
+        assert_eq!(single.cv, None);
+        let idle = Series::of(&snapshots(&[(0, 0), (10, 0), (20, 0)])).unwrap();
+        assert_eq!(idle.cv, None);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(
+            sparkline(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0], 20),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[8.0, 8.0, 0.0, 0.0], 2), "█▁");
+        assert_eq!(sparkline(&[0.0, 0.0], 10), "▁▁");
+        assert_eq!(sparkline(&[], 10), "");
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let path = std::env::temp_dir().join(format!("fifo-throughput-{}.csv", std::process::id()));
+        let first = snapshots(&[(0, 0), (10, 20)]);
+        let second = snapshots(&[(0, 0), (5, 1), (10, 3)]);
+        write_trace(&path, &[&first, &second]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            contents,
+            "trial,elapsed_ms,items,items_per_sec\n1,10.000,20,2000\n2,5.000,1,200\n2,10.000,2,400\n"
+        );
+    }
+}
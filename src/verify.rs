@@ -0,0 +1,193 @@
+//! Checks that consumers saw every produced item exactly once and in order.
+
+use std::fmt;
+
+/// An item as enqueued by the simulation: the producer that made it and its
+/// position in that producer's sequence.
+pub type Tagged = (usize, usize);
+
+/// A way in which the consumed items break FIFO delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A consumer saw a producer's items out of sequence order.
+    OutOfOrder {
+        /// Index of the consumer that saw the items.
+        consumer: usize,
+        /// Position in that consumer's log of the offending item.
+        position: usize,
+        /// Producer the items came from.
+        producer: usize,
+        /// Sequence number the consumer saw earlier from the same producer.
+        previous: usize,
+        /// Sequence number that should have been larger than `previous`.
+        seq: usize,
+    },
+    /// An item was consumed more than once.
+    Duplicate {
+        /// Index of the consumer that saw the second copy.
+        consumer: usize,
+        /// Producer the item came from.
+        producer: usize,
+        /// Sequence number of the item.
+        seq: usize,
+    },
+    /// An item was produced but never consumed.
+    Missing {
+        /// Producer the item came from.
+        producer: usize,
+        /// Sequence number of the item.
+        seq: usize,
+    },
+    /// A consumer saw an item no producer made.
+    Unknown {
+        /// Index of the consumer that saw the item.
+        consumer: usize,
+        /// The unexpected item.
+        item: Tagged,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Violation::OutOfOrder {
+                consumer,
+                position,
+                producer,
+                previous,
+                seq,
+            } => write!(
+                f,
+                "consumer {consumer} took item {seq} of producer {producer} \
+                 at position {position}, after item {previous}"
+            ),
+            Violation::Duplicate {
+                consumer,
+                producer,
+                seq,
+            } => write!(
+                f,
+                "consumer {consumer} took item {seq} of producer {producer}, \
+                 which was already consumed"
+            ),
+            Violation::Missing { producer, seq } => {
+                write!(f, "item {seq} of producer {producer} was never consumed")
+            }
+            Violation::Unknown { consumer, item } => write!(
+                f,
+                "consumer {consumer} took item {} of producer {}, which was never produced",
+                item.1, item.0
+            ),
+        }
+    }
+}
+
+/// Compares what each consumer took against what each producer made.
+///
+/// Within a single consumer's log, the items of any one producer must appear
+/// with strictly increasing sequence numbers, and across all logs every
+/// produced item must appear exactly once.
+///
+/// # Arguments
+///
+/// * `shares` - Number of items each producer enqueued, indexed by producer.
+/// * `logs` - Items in the order each consumer dequeued them, indexed by consumer.
+///
+/// # Returns
+///
+/// Every violation found, empty if delivery was correct.
+pub fn check(shares: &[usize], logs: &[Vec<Tagged>]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut seen: Vec<Vec<bool>> = shares.iter().map(|&n| vec![false; n]).collect();
+
+    for (consumer, log) in logs.iter().enumerate() {
+        let mut last: Vec<Option<usize>> = vec![None; shares.len()];
+        for (position, &(producer, seq)) in log.iter().enumerate() {
+            let Some(slot) = seen.get_mut(producer).and_then(|s| s.get_mut(seq)) else {
+                violations.push(Violation::Unknown {
+                    consumer,
+                    item: (producer, seq),
+                });
+                continue;
+            };
+            if std::mem::replace(slot, true) {
+                violations.push(Violation::Duplicate {
+                    consumer,
+                    producer,
+                    seq,
+                });
+            }
+            if let Some(previous) = last[producer].filter(|&p| p >= seq) {
+                violations.push(Violation::OutOfOrder {
+                    consumer,
+                    position,
+                    producer,
+                    previous,
+                    seq,
+                });
+            }
+            last[producer] = Some(seq);
+        }
+    }
+
+    for (producer, items) in seen.iter().enumerate() {
+        for (seq, _) in items.iter().enumerate().filter(|(_, seen)| !**seen) {
+            violations.push(Violation::Missing { producer, seq });
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleaved_consumers_are_valid() {
+        let logs = vec![vec![(0, 0), (1, 0), (0, 2)], vec![(0, 1), (1, 1), (1, 2)]];
+        assert!(check(&[3, 3], &logs).is_empty());
+    }
+
+    #[test]
+    fn test_reports_out_of_order() {
+        let logs = vec![vec![(0, 1), (0, 0)]];
+        assert_eq!(
+            check(&[2], &logs),
+            vec![Violation::OutOfOrder {
+                consumer: 0,
+                position: 1,
+                producer: 0,
+                previous: 1,
+                seq: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_duplicate_and_unknown() {
+        let logs = vec![vec![(0, 0)], vec![(0, 0), (2, 0)]];
+        let violations = check(&[2], &logs);
+        assert_eq!(
+            violations,
+            vec![
+                Violation::Duplicate {
+                    consumer: 1,
+                    producer: 0,
+                    seq: 0,
+                },
+                Violation::Unknown {
+                    consumer: 1,
+                    item: (2, 0),
+                },
+                Violation::Missing {
+                    producer: 0,
+                    seq: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            violations[2].to_string(),
+            "item 1 of producer 0 was never consumed"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! The warmup phase at the start of a trial, set with `--warmup-items` or
+//! `--warmup-ms`.
+//!
+//! Threads run normally during the warmup, but what consumers take is
+//! counted apart and the trial's timer starts only once it is over. The
+//! switch is a single flag consumers check with a relaxed load, so it costs
+//! nothing once set and never stops a thread.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long the warmup of a trial lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarmupPhase {
+    /// Until this many items have been consumed.
+    Items(usize),
+    /// For this many milliseconds after the trial starts.
+    Millis(u64),
+}
+
+/// The warmup of one running trial, shared by its consumers.
+pub struct Warmup {
+    phase: Option<WarmupPhase>,
+    start: Instant,
+    /// Items taken so far by an item-counted warmup.
+    taken: AtomicUsize,
+    /// Whether the warmup is over.
+    measuring: AtomicBool,
+    /// When the warmup ended.
+    ended: OnceLock<Instant>,
+}
+
+impl Warmup {
+    /// Starts the warmup of a trial that started at `start`; without a
+    /// `phase` it is over at once.
+    pub fn new(phase: Option<WarmupPhase>, start: Instant) -> Self {
+        let warmup = Self {
+            phase,
+            start,
+            taken: AtomicUsize::new(0),
+            measuring: AtomicBool::new(false),
+            ended: OnceLock::new(),
+        };
+        if phase.is_none() {
+            warmup.end(start);
+        }
+        warmup
+    }
+
+    /// Counts an item a consumer just took.
+    ///
+    /// # Returns
+    ///
+    /// Whether the item belongs to the warmup and must be left out of the
+    /// measurements.
+    pub fn take(&self) -> bool {
+        if self.measuring.load(Ordering::Relaxed) {
+            return false;
+        }
+        match self.phase {
+            Some(WarmupPhase::Items(items)) => {
+                // Every value below `items` is handed out exactly once
+                let taken = self.taken.fetch_add(1, Ordering::Relaxed);
+                if taken + 1 >= items {
+                    self.end(Instant::now());
+                }
+                taken < items
+            }
+            Some(WarmupPhase::Millis(ms)) => {
+                let end = self.start + Duration::from_millis(ms);
+                let over = Instant::now() >= end;
+                if over {
+                    self.end(end);
+                }
+                !over
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the warmup at `at`, unless it already ended.
+    fn end(&self, at: Instant) {
+        self.ended.get_or_init(|| at);
+        self.measuring.store(true, Ordering::Relaxed);
+    }
+
+    /// When the warmup ended, or `None` if the trial ended first.
+    pub fn ended(&self) -> Option<Instant> {
+        self.ended.get().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_no_warmup() {
+        let start = Instant::now();
+        let warmup = Warmup::new(None, start);
+        assert!(!warmup.take());
+        assert_eq!(warmup.ended(), Some(start));
+    }
+
+    #[test]
+    fn test_items_are_counted_exactly() {
+        let warmup = Arc::new(Warmup::new(Some(WarmupPhase::Items(1000)), Instant::now()));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let warmup = Arc::clone(&warmup);
+                thread::spawn(move || (0..500).filter(|_| warmup.take()).count())
+            })
+            .collect();
+        let warm: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+        assert_eq!(warm, 1000);
+        assert!(warmup.ended().is_some());
+        assert!(!warmup.take());
+    }
+
+    #[test]
+    fn test_millis_end_on_time() {
+        let start = Instant::now();
+        let warmup = Warmup::new(Some(WarmupPhase::Millis(20)), start);
+        assert!(warmup.take());
+        assert_eq!(warmup.ended(), None);
+        thread::sleep(Duration::from_millis(30));
+        assert!(!warmup.take());
+        assert_eq!(warmup.ended(), Some(start + Duration::from_millis(20)));
+    }
+}
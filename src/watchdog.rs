@@ -0,0 +1,218 @@
+//! Detecting a run that stopped making progress, armed by `--watchdog-secs`.
+//!
+//! Simulation threads bump a counter of their own for every item they move.
+//! The watchdog thread polls the counters, and once none of them has moved
+//! for the whole timeout it hands a [`Stall`] describing every thread and
+//! queue to its callback, which normally prints it and exits with
+//! [`EXIT_STALLED`].
+
+use std::process;
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::EXIT_STALLED;
+use crate::progress::Progress;
+
+/// Longest time between two looks at the counters.
+const MAX_POLL: Duration = Duration::from_secs(1);
+
+/// What one thread had done when the run stalled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadProgress {
+    /// Name of the thread, e.g. `consumer 0`.
+    pub label: String,
+    /// Items the thread had moved.
+    pub items: usize,
+    /// Time since the watchdog last saw the thread's counter move.
+    pub idle: Duration,
+    /// Whether the thread had left its loop.
+    pub finished: bool,
+}
+
+/// A run that moved no item for the whole watchdog timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stall {
+    /// How long no counter moved.
+    pub idle: Duration,
+    /// State of each queue, first to last.
+    pub queues: Vec<String>,
+    /// Progress of every thread.
+    pub threads: Vec<ThreadProgress>,
+}
+
+impl Stall {
+    /// The diagnostic dump, one line per queue and thread.
+    pub fn dump(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "no progress for {:.1}s; the run looks deadlocked",
+            self.idle.as_secs_f64()
+        )];
+        lines.extend(
+            self.queues
+                .iter()
+                .enumerate()
+                .map(|(index, state)| format!("  queue {}: {state}", index + 1)),
+        );
+        lines.extend(self.threads.iter().map(|t| {
+            format!(
+                "  {:<14} {:>10} items, last progress {:.1}s ago{}",
+                t.label,
+                t.items,
+                t.idle.as_secs_f64(),
+                if t.finished { ", finished" } else { "" }
+            )
+        }));
+        lines
+    }
+}
+
+/// Prints `stall` to stderr and exits with [`EXIT_STALLED`].
+pub fn abort(stall: Stall) {
+    for line in stall.dump() {
+        eprintln!("error: {line}");
+    }
+    process::exit(EXIT_STALLED.into());
+}
+
+/// A thread watching a run's [`Progress`] until disarmed.
+pub struct Watchdog {
+    disarm: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Watchdog {
+    /// Starts watching `progress`.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - Counters the run's threads bump.
+    /// * `timeout` - How long no counter may move before the run counts as
+    ///   stalled.
+    /// * `queues` - Describes the state of each queue for the dump.
+    /// * `on_stall` - Called once, from the watchdog thread, if the run
+    ///   stalls; usually [`abort`].
+    pub fn arm(
+        progress: Arc<Progress>,
+        timeout: Duration,
+        queues: impl Fn() -> Vec<String> + Send + 'static,
+        on_stall: impl FnOnce(Stall) + Send + 'static,
+    ) -> Self {
+        let (disarm, disarmed) = mpsc::channel();
+        let poll = (timeout / 4).clamp(Duration::from_millis(1), MAX_POLL);
+        let thread = thread::spawn(move || {
+            let mut counts = progress.counts();
+            let mut moved = vec![Instant::now(); counts.len()];
+            let mut last_move = Instant::now();
+            // Disarming drops the sender, which ends the wait at once
+            while let Err(RecvTimeoutError::Timeout) = disarmed.recv_timeout(poll) {
+                let now = Instant::now();
+                for (index, count) in progress.counts().into_iter().enumerate() {
+                    if count != counts[index] {
+                        counts[index] = count;
+                        moved[index] = now;
+                        last_move = now;
+                    }
+                }
+                if progress.all_finished() {
+                    last_move = now;
+                }
+                if now - last_move >= timeout {
+                    let threads = moved
+                        .iter()
+                        .enumerate()
+                        .map(|(index, &moved)| ThreadProgress {
+                            label: progress.label(index).to_string(),
+                            items: counts[index],
+                            idle: now - moved,
+                            finished: progress.is_finished(index),
+                        })
+                        .collect();
+                    on_stall(Stall {
+                        idle: now - last_move,
+                        queues: queues(),
+                        threads,
+                    });
+                    return;
+                }
+            }
+        });
+        Self { disarm, thread }
+    }
+
+    /// Stops watching and waits for the watchdog thread to end.
+    pub fn disarm(self) {
+        drop(self.disarm);
+        self.thread.join().expect("watchdog panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fifo_bounded_buffer::Queue;
+
+    #[test]
+    fn test_stall_triggers() {
+        // A producer fills the queue and blocks; nobody ever dequeues
+        let queue: Arc<Queue<usize>> = Queue::new(2);
+        let progress = Progress::new(vec![String::from("producer 0")]);
+        let producer = {
+            let (queue, progress) = (Arc::clone(&queue), Arc::clone(&progress));
+            thread::spawn(move || {
+                for i in 0..3 {
+                    queue.enqueue(i);
+                    progress.tick(0);
+                }
+                progress.finish(0);
+            })
+        };
+
+        let (stalled, stall) = mpsc::channel();
+        let describe = {
+            let queue = Arc::clone(&queue);
+            move || {
+                vec![format!(
+                    "len {}, blocked producers {}",
+                    queue.len(),
+                    queue.blocked_producers()
+                )]
+            }
+        };
+        let watchdog = Watchdog::arm(progress, Duration::from_millis(50), describe, move |s| {
+            stalled.send(s).unwrap();
+        });
+
+        let stall = stall.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(stall.idle >= Duration::from_millis(50));
+        assert_eq!(stall.queues, ["len 2, blocked producers 1"]);
+        assert_eq!(stall.threads[0].items, 2);
+        assert!(!stall.threads[0].finished);
+        let dump = stall.dump();
+        assert!(dump[0].starts_with("no progress for"));
+        assert!(dump[2].contains("producer 0"), "{dump:?}");
+
+        watchdog.disarm();
+        queue.shutdown();
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn test_disarm_does_not_fire() {
+        let progress = Progress::new(vec![String::from("consumer 0")]);
+        let watchdog = Watchdog::arm(
+            Arc::clone(&progress),
+            Duration::from_millis(200),
+            Vec::new,
+            |stall| panic!("stalled: {stall:?}"),
+        );
+        for _ in 0..5 {
+            progress.tick(0);
+            thread::sleep(Duration::from_millis(5));
+        }
+        progress.finish(0);
+        thread::sleep(Duration::from_millis(300));
+        watchdog.disarm();
+    }
+}
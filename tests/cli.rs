@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn binary() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_fifo_bounded_buffer"))
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fifo-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn csv_appends_rows_under_one_header() {
+    let path = temp_path("results.csv");
+
+    for _ in 0..2 {
+        let status = binary()
+            .args(["-p", "2", "-c", "2", "-i", "50", "--oversubscribe", "--csv"])
+            .arg(&path)
+            .status()
+            .expect("failed to run the binary");
+        assert!(status.success());
+    }
+
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 3, "{contents}");
+    assert!(lines[0].starts_with("timestamp,"));
+    for row in &lines[1..] {
+        assert!(row.contains(",2,2,50,5,fifo,0..0,0..0,"), "{row}");
+    }
+}
+
+#[test]
+fn compare_reports_every_backend() {
+    let output = binary()
+        .args(["-i", "100", "--compare", "--output", "json"])
+        .output()
+        .expect("failed to run the binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let backends: Vec<_> = stdout
+        .split("\"backend\":\"")
+        .skip(1)
+        .map(|rest| &rest[..rest.find('"').unwrap()])
+        .collect();
+    // fifo runs twice: with direct calls, then through the trait object
+    assert!(
+        backends.starts_with(&["fifo", "fifo", "std-mpsc"]),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\"queue_impl\":\"mutex\""), "{stdout}");
+    assert!(stdout.contains("\"dispatch_overhead_pct\":"), "{stdout}");
+}
+
+#[test]
+fn matrix_reports_every_combination() {
+    let path = temp_path("matrix.csv");
+    let output = binary()
+        .args(["-p", "1,2", "-s", "2,8", "-i", "50", "--oversubscribe"])
+        .args(["--output", "json", "--csv"])
+        .arg(&path)
+        .output()
+        .expect("failed to run the binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\"matrix\":["), "{stdout}");
+    assert!(stdout.contains("\"best\":"), "{stdout}");
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let rows: Vec<_> = contents.lines().skip(1).collect();
+    assert_eq!(rows.len(), 4, "{contents}");
+    for (row, point) in rows
+        .iter()
+        .zip([",1,1,50,2,", ",1,1,50,8,", ",2,1,50,2,", ",2,1,50,8,"])
+    {
+        assert!(row.contains(point), "{row}");
+    }
+}
+
+#[test]
+fn injected_panic_fails_with_a_report() {
+    let output = binary()
+        .args(["-p", "2", "-c", "2", "-i", "200", "--oversubscribe"])
+        .args(["--inject-panic", "consumer:1", "--panic-after", "3"])
+        .args(["--output", "json"])
+        .output()
+        .expect("failed to run the binary");
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\"status\":\"failed\""), "{stdout}");
+    assert!(
+        stdout.contains(
+            "\"panicked\":[{\"thread\":\"consumer 1\",\"items\":3,\"message\":\"injected panic\"}]"
+        ),
+        "{stdout}"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("consumer 1 panicked after 3 items"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn argument_errors_exit_with_usage_status() {
+    let output = binary()
+        .args(["--items", "0"])
+        .output()
+        .expect("failed to run the binary");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--items"));
+}
+
+#[test]
+fn scenarios_report_each_workload() {
+    let path = temp_path("scenarios.toml");
+    fs::write(
+        &path,
+        "[[scenario]]\nname = \"tiny\"\nproducers = 2\nconsumers = 1\nitems = 40\nsize = 3\n",
+    )
+    .unwrap();
+
+    let output = binary()
+        .args(["--output", "json", "--scenarios"])
+        .arg(&path)
+        .output()
+        .expect("failed to run the binary");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["failed"], 0);
+    assert_eq!(report["scenarios"][0]["name"], "tiny");
+    assert_eq!(report["scenarios"][0]["status"], "ok");
+    assert_eq!(report["scenarios"][0]["consumed"], 40);
+
+    fs::write(&path, "[[scenario]]\nname = \"broken\"\n").unwrap();
+    let status = binary()
+        .arg("--scenarios")
+        .arg(&path)
+        .status()
+        .expect("failed to run the binary");
+    fs::remove_file(&path).unwrap();
+    assert_eq!(status.code(), Some(2));
+}
+
+#[test]
+fn pipe_copies_stdin_in_order() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let input: String = (0..5000).map(|i| format!("line {i}\n")).collect();
+    let mut child = binary()
+        .args(["pipe", "-w", "4", "-s", "2", "--transform", "upper"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run the binary");
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = {
+        let input = input.clone();
+        std::thread::spawn(move || stdin.write_all(input.as_bytes()).unwrap())
+    };
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        input.to_uppercase()
+    );
+}
@@ -1,25 +1,38 @@
 use fifo_bounded_buffer::Queue;
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::path::Path;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+// The binary's scenario file format, so both run the same matrix
+#[path = "../src/scenario.rs"]
+mod scenario;
+
 const MAX_SLEEP_NS: u64 = 1_000_000;
 
+/// Delay generator of thread `index`, derived from `seed` the same way the
+/// binary's `--seed` does: producers first, then consumers.
+fn thread_rng(seed: u64, index: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ index as u64)
+}
+
 fn spawn_producers(
     queue: Arc<Queue<Box<usize>>>,
     num_producers: usize,
     items_per_thread: usize,
     delay: bool,
+    seed: u64,
 ) -> Vec<thread::JoinHandle<usize>> {
     (0..num_producers)
-        .map(|_| {
+        .map(|index| {
             let q = Arc::clone(&queue);
             thread::spawn(move || {
+                let mut rng = thread_rng(seed, index);
                 let mut produced = 0;
                 for i in 0..items_per_thread {
                     if delay {
-                        let sleep = rand::rng().random_range(0..MAX_SLEEP_NS);
+                        let sleep = rng.random_range(0..MAX_SLEEP_NS);
                         thread::sleep(Duration::from_nanos(sleep));
                     }
                     q.enqueue(Box::new(i));
@@ -34,16 +47,19 @@ fn spawn_producers(
 fn spawn_consumers(
     queue: Arc<Queue<Box<usize>>>,
     num_consumers: usize,
+    first_index: usize,
     delay: bool,
+    seed: u64,
 ) -> Vec<thread::JoinHandle<usize>> {
     (0..num_consumers)
-        .map(|_| {
+        .map(|index| {
             let q = Arc::clone(&queue);
             thread::spawn(move || {
+                let mut rng = thread_rng(seed, first_index + index);
                 let mut consumed = 0;
                 loop {
                     if delay {
-                        let sleep = rand::rng().random_range(0..MAX_SLEEP_NS);
+                        let sleep = rng.random_range(0..MAX_SLEEP_NS);
                         thread::sleep(Duration::from_nanos(sleep));
                     }
                     match q.dequeue() {
@@ -67,66 +83,115 @@ fn run_test(
     items: usize,
     queue_size: usize,
     delay: bool,
+    seed: u64,
 ) {
+    // Printed up front so a failing run can still be replayed
+    println!(
+        "Running: {num_producers} producers, {num_consumers} consumers, {items} items/thread, \
+         queue size {queue_size}, delay {delay}, seed {seed}"
+    );
     let queue = Arc::new(Queue::new(queue_size));
     let start = Instant::now();
 
-    let producers = spawn_producers(Arc::clone(&queue), num_producers, items, delay);
-    let consumers = spawn_consumers(Arc::clone(&queue), num_consumers, delay);
+    let producers = spawn_producers(Arc::clone(&queue), num_producers, items, delay, seed);
+    let consumers = spawn_consumers(
+        Arc::clone(&queue),
+        num_consumers,
+        num_producers,
+        delay,
+        seed,
+    );
 
     let total_produced: usize = producers
         .into_iter()
-        .map(|h| h.join().expect("Producer thread panicked"))
+        .map(|h| {
+            h.join()
+                .unwrap_or_else(|_| panic!("Producer thread panicked (seed {seed})"))
+        })
         .sum();
 
     queue.shutdown();
 
     let total_consumed: usize = consumers
         .into_iter()
-        .map(|h| h.join().expect("Consumer thread panicked"))
+        .map(|h| {
+            h.join()
+                .unwrap_or_else(|_| panic!("Consumer thread panicked (seed {seed})"))
+        })
         .sum();
 
     let elapsed = start.elapsed();
 
     assert_eq!(
         total_produced, total_consumed,
-        "Mismatch: {} != {}",
-        total_produced, total_consumed
-    );
-    assert!(queue.is_empty());
-    println!(
-        "Test completed: {} producers, {} consumers, {} items/thread, queue size {}, delay {} — Time: {:.2?}",
-        num_producers, num_consumers, items, queue_size, delay, elapsed
+        "Mismatch: {} != {} (seed {})",
+        total_produced, total_consumed, seed
     );
+    assert!(queue.is_empty(), "items left in the queue (seed {seed})");
+    println!("Test completed — Time: {elapsed:.2?}");
 }
 
 #[test]
 fn integration_scenarios() {
-    let configs = vec![
-        (2, 2, 10, 5, false),
-        (4, 4, 20, 10, true),
-        (8, 2, 15, 8, false),
-        (2, 8, 15, 8, true),
-        (3, 3, 50, 20, true),
-        (1, 1, 10, 5, false),
-        (2, 2, 50, 10, false),
-        (4, 2, 100, 20, false),
-        (2, 4, 100, 10, false),
-        (1, 8, 80, 5, false),
-        (8, 1, 10, 2, false),
-        (4, 4, 1000, 50, true),
-        (8, 8, 1000, 100, true),
-        (16, 4, 500, 10, true),
-        (4, 16, 500, 10, true),
-        (1, 1, 1000, 1, true),
-        (3, 5, 200, 3, true),
-        (5, 3, 200, 3, true),
-        (6, 6, 600, 6, true),
-        (10, 2, 150, 2, true),
-        (2, 10, 150, 2, true),
-    ];
-
-    for (p, c, items, size, delay) in configs {
-        run_test(p, c, items, size, delay);
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios.toml");
+    let scenarios = scenario::load(&path).expect("scenarios.toml should parse");
+
+    let seed = match std::env::var("FIFO_SEED") {
+        Ok(seed) => seed.parse().expect("FIFO_SEED should be a whole number"),
+        Err(_) => rand::random(),
+    };
+    println!("scenario seed: {seed} (set FIFO_SEED={seed} to reproduce)");
+    for s in scenarios {
+        assert_eq!(s.items % s.producers, 0, "{}: uneven split", s.name);
+        let items_per_thread = s.items / s.producers;
+        let seed = s.seed.unwrap_or(seed);
+        run_test(
+            s.producers,
+            s.consumers,
+            items_per_thread,
+            s.size,
+            s.delay,
+            seed,
+        );
     }
 }
+
+#[test]
+fn labeled_handles_scenario() {
+    let queue = Arc::new(Queue::new(8));
+    let items_per_producer = 200;
+
+    let producers: Vec<_> = (0..4)
+        .map(|i| {
+            let handle = queue.producer(format!("producer-{i}"));
+            thread::spawn(move || {
+                for item in 0..items_per_producer {
+                    handle.enqueue(Box::new(item));
+                }
+            })
+        })
+        .collect();
+
+    let consumers: Vec<_> = (0..3)
+        .map(|i| {
+            let handle = queue.consumer(format!("consumer-{i}"));
+            thread::spawn(move || while handle.dequeue().is_some() {})
+        })
+        .collect();
+
+    for p in producers {
+        p.join().expect("Producer thread panicked");
+    }
+    queue.shutdown();
+    for c in consumers {
+        c.join().expect("Consumer thread panicked");
+    }
+
+    let produced: u64 = queue.producer_stats().iter().map(|s| s.enqueued).sum();
+    let consumed: u64 = queue.consumer_stats().iter().map(|s| s.consumed).sum();
+
+    assert_eq!(produced, 4 * items_per_producer as u64);
+    assert_eq!(consumed, produced);
+    assert_eq!(queue.consumer_count(), 3);
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,96 @@
+//! Exhaustive interleaving checks of the queue's monitor logic.
+//!
+//! Only built under `--cfg loom`, which swaps the queue's `Mutex`, `Condvar`
+//! and atomics for `loom::sync`:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+#![cfg(loom)]
+
+use std::sync::Arc;
+
+use fifo_bounded_buffer::Queue;
+use loom::thread;
+
+#[test]
+fn two_producers_one_consumer_capacity_one() {
+    loom::model(|| {
+        let queue = Queue::new(1);
+
+        let producers: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|item| {
+                let q = Arc::clone(&queue);
+                thread::spawn(move || q.enqueue(item))
+            })
+            .collect();
+
+        let mut received = [queue.dequeue().unwrap(), queue.dequeue().unwrap()];
+        for p in producers {
+            p.join().unwrap();
+        }
+
+        received.sort_unstable();
+        assert_eq!(received, [1, 2]);
+        assert!(queue.is_empty());
+    });
+}
+
+#[test]
+fn shutdown_wakes_blocked_dequeue() {
+    loom::model(|| {
+        let queue = Queue::<u32>::new(1);
+
+        let consumer = {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || q.dequeue())
+        };
+
+        queue.shutdown();
+        assert_eq!(consumer.join().unwrap(), None);
+        assert!(queue.is_terminated());
+    });
+}
+
+#[test]
+fn shutdown_races_enqueue_without_losing_accepted_item() {
+    loom::model(|| {
+        let queue = Queue::new(1);
+
+        let producer = {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || q.enqueue_from_iter([7]).is_ok())
+        };
+        let consumer = {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || q.dequeue())
+        };
+
+        queue.shutdown();
+        let accepted = producer.join().unwrap();
+        let received = consumer.join().unwrap();
+
+        // An item accepted before shutdown is always delivered.
+        assert_eq!(received, accepted.then_some(7));
+        assert!(queue.is_terminated());
+    });
+}
+
+#[test]
+fn shutdown_wakes_blocked_enqueue() {
+    loom::model(|| {
+        let queue = Queue::new(1);
+        queue.enqueue(1);
+
+        let producer = {
+            let q = Arc::clone(&queue);
+            thread::spawn(move || q.enqueue(2))
+        };
+
+        queue.shutdown();
+        producer.join().unwrap();
+        assert_eq!(queue.dequeue(), Some(1));
+        assert_eq!(queue.dequeue(), None);
+    });
+}
@@ -0,0 +1,89 @@
+mod support;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+use fifo_bounded_buffer::Queue;
+use proptest::prelude::*;
+use support::{op_strategy, run_trace};
+
+proptest! {
+    #[test]
+    fn single_threaded_queue_matches_model(
+        capacity in 1usize..6,
+        ops in prop::collection::vec(op_strategy(), 0..64),
+    ) {
+        if let Err((trace, reason)) = run_trace(capacity, &ops) {
+            prop_assert!(false, "{reason} after {} steps: {trace:#?}", trace.len());
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn concurrent_schedule_conserves_items_and_order(
+        capacity in 1usize..4,
+        producer_items in prop::collection::vec(0u32..40, 1..3),
+        consumers in 1usize..3,
+    ) {
+        let queue = Queue::new(capacity);
+
+        let producer_threads: Vec<_> = producer_items
+            .iter()
+            .enumerate()
+            .map(|(id, &count)| {
+                let q = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for seq in 0..count {
+                        q.enqueue((id, seq));
+                    }
+                })
+            })
+            .collect();
+
+        let consumer_threads: Vec<_> = (0..consumers)
+            .map(|_| {
+                let q = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while let Some(item) = q.dequeue() {
+                        seen.push(item);
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for p in producer_threads {
+            p.join().unwrap();
+        }
+        queue.shutdown();
+        let traces: Vec<Vec<(usize, u32)>> =
+            consumer_threads.into_iter().map(|c| c.join().unwrap()).collect();
+
+        // Each consumer sees every producer's items in the order they were sent.
+        for trace in &traces {
+            let mut last: HashMap<usize, u32> = HashMap::new();
+            for &(id, seq) in trace {
+                if let Some(&prev) = last.get(&id) {
+                    prop_assert!(seq > prev, "producer {id}: {seq} after {prev}");
+                }
+                last.insert(id, seq);
+            }
+        }
+
+        // Every item is delivered exactly once.
+        let mut delivered: Vec<(usize, u32)> = traces.concat();
+        delivered.sort_unstable();
+        let expected: Vec<(usize, u32)> = producer_items
+            .iter()
+            .enumerate()
+            .flat_map(|(id, &count)| (0..count).map(move |seq| (id, seq)))
+            .collect();
+        prop_assert_eq!(delivered, expected);
+        prop_assert!(queue.is_terminated());
+    }
+}
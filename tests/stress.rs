@@ -0,0 +1,216 @@
+//! Long-running randomized stress test with hang detection.
+//!
+//! Ignored by default; run it with
+//!
+//! ```text
+//! cargo test --release --test stress -- --ignored --nocapture
+//! ```
+//!
+//! Environment variables:
+//! - `STRESS_SECS`: wall-clock duration of the run (default 10)
+//! - `STRESS_SEED`: seed to reproduce a failing run (default random)
+//! - `STRESS_STALL_SECS`: seconds without progress before failing (default 5)
+
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use fifo_bounded_buffer::Queue;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Parameters of one round, printed with every failure.
+#[derive(Debug)]
+struct Round {
+    seed: u64,
+    producers: usize,
+    consumers: usize,
+    capacity: usize,
+    fair_consumers: bool,
+    items_per_producer: u64,
+    shutdown_after: Option<Duration>,
+}
+
+impl Round {
+    fn generate(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            seed,
+            producers: rng.random_range(1..=8),
+            consumers: rng.random_range(1..=8),
+            capacity: rng.random_range(1..=32),
+            fair_consumers: rng.random_bool(0.3),
+            items_per_producer: rng.random_range(0..=2_000),
+            shutdown_after: rng
+                .random_bool(0.3)
+                .then(|| Duration::from_micros(rng.random_range(0..20_000))),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+fn spawn_producer(
+    queue: Arc<Queue<u64>>,
+    seed: u64,
+    items: u64,
+    progress: Arc<AtomicU64>,
+) -> JoinHandle<u64> {
+    thread::spawn(move || {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut accepted = 0;
+        let mut next = 0;
+        while next < items {
+            let batch = if rng.random_bool(0.5) {
+                1
+            } else {
+                rng.random_range(1..=8).min(items - next)
+            };
+            let (count, stopped) = match queue.enqueue_from_iter(next..next + batch) {
+                Ok(count) => (count as u64, false),
+                Err((count, _)) => (count as u64, true),
+            };
+            accepted += count;
+            progress.fetch_add(count, Ordering::Relaxed);
+            if stopped {
+                break;
+            }
+            next += batch;
+        }
+        accepted
+    })
+}
+
+fn spawn_consumer(queue: Arc<Queue<u64>>, seed: u64, progress: Arc<AtomicU64>) -> JoinHandle<u64> {
+    thread::spawn(move || {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut consumed = 0;
+        let mut record = |n: usize| {
+            consumed += n as u64;
+            progress.fetch_add(n as u64, Ordering::Relaxed);
+        };
+
+        if rng.random_bool(0.25) {
+            let max_items = rng.random_range(1..=8);
+            let max_wait = Duration::from_micros(rng.random_range(0..500));
+            queue.consume_batches(max_items, max_wait, |batch| record(batch.len()));
+            return consumed;
+        }
+
+        let mut buf = [0; 8];
+        loop {
+            match rng.random_range(0..3) {
+                0 => match queue.dequeue() {
+                    Some(_) => record(1),
+                    None => break,
+                },
+                1 => {
+                    let len = rng.random_range(1..=buf.len());
+                    match queue.dequeue_into(&mut buf[..len]) {
+                        0 => break,
+                        n => record(n),
+                    }
+                }
+                _ => match queue.try_dequeue_into(&mut buf) {
+                    0 if queue.is_terminated() => break,
+                    0 => thread::yield_now(),
+                    n => record(n),
+                },
+            }
+        }
+        consumed
+    })
+}
+
+/// Runs one round, acting as the watchdog until every thread has finished.
+fn run_round(round: &Round, stall_limit: Duration) {
+    let queue = Queue::builder(round.capacity)
+        .fair_consumers(round.fair_consumers)
+        .build();
+    let progress = Arc::new(AtomicU64::new(0));
+    let mut rng = StdRng::seed_from_u64(round.seed);
+
+    let producers: Vec<_> = (0..round.producers)
+        .map(|_| {
+            let q = Arc::clone(&queue);
+            spawn_producer(
+                q,
+                rng.random(),
+                round.items_per_producer,
+                Arc::clone(&progress),
+            )
+        })
+        .collect();
+    let consumers: Vec<_> = (0..round.consumers)
+        .map(|_| spawn_consumer(Arc::clone(&queue), rng.random(), Arc::clone(&progress)))
+        .collect();
+
+    let start = Instant::now();
+    let mut last_progress = (0, Instant::now());
+    loop {
+        if !queue.is_shutdown()
+            && (producers.iter().all(JoinHandle::is_finished)
+                || round
+                    .shutdown_after
+                    .is_some_and(|after| start.elapsed() >= after))
+        {
+            queue.shutdown();
+        }
+        if producers
+            .iter()
+            .chain(&consumers)
+            .all(JoinHandle::is_finished)
+        {
+            break;
+        }
+
+        let moved = progress.load(Ordering::Relaxed);
+        if moved != last_progress.0 {
+            last_progress = (moved, Instant::now());
+        } else if last_progress.1.elapsed() >= stall_limit {
+            panic!(
+                "no progress for {stall_limit:?}\n\
+                 round: {round:?}\n\
+                 queue: {queue:?}\n\
+                 shutdown: {}, blocked producers: {}, blocked consumers: {}, items moved: {moved}",
+                queue.is_shutdown(),
+                queue.blocked_producers(),
+                queue.blocked_consumers(),
+            );
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let accepted: u64 = producers.into_iter().map(|p| p.join().unwrap()).sum();
+    let consumed: u64 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+    assert_eq!(accepted, consumed, "round: {round:?}");
+    if round.shutdown_after.is_none() {
+        assert_eq!(accepted, round.producers as u64 * round.items_per_producer);
+    }
+    assert!(queue.is_terminated(), "round: {round:?}");
+}
+
+#[test]
+#[ignore = "long-running; run with --ignored"]
+fn randomized_stress_with_watchdog() {
+    let duration = Duration::from_secs(env_or("STRESS_SECS", 10));
+    let stall_limit = Duration::from_secs(env_or("STRESS_STALL_SECS", 5));
+    let seed = env_or("STRESS_SEED", rand::rng().random());
+    println!("stress seed: {seed} (set STRESS_SEED={seed} to reproduce)");
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let deadline = Instant::now() + duration;
+    let mut rounds = 0;
+    while Instant::now() < deadline {
+        run_round(&Round::generate(rng.random()), stall_limit);
+        rounds += 1;
+    }
+    println!("stress completed {rounds} rounds");
+}
@@ -0,0 +1,261 @@
+//! Reference model and trace recording shared by the property-based tests.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fifo_bounded_buffer::{MockClock, Queue};
+use proptest::prelude::*;
+
+/// A single operation applied to both the real queue and the model.
+///
+/// Blocking operations are only generated as ops that can complete on one
+/// thread; [`Model::can_apply`] filters out the ones that would block.
+#[derive(Debug, Clone)]
+pub enum Op {
+    Enqueue(u32),
+    EnqueueFromIter(Vec<u32>),
+    Dequeue,
+    DequeueInto(usize),
+    TryDequeueInto(usize),
+    /// `consume_batches` whose callback shuts the queue down after
+    /// `stop_after` batches, so it also returns before a shutdown.
+    ConsumeBatches {
+        max_items: usize,
+        max_wait: Duration,
+        stop_after: usize,
+    },
+    AdvanceClock(Duration),
+    Shutdown,
+    IsEmpty,
+    IsTerminated,
+}
+
+/// What an operation returned, compared between the queue and the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Observation {
+    Unit,
+    Enqueued(Result<usize, (usize, u32)>),
+    Item(Option<u32>),
+    Items(Vec<u32>),
+    Batches(Vec<Vec<u32>>),
+    Flag(bool),
+}
+
+pub fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => any::<u32>().prop_map(Op::Enqueue),
+        1 => prop::collection::vec(any::<u32>(), 0..6).prop_map(Op::EnqueueFromIter),
+        3 => Just(Op::Dequeue),
+        1 => (0usize..5).prop_map(Op::DequeueInto),
+        2 => (0usize..5).prop_map(Op::TryDequeueInto),
+        2 => (1usize..4, 1u64..50, 1usize..4).prop_map(|(max_items, ms, stop_after)| {
+            Op::ConsumeBatches {
+                max_items,
+                max_wait: Duration::from_millis(ms),
+                stop_after,
+            }
+        }),
+        1 => (0u64..100).prop_map(|ms| Op::AdvanceClock(Duration::from_millis(ms))),
+        1 => Just(Op::Shutdown),
+        1 => Just(Op::IsEmpty),
+        1 => Just(Op::IsTerminated),
+    ]
+}
+
+/// An ideal bounded FIFO built on a `VecDeque`, with the time its mock clock
+/// should show.
+#[derive(Debug)]
+pub struct Model {
+    capacity: usize,
+    buffer: VecDeque<u32>,
+    shutdown: bool,
+    elapsed: Duration,
+}
+
+impl Model {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            buffer: VecDeque::new(),
+            shutdown: false,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn contents(&self) -> Vec<u32> {
+        self.buffer.iter().copied().collect()
+    }
+
+    /// Returns `false` for operations that would block forever on a single thread.
+    pub fn can_apply(&self, op: &Op) -> bool {
+        let room = self.capacity - self.buffer.len();
+        match op {
+            Op::Enqueue(_) => self.shutdown || room > 0,
+            // A free slot is awaited before the iterator is polled, so even the
+            // end of the iterator is only observed while the queue has room.
+            Op::EnqueueFromIter(items) => self.shutdown || items.len() < room,
+            Op::Dequeue | Op::DequeueInto(_) => self.shutdown || !self.buffer.is_empty(),
+            // Every batch before the one that shuts down must find an item
+            Op::ConsumeBatches {
+                max_items,
+                stop_after,
+                ..
+            } => self.shutdown || self.buffer.len().div_ceil(*max_items) >= *stop_after,
+            _ => true,
+        }
+    }
+
+    pub fn apply(&mut self, op: &Op) -> Observation {
+        match op {
+            Op::Enqueue(v) => {
+                if !self.shutdown {
+                    self.buffer.push_back(*v);
+                }
+                Observation::Unit
+            }
+            Op::EnqueueFromIter(items) => {
+                if self.shutdown {
+                    return Observation::Enqueued(items.first().map_or(Ok(0), |&v| Err((0, v))));
+                }
+                self.buffer.extend(items);
+                Observation::Enqueued(Ok(items.len()))
+            }
+            Op::Dequeue => Observation::Item(self.buffer.pop_front()),
+            Op::DequeueInto(n) | Op::TryDequeueInto(n) => {
+                let take = (*n).min(self.buffer.len());
+                Observation::Items(self.buffer.drain(..take).collect())
+            }
+            Op::ConsumeBatches {
+                max_items,
+                max_wait,
+                stop_after,
+            } => {
+                let mut batches = Vec::new();
+                while !self.buffer.is_empty() {
+                    let take = (*max_items).min(self.buffer.len());
+                    // A batch that cannot fill waits out its window, unless
+                    // the queue is shut down and it is flushed at once
+                    if take < *max_items && !self.shutdown {
+                        self.elapsed += *max_wait;
+                    }
+                    batches.push(self.buffer.drain(..take).collect());
+                    if batches.len() == *stop_after {
+                        self.shutdown = true;
+                    }
+                }
+                Observation::Batches(batches)
+            }
+            Op::AdvanceClock(by) => {
+                self.elapsed += *by;
+                Observation::Unit
+            }
+            Op::Shutdown => {
+                self.shutdown = true;
+                Observation::Unit
+            }
+            Op::IsEmpty => Observation::Flag(self.buffer.is_empty()),
+            Op::IsTerminated => Observation::Flag(self.shutdown && self.buffer.is_empty()),
+        }
+    }
+}
+
+/// Applies `op` to the real queue, whose time is kept by `clock`.
+pub fn apply(queue: &Queue<u32>, clock: &MockClock, op: &Op) -> Observation {
+    match op {
+        Op::Enqueue(v) => {
+            queue.enqueue(*v);
+            Observation::Unit
+        }
+        Op::EnqueueFromIter(items) => {
+            Observation::Enqueued(queue.enqueue_from_iter(items.iter().copied()))
+        }
+        Op::Dequeue => Observation::Item(queue.dequeue()),
+        Op::DequeueInto(n) => {
+            let mut buf = vec![0; *n];
+            let written = queue.dequeue_into(&mut buf);
+            buf.truncate(written);
+            Observation::Items(buf)
+        }
+        Op::TryDequeueInto(n) => {
+            let mut buf = vec![0; *n];
+            let written = queue.try_dequeue_into(&mut buf);
+            buf.truncate(written);
+            Observation::Items(buf)
+        }
+        Op::ConsumeBatches {
+            max_items,
+            max_wait,
+            stop_after,
+        } => {
+            let mut batches = Vec::new();
+            queue.consume_batches(*max_items, *max_wait, |batch| {
+                batches.push(batch);
+                if batches.len() == *stop_after {
+                    queue.shutdown();
+                }
+            });
+            Observation::Batches(batches)
+        }
+        Op::AdvanceClock(by) => {
+            clock.advance(*by);
+            Observation::Unit
+        }
+        Op::Shutdown => {
+            queue.shutdown();
+            Observation::Unit
+        }
+        Op::IsEmpty => Observation::Flag(queue.is_empty()),
+        Op::IsTerminated => Observation::Flag(queue.is_terminated()),
+    }
+}
+
+/// One executed step as `(op, queue observation, model observation)`, kept so a
+/// failing case shows how the states diverged.
+pub type Step = (Op, Observation, Observation);
+
+/// Runs `ops` against a fresh queue and model, returning the recorded trace.
+///
+/// Stops at the first step whose observations or resulting contents differ;
+/// that step is the last entry of the trace.
+pub fn run_trace(capacity: usize, ops: &[Op]) -> Result<Vec<Step>, (Vec<Step>, String)> {
+    let clock = Arc::new(MockClock::new());
+    let queue = Queue::with_clock(capacity, clock.clone());
+    let mut model = Model::new(capacity);
+    let mut trace = Vec::new();
+
+    for op in ops {
+        if !model.can_apply(op) {
+            continue;
+        }
+        let real = apply(&queue, &clock, op);
+        let expected = model.apply(op);
+        let diverged = real != expected;
+        trace.push((op.clone(), real, expected));
+
+        if diverged {
+            return Err((trace, String::from("observations differ")));
+        }
+        if *queue != model.contents() {
+            return Err((
+                trace,
+                format!("contents differ: model has {:?}", model.contents()),
+            ));
+        }
+        if queue.item_bytes() != model.contents().len() * std::mem::size_of::<u32>() {
+            return Err((trace, String::from("item_bytes out of sync")));
+        }
+        if clock.elapsed() != model.elapsed {
+            return Err((
+                trace,
+                format!(
+                    "clock shows {:?}, model expected {:?}",
+                    clock.elapsed(),
+                    model.elapsed
+                ),
+            ));
+        }
+    }
+
+    Ok(trace)
+}